@@ -0,0 +1,47 @@
+//! Times `fib(25)` under both backends. Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use moss::parser::parse_program;
+use moss::test_util;
+
+const FIB: &str = r#"
+let fib = |n: Int|: Int => {
+    if n <= 1 {
+        n;
+    } else {
+        fib(n - 1) + fib(n - 2);
+    };
+};
+
+fib(25);
+"#;
+
+const RUNS: u32 = 5;
+
+fn time(name: &str, run: impl Fn()) {
+    let mut best = Duration::MAX;
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed());
+    }
+
+    println!("{name:<12} best of {RUNS}: {best:?}");
+}
+
+fn main() {
+    let parsed = parse_program(FIB).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    time("interpreter", || {
+        let result = test_util::exec_program(analyzed.clone()).unwrap();
+        assert_eq!(result.unwrap_int(), 75025);
+    });
+
+    time("vm", || {
+        let result = test_util::exec_program_vm(analyzed.clone()).unwrap();
+        assert_eq!(result.unwrap_int(), 75025);
+    });
+}
@@ -1,7 +1,10 @@
+use std::rc::Rc;
+
 use crate::{
     ast::{
         typed::{typed_expr::TypedExpr, TypedLiteral},
         untyped::Expr,
+        Span,
     },
     errors::type_error::TypeError,
     scopes::{scope::Scope, scope_stack::ScopeStack},
@@ -13,21 +16,42 @@ use super::{analyze_expr, scope_entry::AnalyzerScopeEntry};
 pub fn analyze_binary_op(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     expr: Expr,
 ) -> Result<TypedExpr, TypeError> {
     use Expr::*;
 
     match expr {
-        Eq(left, right) => analyze_eq(value_scope_stack, type_scope, *left, *right),
-        Gt(left, right) => analyze_gt(value_scope_stack, type_scope, *left, *right),
-        Lt(left, right) => analyze_lt(value_scope_stack, type_scope, *left, *right),
-        Gte(left, right) => analyze_gte(value_scope_stack, type_scope, *left, *right),
-        Lte(left, right) => analyze_lte(value_scope_stack, type_scope, *left, *right),
-        Add(left, right) => analyze_add(value_scope_stack, type_scope, *left, *right),
-        Sub(left, right) => analyze_sub(value_scope_stack, type_scope, *left, *right),
-        Mult(left, right) => analyze_mult(value_scope_stack, type_scope, *left, *right),
-        Div(left, right) => analyze_div(value_scope_stack, type_scope, *left, *right),
-        Modulo(left, right) => analyze_modulo(value_scope_stack, type_scope, *left, *right),
+        Eq(left, right, span) => {
+            analyze_eq(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Gt(left, right, span) => {
+            analyze_gt(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Lt(left, right, span) => {
+            analyze_lt(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Gte(left, right, span) => {
+            analyze_gte(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Lte(left, right, span) => {
+            analyze_lte(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Add(left, right, span) => {
+            analyze_add(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Sub(left, right, span) => {
+            analyze_sub(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Mult(left, right, span) => {
+            analyze_mult(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Div(left, right, span) => {
+            analyze_div(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
+        Modulo(left, right, span) => {
+            analyze_modulo(value_scope_stack, type_scope, errors, *left, *right, span)
+        }
         _ => unimplemented!(),
     }
 }
@@ -35,37 +59,66 @@ pub fn analyze_binary_op(
 fn analyze_eq(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Eq(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Bool,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "==".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
-    Ok(TypedExpr::Eq(Box::new(left), Box::new(right), Type::Bool))
+    Ok(TypedExpr::Eq(
+        Rc::new(left),
+        Rc::new(right),
+        Type::Bool,
+        span,
+    ))
 }
 
 fn analyze_gt(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Gt(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Bool,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             ">".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -75,26 +128,44 @@ fn analyze_gt(
             ">".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
-    Ok(TypedExpr::Gt(Box::new(left), Box::new(right), Type::Bool))
+    Ok(TypedExpr::Gt(
+        Rc::new(left),
+        Rc::new(right),
+        Type::Bool,
+        span,
+    ))
 }
 
 fn analyze_gte(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Gte(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Bool,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             ">=".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -104,26 +175,44 @@ fn analyze_gte(
             ">=".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
-    Ok(TypedExpr::Gte(Box::new(left), Box::new(right), Type::Bool))
+    Ok(TypedExpr::Gte(
+        Rc::new(left),
+        Rc::new(right),
+        Type::Bool,
+        span,
+    ))
 }
 
 fn analyze_lt(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Lt(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Bool,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "<".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -133,26 +222,44 @@ fn analyze_lt(
             "<".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
-    Ok(TypedExpr::Lt(Box::new(left), Box::new(right), Type::Bool))
+    Ok(TypedExpr::Lt(
+        Rc::new(left),
+        Rc::new(right),
+        Type::Bool,
+        span,
+    ))
 }
 
 fn analyze_lte(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Lte(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Bool,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "<=".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -162,26 +269,44 @@ fn analyze_lte(
             "<=".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
-    Ok(TypedExpr::Lte(Box::new(left), Box::new(right), Type::Bool))
+    Ok(TypedExpr::Lte(
+        Rc::new(left),
+        Rc::new(right),
+        Type::Bool,
+        span,
+    ))
 }
 
 fn analyze_add(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Add(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Unknown,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "+".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -190,27 +315,40 @@ fn analyze_add(
             "+".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
     let ty = left.ty();
-    Ok(TypedExpr::Add(Box::new(left), Box::new(right), ty))
+    Ok(TypedExpr::Add(Rc::new(left), Rc::new(right), ty, span))
 }
 
 fn analyze_sub(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Sub(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Unknown,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "-".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -219,27 +357,40 @@ fn analyze_sub(
             "-".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
     let ty = left.ty();
-    Ok(TypedExpr::Sub(Box::new(left), Box::new(right), ty))
+    Ok(TypedExpr::Sub(Rc::new(left), Rc::new(right), ty, span))
 }
 
 fn analyze_mult(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Mult(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Unknown,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "*".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -248,27 +399,40 @@ fn analyze_mult(
             "*".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
     let ty = left.ty();
-    Ok(TypedExpr::Mult(Box::new(left), Box::new(right), ty))
+    Ok(TypedExpr::Mult(Rc::new(left), Rc::new(right), ty, span))
 }
 
 fn analyze_div(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Div(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Unknown,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "/".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -277,31 +441,44 @@ fn analyze_div(
             "/".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
-    if let TypedExpr::Literal(TypedLiteral::Int(0), _) = right {
-        return Err(TypeError::DivisionZero);
+    if let TypedExpr::Literal(TypedLiteral::Int(0), _, _) = right {
+        return Err(TypeError::DivisionZero(span));
     }
 
     let ty = left.ty();
-    Ok(TypedExpr::Div(Box::new(left), Box::new(right), ty))
+    Ok(TypedExpr::Div(Rc::new(left), Rc::new(right), ty, span))
 }
 
 fn analyze_modulo(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     left: Expr,
     right: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let left = analyze_expr(value_scope_stack, type_scope, &None, left)?;
-    let right = analyze_expr(value_scope_stack, type_scope, &None, right)?;
+    let left = analyze_expr(value_scope_stack, type_scope, errors, &None, left);
+    let right = analyze_expr(value_scope_stack, type_scope, errors, &None, right);
+
+    if has_unknown(&left, &right) {
+        return Ok(TypedExpr::Modulo(
+            Rc::new(left),
+            Rc::new(right),
+            Type::Unknown,
+            span,
+        ));
+    }
 
     if left.ty() != right.ty() {
         return Err(TypeError::BinaryOpWrongTypes(
             "%".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
@@ -310,13 +487,20 @@ fn analyze_modulo(
             "%".to_string(),
             left.ty(),
             right.ty(),
+            span,
         ));
     }
 
-    if let TypedExpr::Literal(TypedLiteral::Int(0), _) = right {
-        return Err(TypeError::DivisionZero);
+    if let TypedExpr::Literal(TypedLiteral::Int(0), _, _) = right {
+        return Err(TypeError::DivisionZero(span));
     }
 
     let ty = left.ty();
-    Ok(TypedExpr::Modulo(Box::new(left), Box::new(right), ty))
+    Ok(TypedExpr::Modulo(Rc::new(left), Rc::new(right), ty, span))
+}
+
+// Operands which already failed analysis have already been reported, so further checks on them
+// would only produce duplicate errors.
+fn has_unknown(left: &TypedExpr, right: &TypedExpr) -> bool {
+    left.ty() == Type::Unknown || right.ty() == Type::Unknown
 }
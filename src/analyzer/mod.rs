@@ -1,5 +1,8 @@
 mod binary_ops;
 mod scope_entry;
+mod tail_calls;
+
+use std::rc::Rc;
 
 use crate::ast::typed::typed_block::TypedBlock;
 use crate::ast::typed::typed_expr::TypedExpr;
@@ -8,21 +11,125 @@ use crate::ast::untyped::{Expr, FuncCall, FuncDeclare, Literal, Stmt};
 use crate::ast::Span;
 use crate::errors::type_error::TypeError;
 use crate::scopes::scope::Scope;
-use crate::scopes::scope_stack::ScopeStack;
+use crate::scopes::scope_stack::{ScopeMark, ScopeStack, Slot};
 use crate::typing::{ProtoType, Type, TypeBinding};
 
 use binary_ops::analyze_binary_op;
 use scope_entry::AnalyzerScopeEntry;
+use tail_calls::mark_tail_calls;
 
+/// Analyzes a whole program, collecting every type error instead of stopping at the first one.
+/// Sub-expressions that fail to type check are replaced with `TypedExpr::Unknown`, and any checks
+/// involving `Type::Unknown` are skipped so that a single mistake isn't reported more than once.
 pub fn analyze_program(
     stmts: Expr,
     builtin_funcs: Vec<(String, TypedExpr)>,
     builtin_types: Vec<(String, TypeBinding)>,
-) -> Result<TypedExpr, TypeError> {
+) -> Result<TypedExpr, Vec<TypeError>> {
+    let (analyzed, errors) = analyze_program_with_errors(stmts, builtin_funcs, builtin_types);
+
+    if errors.is_empty() {
+        Ok(analyzed)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Analyzes a whole program the same way, but keeps what was analyzed alongside any errors, for
+/// tools which work on programs while they're still being written.
+pub fn analyze_program_with_errors(
+    stmts: Expr,
+    builtin_funcs: Vec<(String, TypedExpr)>,
+    builtin_types: Vec<(String, TypeBinding)>,
+) -> (TypedExpr, Vec<TypeError>) {
+    let span = stmts.span();
+
+    let (mut value_scope_stack, mut type_scope) = match builtin_scopes(builtin_funcs, builtin_types)
+    {
+        Ok(scopes) => scopes,
+        Err(errors) => return (TypedExpr::Unknown(span), errors),
+    };
+
+    let mut errors = Vec::new();
+    let analyzed = analyze_block(&mut value_scope_stack, &mut type_scope, &mut errors, stmts);
+
+    (analyzed, errors)
+}
+
+/// The analyzer's scopes, kept between inputs at the REPL so each input can use whatever earlier
+/// ones declared.
+pub struct Session {
+    value_scope_stack: ScopeStack<AnalyzerScopeEntry>,
+    type_scope: Scope<TypeBinding>,
+}
+
+impl Session {
+    pub fn new(
+        builtin_funcs: Vec<(String, TypedExpr)>,
+        builtin_types: Vec<(String, TypeBinding)>,
+    ) -> Result<Self, Vec<TypeError>> {
+        let (mut value_scope_stack, type_scope) = builtin_scopes(builtin_funcs, builtin_types)?;
+
+        // Inputs share a scope of their own above the builtins, as a program's statements would.
+        value_scope_stack.push_scope();
+
+        Ok(Self {
+            value_scope_stack,
+            type_scope,
+        })
+    }
+
+    /// Analyzes an input's statements directly in the session's scope, rather than in a block of
+    /// their own, so any bindings they declare stay in scope. Nothing is kept from an input with
+    /// errors.
+    pub fn analyze(&mut self, stmts: Expr) -> Result<TypedExpr, Vec<TypeError>> {
+        let mark = self.value_scope_stack.mark();
+
+        let Expr::Block(stmts, span) = stmts else {
+            unreachable!();
+        };
+
+        let mut errors = Vec::new();
+        let stmts = analyze_stmts(
+            &mut self.value_scope_stack,
+            &mut self.type_scope,
+            &mut errors,
+            stmts,
+        );
+
+        if !errors.is_empty() {
+            self.value_scope_stack.unwind_to(mark);
+            return Err(errors);
+        }
+
+        let ty = block_type(&stmts);
+
+        Ok(TypedExpr::Block(
+            TypedBlock::Interpreted(stmts.into(), ty),
+            span,
+        ))
+    }
+
+    pub fn mark(&self) -> ScopeMark {
+        self.value_scope_stack.mark()
+    }
+
+    /// Forgets everything declared since the mark, such as by an input which failed while running.
+    pub fn unwind_to(&mut self, mark: ScopeMark) {
+        self.value_scope_stack.unwind_to(mark);
+    }
+}
+
+fn builtin_scopes(
+    builtin_funcs: Vec<(String, TypedExpr)>,
+    builtin_types: Vec<(String, TypeBinding)>,
+) -> Result<(ScopeStack<AnalyzerScopeEntry>, Scope<TypeBinding>), Vec<TypeError>> {
     let mut value_scope_stack = ScopeStack::<AnalyzerScopeEntry>::new();
 
     for (ident, binding) in builtin_funcs {
-        value_scope_stack.insert(ident, false, binding.ty())?;
+        value_scope_stack
+            .insert(ident, false, binding.ty(), Span::default())
+            .map_err(|e| vec![e])?;
     }
 
     let mut type_scope = Scope::new();
@@ -31,83 +138,142 @@ pub fn analyze_program(
         type_scope.insert(ident, binding);
     }
 
-    analyze_block(&mut value_scope_stack, &mut type_scope, stmts)
+    Ok((value_scope_stack, type_scope))
 }
 
 fn analyze_stmts(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     stmts: Vec<Stmt>,
-) -> Result<Vec<TypedStmt>, TypeError> {
+) -> Vec<TypedStmt> {
     stmts
         .into_iter()
-        .map(|stmt| analyze_stmt(value_scope_stack, type_scope, stmt))
+        .map(|stmt| analyze_stmt(value_scope_stack, type_scope, errors, stmt))
         .collect()
 }
 
 fn analyze_stmt(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     stmt: Stmt,
-) -> Result<TypedStmt, TypeError> {
-    let expr = analyze_expr(value_scope_stack, type_scope, &None, stmt.expr)?;
+) -> TypedStmt {
+    let expr = analyze_expr(value_scope_stack, type_scope, errors, &None, stmt.expr);
 
-    Ok(TypedStmt { expr })
+    TypedStmt { expr }
 }
 
+// This is the recovery point for the analyzer. Any error returned while analyzing an expression is
+// recorded, and the expression is replaced with `TypedExpr::Unknown` so analysis can carry on.
 fn analyze_expr(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     type_hint: &Option<Type>,
     expr: Expr,
-) -> Result<TypedExpr, TypeError> {
+) -> TypedExpr {
     use Expr::*;
 
-    match expr {
-        Block(stmts, span) => {
-            analyze_block(value_scope_stack, type_scope, Expr::Block(stmts, span))
-        }
-        Literal(literal) => analyze_literal(literal),
-        Identifier(ident) => analyze_identifier(value_scope_stack, ident),
+    let span = expr.span();
+
+    let result = match expr {
+        Block(stmts, span) => Ok(analyze_block(
+            value_scope_stack,
+            type_scope,
+            errors,
+            Expr::Block(stmts, span),
+        )),
+        Literal(literal, span) => Ok(analyze_literal(literal, span)),
+        Identifier(ident, span) => analyze_identifier(value_scope_stack, ident, span),
 
         // TODO: `analyze_binary_op` is an experiment, let's try this out for a while and see how it
         // goes. I have concerns with this approach because it moves the non-exhaustive match error
         // to runtime.
         // Binary ops
         Eq(..) | Gt(..) | Lt(..) | Gte(..) | Lte(..) | Add(..) | Sub(..) | Mult(..) | Div(..)
-        | Modulo(..) => analyze_binary_op(value_scope_stack, type_scope, expr),
+        | Modulo(..) => analyze_binary_op(value_scope_stack, type_scope, errors, expr),
 
         // Unary ops
-        Negate(inner) => analyze_negate(value_scope_stack, type_scope, *inner),
-        Assignment { ident, expr } => {
-            analyze_assignment(value_scope_stack, type_scope, ident, *expr)
+        Negate(inner, span) => analyze_negate(value_scope_stack, type_scope, errors, *inner, span),
+        Assignment { ident, expr, span } => {
+            analyze_assignment(value_scope_stack, type_scope, errors, ident, *expr, span)
         }
         Declaration {
             ident,
             type_annotation,
             expr,
             is_mutable,
-        } => analyze_declaration(
+            span,
+        } => Ok(analyze_declaration(
             value_scope_stack,
             type_scope,
+            errors,
             ident,
             type_annotation,
             is_mutable,
             *expr,
-        ),
+            span,
+        )),
 
-        FuncDeclare(func) => analyze_func_declare(value_scope_stack, type_scope, func),
-        FuncCall(call, span) => analyze_func_call(value_scope_stack, type_scope, call, span),
+        FuncDeclare(func, span) => Ok(analyze_func_declare(
+            value_scope_stack,
+            type_scope,
+            errors,
+            func,
+            span,
+        )),
+        FuncCall(call, span) => {
+            analyze_func_call(value_scope_stack, type_scope, errors, call, span)
+        }
 
         // Control flow
-        If(expr, then) => analyze_if(value_scope_stack, type_scope, *expr, *then),
-        IfElse(expr, then, els) => {
-            analyze_if_else(value_scope_stack, type_scope, *expr, *then, *els)
-        }
-        Loop(block) => analyze_loop(value_scope_stack, type_scope, *block),
-        Break => analyze_break(value_scope_stack),
-        List(values) => analyze_list(value_scope_stack, type_scope, type_hint, values),
-    }
+        If(expr, then, span) => Ok(analyze_if(
+            value_scope_stack,
+            type_scope,
+            errors,
+            *expr,
+            *then,
+            span,
+        )),
+        IfElse(expr, then, els, span) => Ok(analyze_if_else(
+            value_scope_stack,
+            type_scope,
+            errors,
+            *expr,
+            *then,
+            *els,
+            span,
+        )),
+        Loop(block, span) => Ok(analyze_loop(
+            value_scope_stack,
+            type_scope,
+            errors,
+            *block,
+            span,
+        )),
+        Break(span) => Ok(analyze_break(value_scope_stack, span)),
+        List(values, span) => analyze_list(
+            value_scope_stack,
+            type_scope,
+            errors,
+            type_hint,
+            values,
+            span,
+        ),
+        Test(_, block, span) => Ok(analyze_test(
+            value_scope_stack,
+            type_scope,
+            errors,
+            *block,
+            span,
+        )),
+    };
+
+    result.unwrap_or_else(|error| {
+        errors.push(error);
+        TypedExpr::Unknown(span)
+    })
 }
 
 // Unary operations
@@ -115,129 +281,208 @@ fn analyze_expr(
 fn analyze_negate(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     inner: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let inner = analyze_expr(value_scope_stack, type_scope, &None, inner)?;
+    let inner = analyze_expr(value_scope_stack, type_scope, errors, &None, inner);
 
-    if inner.ty() != Type::Int && inner.ty() != Type::Float {
-        return Err(TypeError::UnaryOpWrongType("-".to_string(), inner.ty()));
+    if inner.ty() != Type::Int && inner.ty() != Type::Float && inner.ty() != Type::Unknown {
+        return Err(TypeError::UnaryOpWrongType(
+            "-".to_string(),
+            inner.ty(),
+            span,
+        ));
     }
 
     let ty = inner.ty();
-    Ok(TypedExpr::Negate(Box::new(inner), ty))
+    Ok(TypedExpr::Negate(Rc::new(inner), ty, span))
 }
 
 fn analyze_assignment(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     ident: String,
     expr: Expr,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    if !value_scope_stack.lookup(&ident)?.is_mutable {
-        return Err(TypeError::AssignImmutable(ident));
+    // The value is analyzed first so that errors inside it are still reported when the binding
+    // itself is invalid.
+    let expr = analyze_expr(value_scope_stack, type_scope, errors, &None, expr);
+
+    if !value_scope_stack.lookup(&ident, span)?.is_mutable {
+        return Err(TypeError::AssignImmutable(ident, span));
     }
 
-    let expr = analyze_expr(value_scope_stack, type_scope, &None, expr)?;
+    let slot = value_scope_stack.resolve(&ident);
 
     Ok(TypedExpr::Assignment {
         ident,
-        expr: Box::new(expr),
+        slot,
+        expr: Rc::new(expr),
+        span,
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn analyze_declaration(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     ident: String,
     type_annotation: Option<ProtoType>,
     is_mutable: bool,
     value: Expr,
-) -> Result<TypedExpr, TypeError> {
+    span: Span,
+) -> TypedExpr {
     // TODO: There's a lot of code duplication between these two. They're separate now because in the
     // case of type checking function declarations, the function has to be bound to scope prior to
     // analyzing the funciton body, to allow for recursion. In all other cases, the value expression
     // is analyzed before binding the identifier.
     if value.is_func_declare() {
-        analyze_func_declaration(value_scope_stack, type_scope, ident, is_mutable, value)
+        analyze_func_declaration(
+            value_scope_stack,
+            type_scope,
+            errors,
+            ident,
+            is_mutable,
+            value,
+            span,
+        )
     } else {
         analyze_non_func_declaration(
             value_scope_stack,
             type_scope,
+            errors,
             ident,
             is_mutable,
             type_annotation,
             value,
+            span,
         )
     }
 }
 
+// Declarations always bind their identifier, even when they contain errors, so that later uses of
+// the binding don't produce a cascade of "not found" errors.
+#[allow(clippy::too_many_arguments)]
 fn analyze_non_func_declaration(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     ident: String,
     is_mutable: bool,
     type_annotation: Option<ProtoType>,
     value: Expr,
-) -> Result<TypedExpr, TypeError> {
-    let type_annotation = type_annotation
-        .map(|a| analyze_proto_type(type_scope, a))
-        .transpose()?;
-
-    let value = analyze_expr(value_scope_stack, type_scope, &type_annotation, value)?;
+    span: Span,
+) -> TypedExpr {
+    let type_annotation = type_annotation.map(|a| analyze_proto_type(type_scope, errors, a));
+
+    let value = analyze_expr(
+        value_scope_stack,
+        type_scope,
+        errors,
+        &type_annotation,
+        value,
+    );
     let value_type = value.ty();
 
-    if value_type == Type::Void {
-        return Err(TypeError::AssignVoid);
-    }
-
-    if let Some(annotation) = type_annotation {
-        if value_type != annotation {
-            return Err(TypeError::AssignWrongType(annotation, value_type));
+    let binding_type = if value_type == Type::Void {
+        errors.push(TypeError::AssignVoid(span));
+        Type::Unknown
+    } else {
+        match type_annotation {
+            Some(annotation)
+                if value_type != annotation
+                    && value_type != Type::Unknown
+                    && annotation != Type::Unknown =>
+            {
+                errors.push(TypeError::AssignWrongType(
+                    annotation.clone(),
+                    value_type,
+                    span,
+                ));
+                annotation
+            }
+            Some(Type::Unknown) | None => value_type,
+            Some(annotation) => annotation,
         }
-    }
+    };
 
-    value_scope_stack.insert(ident.clone(), is_mutable, value_type)?;
+    let slot = value_scope_stack
+        .insert(ident.clone(), is_mutable, binding_type, span)
+        .unwrap_or_else(|error| {
+            errors.push(error);
+            Slot::default()
+        });
 
-    Ok(TypedExpr::Declaration {
+    TypedExpr::Declaration {
         ident,
+        slot,
         is_mutable,
-        expr: Box::new(value),
+        expr: Rc::new(value),
         ty: Type::Void,
-    })
+        span,
+    }
 }
 
 fn analyze_func_declaration(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     ident: String,
     is_mutable: bool,
     value: Expr,
-) -> Result<TypedExpr, TypeError> {
+    span: Span,
+) -> TypedExpr {
     let func = value.as_func_declare();
 
-    let return_type = analyze_proto_type(type_scope, *func.return_type.clone())?;
+    // The signature is resolved again when the function itself is analyzed below, so any errors
+    // found here would be reported twice.
+    let mut signature_errors = Vec::new();
+
+    let return_type =
+        analyze_proto_type(type_scope, &mut signature_errors, *func.return_type.clone());
     let mut type_args: Vec<_> = func
         .params
         .iter()
-        .map(|(_, proto)| analyze_proto_type(type_scope, proto.clone()))
-        .collect::<Result<_, _>>()?;
+        .map(|(_, proto)| analyze_proto_type(type_scope, &mut signature_errors, proto.clone()))
+        .collect();
 
     type_args.push(return_type);
 
-    value_scope_stack.insert(ident.clone(), is_mutable, Type::Func(type_args))?;
-
-    let value = analyze_expr(value_scope_stack, type_scope, &None, value)?;
+    let slot = value_scope_stack
+        .insert(ident.clone(), is_mutable, Type::Func(type_args), span)
+        .unwrap_or_else(|error| {
+            errors.push(error);
+            Slot::default()
+        });
+
+    let value = match analyze_expr(value_scope_stack, type_scope, errors, &None, value) {
+        TypedExpr::FuncDeclare(func, ty, span) => {
+            let func = TypedFunc {
+                name: Some(ident.clone()),
+                ..func
+            };
+
+            TypedExpr::FuncDeclare(func, ty, span)
+        }
+        value => value,
+    };
 
     if value.ty() == Type::Void {
-        return Err(TypeError::AssignVoid);
+        errors.push(TypeError::AssignVoid(span));
     }
 
-    Ok(TypedExpr::Declaration {
+    TypedExpr::Declaration {
         ident,
+        slot,
         is_mutable,
-        expr: Box::new(value),
+        expr: Rc::new(value),
         ty: Type::Void,
-    })
+        span,
+    }
 }
 
 // Postfix operations
@@ -245,234 +490,297 @@ fn analyze_func_declaration(
 fn analyze_func_call(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     call: FuncCall,
     span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let callee = analyze_expr(value_scope_stack, type_scope, &None, *call.func)?;
+    let callee = analyze_expr(value_scope_stack, type_scope, errors, &None, *call.func);
 
     let args = call
         .args
         .into_iter()
-        .map(|arg| analyze_expr(value_scope_stack, type_scope, &None, arg))
-        .collect::<Result<Vec<_>, _>>()?;
-
-    if let Type::Func(inner_types) = callee.ty() {
-        let mut inner_types = inner_types.clone();
+        .map(|arg| analyze_expr(value_scope_stack, type_scope, errors, &None, arg))
+        .collect::<Vec<_>>();
+
+    match callee.ty() {
+        Type::Func(inner_types) => {
+            let mut inner_types = inner_types.clone();
+
+            let return_type = inner_types.pop().unwrap();
+            let param_types = inner_types;
+
+            let matches_signature = param_types.len() == args.len()
+                && param_types.iter().zip(&args).all(|(param_type, arg)| {
+                    arg.ty() == *param_type
+                        || *param_type == Type::Any
+                        || arg.ty() == Type::Unknown
+                        || *param_type == Type::Unknown
+                });
+
+            if !matches_signature {
+                errors.push(TypeError::InvokeWrongSignature(
+                    param_types,
+                    args.clone(),
+                    span,
+                ));
+            }
 
-        let return_type = inner_types.pop().unwrap();
-        let param_types = inner_types;
+            let func_call = TypedFuncCall {
+                func_expr: Rc::new(callee),
+                args: args.into(),
+                is_tail: false,
+            };
 
-        if param_types.len() != args.len() {
-            return Err(TypeError::InvokeWrongSignature(param_types, args, span));
+            Ok(TypedExpr::FuncCall(func_call, return_type, span))
         }
-
-        for (param_type, arg) in param_types.clone().into_iter().zip(args.clone()) {
-            if arg.ty() != param_type && param_type != Type::Any {
-                return Err(TypeError::InvokeWrongSignature(param_types, args, span));
-            }
+        Type::Unknown => {
+            let func_call = TypedFuncCall {
+                func_expr: Rc::new(callee),
+                args: args.into(),
+                is_tail: false,
+            };
+
+            Ok(TypedExpr::FuncCall(func_call, Type::Unknown, span))
         }
-
-        let func_call = TypedFuncCall {
-            func_expr: Box::new(callee),
-            args,
-        };
-
-        Ok(TypedExpr::FuncCall(func_call, return_type))
-    } else {
-        return Err(TypeError::InvokeNonFunc(callee.ty()));
+        ty => Err(TypeError::InvokeNonFunc(ty, span)),
     }
 }
 
 // Primaries
 
-fn analyze_literal(literal: Literal) -> Result<TypedExpr, TypeError> {
-    Ok(match literal {
-        Literal::Int(i) => TypedExpr::Literal(TypedLiteral::Int(i), Type::Int),
-        Literal::Float(f) => TypedExpr::Literal(TypedLiteral::Float(f), Type::Float),
-        Literal::String(s) => TypedExpr::Literal(TypedLiteral::String(s), Type::Str),
-        Literal::Bool(b) => TypedExpr::Literal(TypedLiteral::Bool(b), Type::Bool),
-    })
+fn analyze_literal(literal: Literal, span: Span) -> TypedExpr {
+    match literal {
+        Literal::Int(i) => TypedExpr::Literal(TypedLiteral::Int(i), Type::Int, span),
+        Literal::Float(f) => TypedExpr::Literal(TypedLiteral::Float(f), Type::Float, span),
+        Literal::String(s) => TypedExpr::Literal(TypedLiteral::String(s), Type::Str, span),
+        Literal::Bool(b) => TypedExpr::Literal(TypedLiteral::Bool(b), Type::Bool, span),
+    }
 }
 
 // TODO: Should this resolve the identifier from the scope and error of it's just a Type?
 fn analyze_identifier(
     scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     ident: String,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
-    let ty = scope_stack.lookup(&ident)?.value.clone();
-    Ok(TypedExpr::Identifier(ident, ty.clone()))
+    let ty = scope_stack.lookup(&ident, span)?.value.clone();
+    let slot = scope_stack.resolve(&ident);
+
+    Ok(TypedExpr::Identifier(ident, slot, ty, span))
 }
 
 fn analyze_func_declare(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     func: FuncDeclare,
-) -> Result<TypedExpr, TypeError> {
-    if func.is_closure {
-        value_scope_stack.push_scope();
-    } else {
-        value_scope_stack.create_new_stack();
-    }
+    span: Span,
+) -> TypedExpr {
+    let params: Vec<(String, Type)> = func
+        .params
+        .iter()
+        .map(|(ident, ty)| {
+            let ty = analyze_proto_type(type_scope, errors, ty.clone());
+            (ident.clone(), ty)
+        })
+        .collect();
 
-    for param in &func.params {
-        let ident = param.0.clone();
-        let ty = analyze_proto_type(type_scope, param.1.clone())?;
-        value_scope_stack.insert(ident, false, ty)?;
+    value_scope_stack.push_frame(func.is_closure);
+
+    for (ident, ty) in &params {
+        if let Err(error) = value_scope_stack.insert(ident.clone(), false, ty.clone(), span) {
+            errors.push(error);
+        }
     }
 
     let block = *func.block;
 
-    let span = if let Expr::Block(_, span) = &block {
-        span.clone()
-    } else {
-        todo!();
-    };
-
-    let block = analyze_block(value_scope_stack, type_scope, block)?;
+    let block_span = block.span();
 
-    if func.is_closure {
-        value_scope_stack.pop_scope();
-    } else {
-        value_scope_stack.restore_previous_stack();
-    }
+    let block = analyze_block(value_scope_stack, type_scope, errors, block);
 
-    let params: Vec<(String, Type)> = func
-        .params
-        .iter()
-        .map(|(ident, ty)| {
-            let ty = analyze_proto_type(type_scope, ty.clone())?;
-            Ok((ident.clone(), ty))
-        })
-        .collect::<Result<_, TypeError>>()?;
+    value_scope_stack.pop_frame();
 
-    let declared_return_type = analyze_proto_type(type_scope, *func.return_type)?;
+    let declared_return_type = analyze_proto_type(type_scope, errors, *func.return_type);
     let actual_return_type = block.ty();
 
-    if declared_return_type != actual_return_type {
-        return Err(TypeError::FuncWrongReturnType(
-            declared_return_type,
+    if declared_return_type != actual_return_type
+        && declared_return_type != Type::Unknown
+        && actual_return_type != Type::Unknown
+    {
+        errors.push(TypeError::FuncWrongReturnType(
+            declared_return_type.clone(),
             actual_return_type,
-            span,
+            block_span,
         ));
     };
 
     let func = TypedFunc {
+        name: None,
+        span,
         params: params.clone(),
-        block: Box::new(block),
+        block: Rc::new(mark_tail_calls(block)),
         is_closure: func.is_closure,
     };
 
     let mut inner_types: Vec<Type> = params.into_iter().map(|p| p.1).collect();
     inner_types.push(declared_return_type);
 
-    Ok(TypedExpr::FuncDeclare(func, Type::Func(inner_types)))
+    TypedExpr::FuncDeclare(func, Type::Func(inner_types), span)
 }
 
 fn analyze_if(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     cond: Expr,
     then_block: Expr,
-) -> Result<TypedExpr, TypeError> {
-    let cond = analyze_expr(value_scope_stack, type_scope, &None, cond)?;
+    span: Span,
+) -> TypedExpr {
+    let cond = analyze_expr(value_scope_stack, type_scope, errors, &None, cond);
 
-    if cond.ty() != Type::Bool {
-        return Err(TypeError::IfElseConditionNonBool(cond.ty()));
+    if cond.ty() != Type::Bool && cond.ty() != Type::Unknown {
+        errors.push(TypeError::IfElseConditionNonBool(cond.ty(), cond.span()));
     }
 
-    let then_block = analyze_block(value_scope_stack, type_scope, then_block)?;
+    let then_block = analyze_block(value_scope_stack, type_scope, errors, then_block);
 
     let ty = then_block.ty();
 
-    Ok(TypedExpr::If(Box::new(cond), Box::new(then_block), ty))
+    TypedExpr::If(Rc::new(cond), Rc::new(then_block), ty, span)
 }
 
 fn analyze_if_else(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     cond: Expr,
     then_block: Expr,
     else_expr: Expr,
-) -> Result<TypedExpr, TypeError> {
-    let cond = analyze_expr(value_scope_stack, type_scope, &None, cond)?;
+    span: Span,
+) -> TypedExpr {
+    let cond = analyze_expr(value_scope_stack, type_scope, errors, &None, cond);
 
-    if cond.ty() != Type::Bool {
-        return Err(TypeError::IfElseConditionNonBool(cond.ty()));
+    if cond.ty() != Type::Bool && cond.ty() != Type::Unknown {
+        errors.push(TypeError::IfElseConditionNonBool(cond.ty(), cond.span()));
     }
 
-    let then_block = analyze_block(value_scope_stack, type_scope, then_block)?;
-    let else_expr = analyze_expr(value_scope_stack, type_scope, &None, else_expr)?;
+    let then_block = analyze_block(value_scope_stack, type_scope, errors, then_block);
+    let else_expr = analyze_expr(value_scope_stack, type_scope, errors, &None, else_expr);
 
-    if then_block.ty() != else_expr.ty() {
-        return Err(TypeError::IfElseBlockTypeMismatch(
+    let ty = if then_block.ty() == Type::Unknown || else_expr.ty() == Type::Unknown {
+        Type::Unknown
+    } else if then_block.ty() != else_expr.ty() {
+        errors.push(TypeError::IfElseBlockTypeMismatch(
             then_block.ty(),
             else_expr.ty(),
+            else_expr.span(),
         ));
-    }
 
-    let ty = then_block.ty();
+        Type::Unknown
+    } else {
+        then_block.ty()
+    };
 
-    Ok(TypedExpr::IfElse(
-        Box::new(cond),
-        Box::new(then_block),
-        Box::new(else_expr),
+    TypedExpr::IfElse(
+        Rc::new(cond),
+        Rc::new(then_block),
+        Rc::new(else_expr),
         ty,
-    ))
+        span,
+    )
 }
 
 fn analyze_loop(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     block: Expr,
-) -> Result<TypedExpr, TypeError> {
+    span: Span,
+) -> TypedExpr {
     // TODO: Currently just using this as a wrapper to analyze the block, there may be more we can
     // do here later though.
-    let block = analyze_block(value_scope_stack, type_scope, block.clone())?;
-    Ok(TypedExpr::Loop(Box::new(block)))
+    let block = analyze_block(value_scope_stack, type_scope, errors, block);
+    TypedExpr::Loop(Rc::new(block), span)
 }
 
-fn analyze_break(
-    _scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
-) -> Result<TypedExpr, TypeError> {
+fn analyze_break(_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>, span: Span) -> TypedExpr {
     // TODO: Check if break is used in the right context. This might be a new type of error like
     // ContextError or it might make sense to treat as a TypeError.
 
-    Ok(TypedExpr::Break)
+    TypedExpr::Break(span)
+}
+
+// Tests are only run by the test runner, which analyzes each one again on its own. Anywhere else,
+// they become a branch that's never taken, so they're still checked and linted like any other block.
+fn analyze_test(
+    value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
+    type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
+    block: Expr,
+    span: Span,
+) -> TypedExpr {
+    let never = TypedExpr::Literal(TypedLiteral::Bool(false), Type::Bool, span);
+    let block = analyze_block(value_scope_stack, type_scope, errors, block);
+
+    TypedExpr::If(Rc::new(never), Rc::new(block), Type::Void, span)
 }
 
 fn analyze_block(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     block: Expr,
-) -> Result<TypedExpr, TypeError> {
+) -> TypedExpr {
     value_scope_stack.push_scope();
 
-    let stmts = if let Expr::Block(stmts, _) = block {
-        analyze_stmts(value_scope_stack, type_scope, stmts)?
+    let (stmts, span) = if let Expr::Block(stmts, span) = block {
+        (
+            analyze_stmts(value_scope_stack, type_scope, errors, stmts),
+            span,
+        )
     } else {
         unreachable!();
     };
 
     value_scope_stack.pop_scope();
 
-    let ty = stmts
+    let ty = block_type(&stmts);
+
+    TypedExpr::Block(TypedBlock::Interpreted(stmts.into(), ty), span)
+}
+
+// A block's value is that of its first non-`Void` statement.
+fn block_type(stmts: &[TypedStmt]) -> Type {
+    stmts
         .iter()
         .find(|stmt| stmt.expr.ty() != Type::Void)
         .map(|stmt| stmt.expr.ty())
-        .unwrap_or(Type::Void);
-
-    Ok(TypedExpr::Block(TypedBlock::Interpreted(stmts, ty)))
+        .unwrap_or(Type::Void)
 }
 
+// Type expressions which can't be resolved are recorded and replaced with `Type::Unknown`.
 fn analyze_proto_type(
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
+    proto: ProtoType,
+) -> Type {
+    try_analyze_proto_type(type_scope, errors, proto).unwrap_or_else(|error| {
+        errors.push(error);
+        Type::Unknown
+    })
+}
+
+fn try_analyze_proto_type(
+    type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     proto: ProtoType,
 ) -> Result<Type, TypeError> {
     match proto {
-        ProtoType::Atomic(ident) => {
+        ProtoType::Atomic(ident, span) => {
             let binding = type_scope
                 .get(&ident)
-                .ok_or(TypeError::ScopeBindingNotFound(ident))?;
+                .ok_or(TypeError::ScopeBindingNotFound(ident, span))?;
 
             if let TypeBinding::Atomic(ty) = binding {
                 Ok(ty.clone())
@@ -480,10 +788,10 @@ fn analyze_proto_type(
                 todo!();
             }
         }
-        ProtoType::Applied(ident, inners) => {
+        ProtoType::Applied(ident, inners, span) => {
             let binding = type_scope
                 .get(&ident)
-                .ok_or(TypeError::ScopeBindingNotFound(ident.clone()))?;
+                .ok_or(TypeError::ScopeBindingNotFound(ident.clone(), span))?;
 
             if let TypeBinding::Applied { arity } = binding {
                 if inners.len() != *arity {
@@ -491,6 +799,7 @@ fn analyze_proto_type(
                         ident,
                         *arity,
                         inners.len(),
+                        span,
                     ));
                 }
 
@@ -500,8 +809,8 @@ fn analyze_proto_type(
                     "Func" => Ok(Type::Func(
                         inners
                             .iter()
-                            .map(|proto| analyze_proto_type(type_scope, proto.clone()))
-                            .collect::<Result<_, _>>()?,
+                            .map(|proto| analyze_proto_type(type_scope, errors, proto.clone()))
+                            .collect(),
                     )),
                     "List" => {
                         if inners.len() != 1 {
@@ -509,7 +818,7 @@ fn analyze_proto_type(
                         }
 
                         let inner =
-                            analyze_proto_type(type_scope, inners.first().unwrap().clone())?;
+                            analyze_proto_type(type_scope, errors, inners.first().unwrap().clone());
 
                         Ok(Type::List(Box::new(inner)))
                     }
@@ -525,35 +834,44 @@ fn analyze_proto_type(
 fn analyze_list(
     value_scope_stack: &mut ScopeStack<AnalyzerScopeEntry>,
     type_scope: &mut Scope<TypeBinding>,
+    errors: &mut Vec<TypeError>,
     type_hint: &Option<Type>,
     values: Vec<Expr>,
+    span: Span,
 ) -> Result<TypedExpr, TypeError> {
     let element_hint = match type_hint {
         Some(Type::List(inner)) => Some(inner.as_ref().clone()),
-        Some(type_hint) => return Err(TypeError::ExpectedTypeReceivedList(type_hint.clone())),
-        None => None,
+        Some(Type::Unknown) | None => None,
+        Some(type_hint) => {
+            return Err(TypeError::ExpectedTypeReceivedList(type_hint.clone(), span))
+        }
     };
 
     let mut typed_values = Vec::with_capacity(values.len());
 
     for v in values {
-        typed_values.push(analyze_expr(value_scope_stack, type_scope, &None, v)?);
+        typed_values.push(analyze_expr(
+            value_scope_stack,
+            type_scope,
+            errors,
+            &None,
+            v,
+        ));
     }
 
     let list_type = typed_values.first().map(|t| t.ty());
 
     let list_type = if let Some(list_type) = list_type {
         list_type
+    } else if let Some(element_hint) = element_hint {
+        element_hint
     } else {
-        if let Some(element_hint) = element_hint {
-            Ok(element_hint)?
-        } else {
-            Err(TypeError::AmbiguousListType)?
-        }
+        return Err(TypeError::AmbiguousListType(span));
     };
 
     Ok(TypedExpr::List(
-        typed_values,
+        typed_values.into(),
         Type::List(Box::new(list_type)),
+        span,
     ))
 }
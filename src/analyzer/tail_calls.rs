@@ -0,0 +1,79 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFuncCall, TypedStmt},
+    typing::Type,
+};
+
+// How much of an expression's value is passed straight back out of the enclosing function.
+#[derive(Clone, Copy, PartialEq)]
+enum Position {
+    // The value is always the function's result.
+    Tail,
+    // The value is the function's result unless it's `Void`, in which case the function carries on.
+    TailUnlessVoid,
+    Other,
+}
+
+/// Marks the calls in a function body whose value the function returns as-is. A block's value is
+/// that of its first non-`Void` statement, so besides the last statement, any call which can't
+/// return `Void` also ends the function.
+pub fn mark_tail_calls(body: TypedExpr) -> TypedExpr {
+    mark_expr(body, Position::Tail)
+}
+
+fn mark_expr(expr: TypedExpr, position: Position) -> TypedExpr {
+    if position == Position::Other {
+        return expr;
+    }
+
+    match expr {
+        TypedExpr::FuncCall(call, ty, span) => {
+            let is_tail = match position {
+                Position::Tail => true,
+                _ => !matches!(ty, Type::Void | Type::Any | Type::Unknown),
+            };
+
+            let call = TypedFuncCall { is_tail, ..call };
+
+            TypedExpr::FuncCall(call, ty, span)
+        }
+        TypedExpr::If(cond, then, ty, span) => {
+            TypedExpr::If(cond, mark_rc(then, position), ty, span)
+        }
+        TypedExpr::IfElse(cond, then, els, ty, span) => TypedExpr::IfElse(
+            cond,
+            mark_rc(then, position),
+            mark_rc(els, position),
+            ty,
+            span,
+        ),
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, ty), span) => {
+            let last = stmts.len().saturating_sub(1);
+
+            let stmts = stmts
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(i, stmt)| {
+                    let position = if i == last {
+                        position
+                    } else {
+                        Position::TailUnlessVoid
+                    };
+
+                    TypedStmt {
+                        expr: mark_expr(stmt.expr, position),
+                    }
+                })
+                .collect();
+
+            TypedExpr::Block(TypedBlock::Interpreted(stmts, ty), span)
+        }
+        expr => expr,
+    }
+}
+
+fn mark_rc(expr: Rc<TypedExpr>, position: Position) -> Rc<TypedExpr> {
+    Rc::new(mark_expr(Rc::unwrap_or_clone(expr), position))
+}
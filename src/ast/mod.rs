@@ -1,7 +1,10 @@
 pub mod typed;
 pub mod untyped;
 
-#[derive(Debug, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+// Byte offsets into the source. Builtins have no source, so they use the default, empty span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
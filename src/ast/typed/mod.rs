@@ -2,13 +2,17 @@ pub mod typed_block;
 pub mod typed_expr;
 
 use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
 
 use typed_block::TypedBlock;
 use typed_expr::TypedExpr;
 
+use crate::ast::Span;
 use crate::typing::Type;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum TypedLiteral {
     Int(i32),
     Float(f64),
@@ -16,21 +20,28 @@ pub enum TypedLiteral {
     Bool(bool),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TypedStmt {
     pub expr: TypedExpr,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TypedFuncCall {
-    pub func_expr: Box<TypedExpr>,
-    pub args: Vec<TypedExpr>,
+    pub func_expr: Rc<TypedExpr>,
+    pub args: Rc<[TypedExpr]>,
+    /// Whether the call's value is returned straight from the enclosing function, so the callee
+    /// can take over the caller's frame rather than pushing its own.
+    pub is_tail: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TypedFunc {
+    /// The binding the function was declared with, if it wasn't declared anonymously.
+    pub name: Option<String>,
+    /// Where the function was declared.
+    pub span: Span,
     pub params: Vec<(String, Type)>,
-    pub block: Box<TypedExpr>,
+    pub block: Rc<TypedExpr>,
     pub is_closure: bool,
 }
 
@@ -1,12 +1,19 @@
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
 use crate::builtins::BuiltinFuncId;
 use crate::typing::Type;
 
 use super::TypedStmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum TypedBlock {
-    Interpreted(Vec<TypedStmt>, Type),
+    // Shared, so running the block again doesn't copy its statements.
+    Interpreted(Rc<[TypedStmt]>, Type),
     Builtin(Vec<String>, BuiltinFuncId, Type),
+    // The body of a function compiled for the VM, by index into the program's chunks.
+    Compiled(usize, Type),
 }
 
 impl TypedBlock {
@@ -14,6 +21,7 @@ impl TypedBlock {
         match self {
             TypedBlock::Builtin(_, _, ty) => ty.clone(),
             TypedBlock::Interpreted(_, ty) => ty.clone(),
+            TypedBlock::Compiled(_, ty) => ty.clone(),
         }
     }
 }
@@ -1,87 +1,128 @@
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Span;
+use crate::scopes::scope_stack::Slot;
 use crate::typing::Type;
 
 use super::{TypedBlock, TypedFunc, TypedFuncCall, TypedLiteral};
 
 // TODO: For some of these, the type is always clear and maybe we don't need to store it.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum TypedExpr {
     // Binary operations
-    Eq(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Gt(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Lt(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Gte(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Lte(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Add(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Sub(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Mult(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Div(Box<TypedExpr>, Box<TypedExpr>, Type),
-    Modulo(Box<TypedExpr>, Box<TypedExpr>, Type),
+    Eq(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Gt(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Lt(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Gte(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Lte(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Add(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Sub(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Mult(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Div(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Modulo(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
 
     // Unary operations
-    Negate(Box<TypedExpr>, Type),
+    Negate(Rc<TypedExpr>, Type, Span),
     Assignment {
         ident: String,
-        expr: Box<TypedExpr>,
+        slot: Option<Slot>,
+        expr: Rc<TypedExpr>,
+        span: Span,
     },
     Declaration {
         ident: String,
+        slot: Slot,
         is_mutable: bool,
-        expr: Box<TypedExpr>,
+        expr: Rc<TypedExpr>,
         ty: Type,
+        span: Span,
     }, // TODO: This doesn't need type; always void.
 
     // Postfix operations
-    FuncCall(TypedFuncCall, Type),
+    FuncCall(TypedFuncCall, Type, Span),
 
     // Control flow
-    If(Box<TypedExpr>, Box<TypedExpr>, Type),
-    IfElse(Box<TypedExpr>, Box<TypedExpr>, Box<TypedExpr>, Type),
-    Block(TypedBlock),
-    Loop(Box<TypedExpr>),
-    Break,
+    If(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    IfElse(Rc<TypedExpr>, Rc<TypedExpr>, Rc<TypedExpr>, Type, Span),
+    Block(TypedBlock, Span),
+    Loop(Rc<TypedExpr>, Span),
+    Break(Span),
 
     // Primaries
-    Literal(TypedLiteral, Type),
-    Identifier(String, Type),
-    FuncDeclare(TypedFunc, Type),
-    List(Vec<TypedExpr>, Type),
+    Literal(TypedLiteral, Type, Span),
+    // Bindings declared in the enclosing function carry their slot. Anything else, such as a
+    // binding a closure picks up from its caller, is looked up by name.
+    Identifier(String, Option<Slot>, Type, Span),
+    FuncDeclare(TypedFunc, Type, Span),
+    List(Rc<[TypedExpr]>, Type, Span),
+
+    // Placeholder for an expression which failed analysis.
+    Unknown(Span),
 }
 
 impl TypedExpr {
     pub fn ty(&self) -> Type {
         match self {
-            TypedExpr::Eq(_, _, ty) => ty.clone(),
-            TypedExpr::Gt(_, _, ty) => ty.clone(),
-            TypedExpr::Lt(_, _, ty) => ty.clone(),
-            TypedExpr::Gte(_, _, ty) => ty.clone(),
-            TypedExpr::Lte(_, _, ty) => ty.clone(),
-            TypedExpr::Add(_, _, ty) => ty.clone(),
-            TypedExpr::Sub(_, _, ty) => ty.clone(),
-            TypedExpr::Mult(_, _, ty) => ty.clone(),
-            TypedExpr::Div(_, _, ty) => ty.clone(),
-            TypedExpr::Modulo(_, _, ty) => ty.clone(),
+            TypedExpr::Eq(_, _, ty, _) => ty.clone(),
+            TypedExpr::Gt(_, _, ty, _) => ty.clone(),
+            TypedExpr::Lt(_, _, ty, _) => ty.clone(),
+            TypedExpr::Gte(_, _, ty, _) => ty.clone(),
+            TypedExpr::Lte(_, _, ty, _) => ty.clone(),
+            TypedExpr::Add(_, _, ty, _) => ty.clone(),
+            TypedExpr::Sub(_, _, ty, _) => ty.clone(),
+            TypedExpr::Mult(_, _, ty, _) => ty.clone(),
+            TypedExpr::Div(_, _, ty, _) => ty.clone(),
+            TypedExpr::Modulo(_, _, ty, _) => ty.clone(),
 
-            TypedExpr::Literal(_, ty) => ty.clone(),
-            TypedExpr::Negate(_, ty) => ty.clone(),
+            TypedExpr::Literal(_, ty, _) => ty.clone(),
+            TypedExpr::Negate(_, ty, _) => ty.clone(),
             TypedExpr::Assignment { .. } => Type::Void,
             TypedExpr::Declaration { ty, .. } => ty.clone(),
-            TypedExpr::Identifier(_, ty) => ty.clone(),
-            TypedExpr::FuncCall(_, ty) => ty.clone(),
-            TypedExpr::FuncDeclare(_, ty) => ty.clone(),
-            TypedExpr::If(_, _, ty) => ty.clone(),
-            TypedExpr::IfElse(_, _, _, ty) => ty.clone(),
-            TypedExpr::Loop(block) => block.ty(), // TODO: Is this correct? Maybe loops are always Void?
-            TypedExpr::Break => Type::Void,
-            TypedExpr::Block(TypedBlock::Builtin(_, _, ty)) => ty.clone(),
-            TypedExpr::Block(TypedBlock::Interpreted(_, ty)) => ty.clone(),
-            TypedExpr::List(_, ty) => ty.clone(),
+            TypedExpr::Identifier(_, _, ty, _) => ty.clone(),
+            TypedExpr::FuncCall(_, ty, _) => ty.clone(),
+            TypedExpr::FuncDeclare(_, ty, _) => ty.clone(),
+            TypedExpr::If(_, _, ty, _) => ty.clone(),
+            TypedExpr::IfElse(_, _, _, ty, _) => ty.clone(),
+            TypedExpr::Loop(block, _) => block.ty(), // TODO: Is this correct? Maybe loops are always Void?
+            TypedExpr::Break(_) => Type::Void,
+            TypedExpr::Block(block, _) => block.ty(),
+            TypedExpr::List(_, ty, _) => ty.clone(),
+            TypedExpr::Unknown(_) => Type::Unknown,
         }
     }
 
-    pub fn is_func_declare(&self) -> bool {
+    pub fn span(&self) -> Span {
         match self {
-            TypedExpr::FuncDeclare(_, _) => true,
-            _ => false,
+            TypedExpr::Eq(_, _, _, span)
+            | TypedExpr::Gt(_, _, _, span)
+            | TypedExpr::Lt(_, _, _, span)
+            | TypedExpr::Gte(_, _, _, span)
+            | TypedExpr::Lte(_, _, _, span)
+            | TypedExpr::Add(_, _, _, span)
+            | TypedExpr::Sub(_, _, _, span)
+            | TypedExpr::Mult(_, _, _, span)
+            | TypedExpr::Div(_, _, _, span)
+            | TypedExpr::Modulo(_, _, _, span)
+            | TypedExpr::Negate(_, _, span)
+            | TypedExpr::Assignment { span, .. }
+            | TypedExpr::Declaration { span, .. }
+            | TypedExpr::FuncCall(_, _, span)
+            | TypedExpr::If(_, _, _, span)
+            | TypedExpr::IfElse(_, _, _, _, span)
+            | TypedExpr::Block(_, span)
+            | TypedExpr::Loop(_, span)
+            | TypedExpr::Break(span)
+            | TypedExpr::Literal(_, _, span)
+            | TypedExpr::Identifier(_, _, _, span)
+            | TypedExpr::FuncDeclare(_, _, span)
+            | TypedExpr::List(_, _, span)
+            | TypedExpr::Unknown(span) => *span,
         }
     }
+
+    pub fn is_func_declare(&self) -> bool {
+        matches!(self, TypedExpr::FuncDeclare(..))
+    }
 }
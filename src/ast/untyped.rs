@@ -5,55 +5,89 @@ use super::Span;
 #[derive(Clone, Debug)]
 pub enum Expr {
     // Binary operations
-    Eq(Box<Expr>, Box<Expr>),
-    Gt(Box<Expr>, Box<Expr>),
-    Lt(Box<Expr>, Box<Expr>),
-    Gte(Box<Expr>, Box<Expr>),
-    Lte(Box<Expr>, Box<Expr>),
-    Add(Box<Expr>, Box<Expr>),
-    Sub(Box<Expr>, Box<Expr>),
-    Mult(Box<Expr>, Box<Expr>),
-    Div(Box<Expr>, Box<Expr>),
-    Modulo(Box<Expr>, Box<Expr>),
+    Eq(Box<Expr>, Box<Expr>, Span),
+    Gt(Box<Expr>, Box<Expr>, Span),
+    Lt(Box<Expr>, Box<Expr>, Span),
+    Gte(Box<Expr>, Box<Expr>, Span),
+    Lte(Box<Expr>, Box<Expr>, Span),
+    Add(Box<Expr>, Box<Expr>, Span),
+    Sub(Box<Expr>, Box<Expr>, Span),
+    Mult(Box<Expr>, Box<Expr>, Span),
+    Div(Box<Expr>, Box<Expr>, Span),
+    Modulo(Box<Expr>, Box<Expr>, Span),
 
     // Unary operations
-    Negate(Box<Expr>),
+    Negate(Box<Expr>, Span),
     Assignment {
         ident: String,
         expr: Box<Expr>,
+        span: Span,
     },
     Declaration {
         ident: String,
         type_annotation: Option<ProtoType>,
         expr: Box<Expr>,
         is_mutable: bool,
+        span: Span,
     },
 
     // Postfix operations
     FuncCall(FuncCall, Span),
 
     // Control flow
-    If(Box<Expr>, Box<Expr>),
-    IfElse(Box<Expr>, Box<Expr>, Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Span),
+    IfElse(Box<Expr>, Box<Expr>, Box<Expr>, Span),
     Block(Vec<Stmt>, Span),
-    Loop(Box<Expr>),
-    Break,
+    Loop(Box<Expr>, Span),
+    Break(Span),
 
     // Primaries
-    Literal(Literal),
-    Identifier(String),
-    FuncDeclare(FuncDeclare),
-    List(Vec<Expr>),
+    Literal(Literal, Span),
+    Identifier(String, Span),
+    FuncDeclare(FuncDeclare, Span),
+    List(Vec<Expr>, Span),
+
+    // A named test and its body, only run by the test runner.
+    Test(String, Box<Expr>, Span),
 }
 
 impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Eq(_, _, span)
+            | Expr::Gt(_, _, span)
+            | Expr::Lt(_, _, span)
+            | Expr::Gte(_, _, span)
+            | Expr::Lte(_, _, span)
+            | Expr::Add(_, _, span)
+            | Expr::Sub(_, _, span)
+            | Expr::Mult(_, _, span)
+            | Expr::Div(_, _, span)
+            | Expr::Modulo(_, _, span)
+            | Expr::Negate(_, span)
+            | Expr::Assignment { span, .. }
+            | Expr::Declaration { span, .. }
+            | Expr::FuncCall(_, span)
+            | Expr::If(_, _, span)
+            | Expr::IfElse(_, _, _, span)
+            | Expr::Block(_, span)
+            | Expr::Loop(_, span)
+            | Expr::Break(span)
+            | Expr::Literal(_, span)
+            | Expr::Identifier(_, span)
+            | Expr::FuncDeclare(_, span)
+            | Expr::List(_, span)
+            | Expr::Test(_, _, span) => *span,
+        }
+    }
+
     pub fn is_func_declare(&self) -> bool {
-        return matches!(self, Expr::FuncDeclare(_));
+        matches!(self, Expr::FuncDeclare(..))
     }
 
     pub fn as_func_declare(&self) -> &FuncDeclare {
-        if let Expr::FuncDeclare(func) = self {
-            &func
+        if let Expr::FuncDeclare(func, _) = self {
+            func
         } else {
             unreachable!()
         }
@@ -0,0 +1,111 @@
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+use crate::{
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+        Span,
+    },
+    builtins::BuiltinFuncId,
+    errors::runtime_error::RuntimeError,
+    interpreter::resolved_value::ResolvedValue,
+    state::io_context::IoContext,
+    typing::Type,
+};
+
+pub fn make_assert() -> TypedExpr {
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(
+            vec![String::from("condition")],
+            BuiltinFuncId::Assert,
+            Type::Void,
+        ),
+        Span::default(),
+    ));
+
+    let func = TypedFunc {
+        name: Some(String::from("assert")),
+        span: Span::default(),
+        params: vec![(String::from("condition"), Type::Bool)],
+        is_closure: false,
+        block,
+    };
+
+    TypedExpr::FuncDeclare(
+        func,
+        Type::Func(vec![Type::Bool, Type::Void]),
+        Span::default(),
+    )
+}
+
+pub fn eval_assert<R: Read, W: Write>(
+    _io: &mut IoContext<R, W>,
+    mut args: Vec<ResolvedValue>,
+) -> Result<ResolvedValue, RuntimeError> {
+    if args.pop().unwrap().unwrap_bool() {
+        Ok(ResolvedValue::Void)
+    } else {
+        Err(RuntimeError::AssertionFailed)
+    }
+}
+
+pub fn make_assert_eq() -> TypedExpr {
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(
+            vec![String::from("left"), String::from("right")],
+            BuiltinFuncId::AssertEq,
+            Type::Void,
+        ),
+        Span::default(),
+    ));
+
+    let func = TypedFunc {
+        name: Some(String::from("assert_eq")),
+        span: Span::default(),
+        params: vec![
+            (String::from("left"), Type::Any),
+            (String::from("right"), Type::Any),
+        ],
+        is_closure: false,
+        block,
+    };
+
+    TypedExpr::FuncDeclare(
+        func,
+        Type::Func(vec![Type::Any, Type::Any, Type::Void]),
+        Span::default(),
+    )
+}
+
+pub fn eval_assert_eq<R: Read, W: Write>(
+    _io: &mut IoContext<R, W>,
+    mut args: Vec<ResolvedValue>,
+) -> Result<ResolvedValue, RuntimeError> {
+    let right = args.pop().unwrap();
+    let left = args.pop().unwrap();
+
+    if is_equal(&left, &right) {
+        Ok(ResolvedValue::Void)
+    } else {
+        Err(RuntimeError::AssertionNotEqual(
+            left.render(),
+            right.render(),
+        ))
+    }
+}
+
+// Values of different types are never equal, and functions are only equal to themselves.
+fn is_equal(left: &ResolvedValue, right: &ResolvedValue) -> bool {
+    match (left, right) {
+        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => l == r,
+        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => l == r,
+        (ResolvedValue::String(l), ResolvedValue::String(r)) => l == r,
+        (ResolvedValue::Bool(l), ResolvedValue::Bool(r)) => l == r,
+        (ResolvedValue::Void, ResolvedValue::Void) => true,
+        (ResolvedValue::List(l), ResolvedValue::List(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| is_equal(l, r))
+        }
+        (ResolvedValue::Func(l), ResolvedValue::Func(r)) => Rc::ptr_eq(l, r),
+        _ => false,
+    }
+}
@@ -1,7 +1,11 @@
 use std::io::{Read, Write};
+use std::rc::Rc;
 
 use crate::{
-    ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+        Span,
+    },
     errors::runtime_error::RuntimeError,
     interpreter::resolved_value::ResolvedValue,
     state::io_context::IoContext,
@@ -11,19 +15,24 @@ use crate::{
 use crate::builtins::BuiltinFuncId;
 
 pub fn make_int() -> TypedExpr {
-    let block = Box::new(TypedExpr::Block(TypedBlock::Builtin(
-        vec![String::from("value")],
-        BuiltinFuncId::Int,
-        Type::Int,
-    )));
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(vec![String::from("value")], BuiltinFuncId::Int, Type::Int),
+        Span::default(),
+    ));
 
     let func = TypedFunc {
+        name: Some(String::from("int")),
+        span: Span::default(),
         params: vec![(String::from("value"), Type::Any)],
         is_closure: false,
         block,
     };
 
-    TypedExpr::FuncDeclare(func, Type::Func(vec![Type::Any, Type::Int]))
+    TypedExpr::FuncDeclare(
+        func,
+        Type::Func(vec![Type::Any, Type::Int]),
+        Span::default(),
+    )
 }
 
 // TODO: This is a free function right now, but we might consider implementing it as a static method
@@ -36,9 +45,7 @@ pub fn eval_int<R: Read, W: Write>(
 
     match value {
         // TODO: Parse error handling.
-        ResolvedValue::String(str) => {
-            Ok(ResolvedValue::Int(i32::from_str_radix(&str, 10).unwrap()))
-        }
+        ResolvedValue::String(str) => Ok(ResolvedValue::Int(str.parse::<i32>().unwrap())),
         ResolvedValue::Bool(bool) => {
             if bool {
                 Ok(ResolvedValue::Int(1))
@@ -51,19 +58,24 @@ pub fn eval_int<R: Read, W: Write>(
 }
 
 pub fn make_str() -> TypedExpr {
-    let block = Box::new(TypedExpr::Block(TypedBlock::Builtin(
-        vec![String::from("value")],
-        BuiltinFuncId::Str,
-        Type::Str,
-    )));
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(vec![String::from("value")], BuiltinFuncId::Str, Type::Str),
+        Span::default(),
+    ));
 
     let func = TypedFunc {
+        name: Some(String::from("str")),
+        span: Span::default(),
         params: vec![(String::from("value"), Type::Any)],
         is_closure: false,
         block,
     };
 
-    TypedExpr::FuncDeclare(func, Type::Func(vec![Type::Any, Type::Str]))
+    TypedExpr::FuncDeclare(
+        func,
+        Type::Func(vec![Type::Any, Type::Str]),
+        Span::default(),
+    )
 }
 
 // TODO: This is a free function right now, but we might consider implementing it as a static method
@@ -1,7 +1,11 @@
 use std::io::{Read, Write};
+use std::rc::Rc;
 
 use crate::{
-    ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+        Span,
+    },
     builtins::BuiltinFuncId,
     errors::runtime_error::RuntimeError,
     interpreter::resolved_value::ResolvedValue,
@@ -12,13 +16,18 @@ use crate::{
 // TODO: This is implemented concretely for list of strings only right now, will need to make overloads
 // to support other types, or preferably add support for generics.
 pub fn make_push() -> TypedExpr {
-    let block = Box::new(TypedExpr::Block(TypedBlock::Builtin(
-        vec![String::from("list"), String::from("item")],
-        BuiltinFuncId::Push,
-        Type::List(Box::new(Type::Str)),
-    )));
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(
+            vec![String::from("list"), String::from("item")],
+            BuiltinFuncId::Push,
+            Type::List(Box::new(Type::Str)),
+        ),
+        Span::default(),
+    ));
 
     let func = TypedFunc {
+        name: Some(String::from("push")),
+        span: Span::default(),
         params: vec![
             (String::from("list"), Type::List(Box::new(Type::Str))),
             (String::from("item"), Type::Str),
@@ -34,6 +43,7 @@ pub fn make_push() -> TypedExpr {
             Type::Str,
             Type::List(Box::new(Type::Str)),
         ]),
+        Span::default(),
     )
 }
 
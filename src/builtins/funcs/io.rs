@@ -1,7 +1,11 @@
 use std::io::{Read, Write};
+use std::rc::Rc;
 
 use crate::{
-    ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+        Span,
+    },
     builtins::BuiltinFuncId,
     errors::runtime_error::RuntimeError,
     interpreter::resolved_value::ResolvedValue,
@@ -10,19 +14,28 @@ use crate::{
 };
 
 pub fn make_print_line() -> TypedExpr {
-    let block = Box::new(TypedExpr::Block(TypedBlock::Builtin(
-        vec![String::from("message")],
-        BuiltinFuncId::PrintLine,
-        Type::Void,
-    )));
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(
+            vec![String::from("message")],
+            BuiltinFuncId::PrintLine,
+            Type::Void,
+        ),
+        Span::default(),
+    ));
 
     let func = TypedFunc {
+        name: Some(String::from("print_line")),
+        span: Span::default(),
         params: vec![(String::from("message"), Type::Any)],
         is_closure: false,
         block,
     };
 
-    TypedExpr::FuncDeclare(func, Type::Func(vec![Type::Any, Type::Void]))
+    TypedExpr::FuncDeclare(
+        func,
+        Type::Func(vec![Type::Any, Type::Void]),
+        Span::default(),
+    )
 }
 
 pub fn eval_print_line<R: Read, W: Write>(
@@ -59,19 +72,20 @@ pub fn eval_print_line<R: Read, W: Write>(
 }
 
 pub fn make_read_line() -> TypedExpr {
-    let block = Box::new(TypedExpr::Block(TypedBlock::Builtin(
-        vec![],
-        BuiltinFuncId::ReadLine,
-        Type::Str,
-    )));
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(vec![], BuiltinFuncId::ReadLine, Type::Str),
+        Span::default(),
+    ));
 
     let func = TypedFunc {
+        name: Some(String::from("read_line")),
+        span: Span::default(),
         params: vec![],
         is_closure: false,
         block,
     };
 
-    TypedExpr::FuncDeclare(func, Type::Func(vec![Type::Str]))
+    TypedExpr::FuncDeclare(func, Type::Func(vec![Type::Str]), Span::default())
 }
 
 pub fn eval_read_line<R: Read, W: Write>(
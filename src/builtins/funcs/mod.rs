@@ -1,3 +1,5 @@
+pub mod assertions;
 pub mod casting;
 pub mod collections;
 pub mod io;
+pub mod process;
@@ -0,0 +1,122 @@
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+use crate::{
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc},
+        Span,
+    },
+    builtins::BuiltinFuncId,
+    errors::runtime_error::RuntimeError,
+    interpreter::resolved_value::ResolvedValue,
+    state::io_context::IoContext,
+    typing::Type,
+};
+
+pub fn make_args() -> TypedExpr {
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(vec![], BuiltinFuncId::Args, str_list()),
+        Span::default(),
+    ));
+
+    let func = TypedFunc {
+        name: Some(String::from("args")),
+        span: Span::default(),
+        params: vec![],
+        is_closure: false,
+        block,
+    };
+
+    TypedExpr::FuncDeclare(func, Type::Func(vec![str_list()]), Span::default())
+}
+
+pub fn eval_args<R: Read, W: Write>(
+    io: &mut IoContext<R, W>,
+    mut _args: Vec<ResolvedValue>,
+) -> Result<ResolvedValue, RuntimeError> {
+    let args = io.args.iter().cloned().map(ResolvedValue::String).collect();
+
+    Ok(ResolvedValue::List(args))
+}
+
+// TODO: There's no Option type yet, so a variable which isn't set is an empty list, and one which
+// is set is a list of just its value.
+pub fn make_env() -> TypedExpr {
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(vec![String::from("name")], BuiltinFuncId::Env, str_list()),
+        Span::default(),
+    ));
+
+    let func = TypedFunc {
+        name: Some(String::from("env")),
+        span: Span::default(),
+        params: vec![(String::from("name"), Type::Str)],
+        is_closure: false,
+        block,
+    };
+
+    TypedExpr::FuncDeclare(
+        func,
+        Type::Func(vec![Type::Str, str_list()]),
+        Span::default(),
+    )
+}
+
+pub fn eval_env<R: Read, W: Write>(
+    _io: &mut IoContext<R, W>,
+    mut args: Vec<ResolvedValue>,
+) -> Result<ResolvedValue, RuntimeError> {
+    let name = args.pop().unwrap().unwrap_string();
+
+    // Names no variable could have are never set, and are best not asked about, as doing so can
+    // panic. Values which aren't valid unicode can't be held in a string, so are treated as unset.
+    let is_valid = !name.is_empty() && !name.contains(['=', '\0']);
+
+    let value = is_valid
+        .then(|| std::env::var(name).ok())
+        .flatten()
+        .map(ResolvedValue::String);
+
+    Ok(ResolvedValue::List(value.into_iter().collect()))
+}
+
+pub fn make_exit() -> TypedExpr {
+    let block = Rc::new(TypedExpr::Block(
+        TypedBlock::Builtin(vec![String::from("code")], BuiltinFuncId::Exit, Type::Void),
+        Span::default(),
+    ));
+
+    let func = TypedFunc {
+        name: Some(String::from("exit")),
+        span: Span::default(),
+        params: vec![(String::from("code"), Type::Int)],
+        is_closure: false,
+        block,
+    };
+
+    TypedExpr::FuncDeclare(
+        func,
+        Type::Func(vec![Type::Int, Type::Void]),
+        Span::default(),
+    )
+}
+
+// The program stops by unwinding like any error would, and it's up to whoever ran it to exit. Codes
+// are limited to what a process can exit with, and are passed on as they are, so a program's own
+// code can be the same as one moss exits with when it fails.
+pub fn eval_exit<R: Read, W: Write>(
+    _io: &mut IoContext<R, W>,
+    mut args: Vec<ResolvedValue>,
+) -> Result<ResolvedValue, RuntimeError> {
+    let code = args.pop().unwrap().unwrap_int();
+
+    if !(0..=255).contains(&code) {
+        return Err(RuntimeError::ExitCodeOutOfRange(code));
+    }
+
+    Err(RuntimeError::Exit(code))
+}
+
+fn str_list() -> Type {
+    Type::List(Box::new(Type::Str))
+}
@@ -5,14 +5,23 @@ use std::{
     io::{Read, Write},
 };
 
+use serde::{Deserialize, Serialize};
+
 use funcs::{
+    assertions::{eval_assert, eval_assert_eq, make_assert, make_assert_eq},
     casting::{eval_int, eval_str, make_int, make_str},
     collections::{eval_push, make_push},
     io::{eval_print_line, eval_read_line, make_print_line, make_read_line},
+    process::{eval_args, eval_env, eval_exit, make_args, make_env, make_exit},
 };
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BuiltinFuncId {
+    Args,
+    Assert,
+    AssertEq,
+    Env,
+    Exit,
     Int,
     PrintLine,
     Push,
@@ -34,6 +43,11 @@ use crate::{
 
 pub fn get_builtin_func_bindings() -> Vec<(String, TypedExpr)> {
     vec![
+        (String::from("args"), make_args()),
+        (String::from("assert"), make_assert()),
+        (String::from("assert_eq"), make_assert_eq()),
+        (String::from("env"), make_env()),
+        (String::from("exit"), make_exit()),
         (String::from("int"), make_int()),
         (String::from("print_line"), make_print_line()),
         (String::from("push"), make_push()),
@@ -43,6 +57,11 @@ pub fn get_builtin_func_bindings() -> Vec<(String, TypedExpr)> {
 }
 pub fn get_builtin_funcs<R: Read, W: Write>() -> HashMap<BuiltinFuncId, BuiltinFunc<R, W>> {
     hashmap! {
+        BuiltinFuncId::Args => eval_args as BuiltinFunc<R, W>,
+        BuiltinFuncId::Assert => eval_assert as BuiltinFunc<R, W>,
+        BuiltinFuncId::AssertEq => eval_assert_eq as BuiltinFunc<R, W>,
+        BuiltinFuncId::Env => eval_env as BuiltinFunc<R, W>,
+        BuiltinFuncId::Exit => eval_exit as BuiltinFunc<R, W>,
         BuiltinFuncId::Int => eval_int as BuiltinFunc<R, W>,
         BuiltinFuncId::PrintLine => eval_print_line as BuiltinFunc<R, W>,
         BuiltinFuncId::Push => eval_push as BuiltinFunc<R, W>,
@@ -0,0 +1,101 @@
+use crate::{ast::Span, interpreter::resolved_value::ResolvedValue, scopes::scope_stack::Slot};
+
+/// A single VM instruction. Every expression compiles to instructions which leave exactly one
+/// value on the value stack, so statements can always discard or keep their result.
+#[derive(Clone, Copy, Debug)]
+pub enum Instruction {
+    // Values
+    Constant(usize),
+    Void,
+    Pop,
+    List(usize, Span),
+
+    // Binary operations
+    Add(Span),
+    Sub(Span),
+    Mult(Span),
+    Div(Span),
+    Modulo(Span),
+    Eq,
+    Gt,
+    Lt,
+    Gte,
+    Lte,
+
+    // Unary operations
+    Negate(Span),
+
+    // Bindings, by index into the chunk's name pool, or by slot for those the analyzer resolved.
+    // Declarations and assignments leave `Void`.
+    Load(usize, Span),
+    LoadSlot(Slot),
+    Declare(usize, bool, Span),
+    Assign(usize, Span),
+    AssignSlot(Slot),
+
+    // Scope
+    PushScope,
+    PopScope,
+
+    // Control flow. Offsets are relative to the jumping instruction.
+    Jump(isize),
+    JumpIfFalse(isize),
+    JumpIfNotVoid(isize),
+    EnterLoop(isize),
+    Break,
+
+    // Calls, with the callee's name (for backtraces) and the call site. A tail call takes over the
+    // calling function's frame.
+    Call(usize, usize, Span),
+    TailCall(usize, usize, Span),
+    Return,
+}
+
+/// The compiled body of a single function, or of the program itself.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    pub constants: Vec<ResolvedValue>,
+    pub names: Vec<String>,
+}
+
+impl Chunk {
+    pub fn emit(&mut self, instruction: Instruction) -> usize {
+        self.code.push(instruction);
+        self.code.len() - 1
+    }
+
+    pub fn add_constant(&mut self, value: ResolvedValue) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    pub fn add_name(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        }
+    }
+
+    // Points the jump at `at` to the next instruction to be emitted.
+    pub fn patch_jump(&mut self, at: usize) {
+        let offset = (self.code.len() - at) as isize;
+
+        self.code[at] = match self.code[at] {
+            Instruction::Jump(_) => Instruction::Jump(offset),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(offset),
+            Instruction::JumpIfNotVoid(_) => Instruction::JumpIfNotVoid(offset),
+            Instruction::EnterLoop(_) => Instruction::EnterLoop(offset),
+            _ => unreachable!(),
+        };
+    }
+}
+
+/// A compiled program. The first chunk is the program's top level.
+#[derive(Debug)]
+pub struct Program {
+    pub chunks: Vec<Chunk>,
+}
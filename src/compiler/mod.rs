@@ -0,0 +1,315 @@
+pub mod chunk;
+
+use std::rc::Rc;
+
+use crate::{
+    ast::typed::{
+        typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc, TypedLiteral, TypedStmt,
+    },
+    interpreter::resolved_value::ResolvedValue,
+};
+
+use chunk::{Chunk, Instruction, Program};
+
+/// Compiles an analyzed program into flat instructions for the VM. Each function literal gets a
+/// chunk of its own, and is referenced from its declaring chunk's constant pool.
+pub fn compile_program(program: TypedExpr) -> Program {
+    let stmts = match program {
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty), _span) => stmts,
+        _ => unreachable!(),
+    };
+
+    let mut chunks = vec![Chunk::default()];
+
+    let mut main = Chunk::default();
+    compile_block(&mut chunks, &mut main, &stmts);
+    main.emit(Instruction::Return);
+
+    chunks[0] = main;
+
+    Program { chunks }
+}
+
+fn compile_expr(chunks: &mut Vec<Chunk>, chunk: &mut Chunk, expr: TypedExpr) {
+    match expr {
+        // Binary operations
+        TypedExpr::Eq(l, r, _ty, _span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Eq,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Gt(l, r, _ty, _span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Gt,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Lt(l, r, _ty, _span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Lt,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Gte(l, r, _ty, _span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Gte,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Lte(l, r, _ty, _span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Lte,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Add(l, r, _ty, span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Add(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Sub(l, r, _ty, span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Sub(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Mult(l, r, _ty, span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Mult(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Div(l, r, _ty, span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Div(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Modulo(l, r, _ty, span) => compile_binary_op(
+            chunks,
+            chunk,
+            Instruction::Modulo(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+
+        // Unary operations
+        TypedExpr::Negate(expr, _ty, span) => {
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(expr));
+            chunk.emit(Instruction::Negate(span));
+        }
+        TypedExpr::Assignment {
+            ident,
+            slot,
+            expr,
+            span,
+        } => {
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(expr));
+
+            match slot {
+                Some(slot) => chunk.emit(Instruction::AssignSlot(slot)),
+                None => {
+                    let name = chunk.add_name(&ident);
+                    chunk.emit(Instruction::Assign(name, span))
+                }
+            };
+        }
+        TypedExpr::Declaration {
+            ident,
+            is_mutable,
+            expr,
+            span,
+            ..
+        } => {
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(expr));
+            let name = chunk.add_name(&ident);
+            chunk.emit(Instruction::Declare(name, is_mutable, span));
+        }
+
+        // Postfix operations
+        TypedExpr::FuncCall(call, _ty, span) => {
+            // Backtraces name functions after the binding they're called through, when there is one.
+            let name = match call.func_expr.as_ref() {
+                TypedExpr::Identifier(ident, ..) => chunk.add_name(ident),
+                _ => chunk.add_name("<anonymous>"),
+            };
+
+            let arg_count = call.args.len();
+            let is_tail = call.is_tail;
+
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(call.func_expr));
+
+            for arg in call.args.iter() {
+                compile_expr(chunks, chunk, arg.clone());
+            }
+
+            if is_tail {
+                chunk.emit(Instruction::TailCall(arg_count, name, span));
+            } else {
+                chunk.emit(Instruction::Call(arg_count, name, span));
+            }
+        }
+
+        // Control flow
+        TypedExpr::If(cond, then, _ty, _span) => {
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(cond));
+            let to_else = chunk.emit(Instruction::JumpIfFalse(0));
+
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(then));
+            let to_end = chunk.emit(Instruction::Jump(0));
+
+            chunk.patch_jump(to_else);
+            chunk.emit(Instruction::Void);
+
+            chunk.patch_jump(to_end);
+        }
+        TypedExpr::IfElse(cond, then, els, _ty, _span) => {
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(cond));
+            let to_else = chunk.emit(Instruction::JumpIfFalse(0));
+
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(then));
+            let to_end = chunk.emit(Instruction::Jump(0));
+
+            chunk.patch_jump(to_else);
+            compile_expr(chunks, chunk, Rc::unwrap_or_clone(els));
+
+            chunk.patch_jump(to_end);
+        }
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty), _span) => {
+            compile_block(chunks, chunk, &stmts)
+        }
+        TypedExpr::Block(..) => unreachable!(),
+        TypedExpr::Loop(block, _span) => {
+            let stmts = match Rc::unwrap_or_clone(block) {
+                TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty), _span) => stmts,
+                _ => unreachable!(),
+            };
+
+            // Loops only ever end by breaking, which jumps to the exit registered here.
+            let to_exit = chunk.emit(Instruction::EnterLoop(0));
+            let start = chunk.code.len();
+
+            compile_block(chunks, chunk, &stmts);
+            chunk.emit(Instruction::Pop);
+
+            let offset = start as isize - chunk.code.len() as isize;
+            chunk.emit(Instruction::Jump(offset));
+
+            chunk.patch_jump(to_exit);
+            chunk.emit(Instruction::Void);
+        }
+        TypedExpr::Break(_span) => {
+            chunk.emit(Instruction::Break);
+        }
+
+        // Primaries
+        TypedExpr::Literal(literal, _ty, _span) => {
+            let value = match literal {
+                TypedLiteral::Int(int) => ResolvedValue::Int(int),
+                TypedLiteral::Float(float) => ResolvedValue::Float(float),
+                TypedLiteral::String(string) => ResolvedValue::String(string),
+                TypedLiteral::Bool(boolean) => ResolvedValue::Bool(boolean),
+            };
+
+            let constant = chunk.add_constant(value);
+            chunk.emit(Instruction::Constant(constant));
+        }
+        TypedExpr::Identifier(ident, slot, _ty, span) => {
+            match slot {
+                Some(slot) => chunk.emit(Instruction::LoadSlot(slot)),
+                None => {
+                    let name = chunk.add_name(&ident);
+                    chunk.emit(Instruction::Load(name, span))
+                }
+            };
+        }
+        TypedExpr::FuncDeclare(func, _ty, _span) => {
+            let func = compile_func(chunks, func);
+            let constant = chunk.add_constant(ResolvedValue::Func(Rc::new(func)));
+            chunk.emit(Instruction::Constant(constant));
+        }
+        TypedExpr::List(items, _ty, span) => {
+            let len = items.len();
+
+            for item in items.iter() {
+                compile_expr(chunks, chunk, item.clone());
+            }
+
+            chunk.emit(Instruction::List(len, span));
+        }
+
+        // Programs with type errors are never compiled.
+        TypedExpr::Unknown(_span) => unreachable!(),
+    }
+}
+
+fn compile_binary_op(
+    chunks: &mut Vec<Chunk>,
+    chunk: &mut Chunk,
+    op: Instruction,
+    left: TypedExpr,
+    right: TypedExpr,
+) {
+    compile_expr(chunks, chunk, left);
+    compile_expr(chunks, chunk, right);
+    chunk.emit(op);
+}
+
+// A block's value is that of its first statement which isn't `Void`, at which point the rest of
+// the block is skipped. Every block gets a scope of its own, matching the slots the analyzer gave
+// its bindings.
+fn compile_block(chunks: &mut Vec<Chunk>, chunk: &mut Chunk, stmts: &[TypedStmt]) {
+    let mut to_end = Vec::new();
+
+    chunk.emit(Instruction::PushScope);
+
+    for stmt in stmts {
+        compile_expr(chunks, chunk, stmt.expr.clone());
+        to_end.push(chunk.emit(Instruction::JumpIfNotVoid(0)));
+    }
+
+    chunk.emit(Instruction::Void);
+
+    for at in to_end {
+        chunk.patch_jump(at);
+    }
+
+    chunk.emit(Instruction::PopScope);
+}
+
+// Compiles the function's body into a new chunk, and returns the function with its body replaced
+// by a reference to that chunk.
+fn compile_func(chunks: &mut Vec<Chunk>, func: TypedFunc) -> TypedFunc {
+    let (stmts, ty, span) = match Rc::unwrap_or_clone(func.block) {
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, ty), span) => (stmts, ty, span),
+        _ => unreachable!(),
+    };
+
+    let index = chunks.len();
+    chunks.push(Chunk::default());
+
+    let mut body = Chunk::default();
+    compile_block(chunks, &mut body, &stmts);
+    body.emit(Instruction::Return);
+
+    chunks[index] = body;
+
+    TypedFunc {
+        name: func.name,
+        span: func.span,
+        params: func.params,
+        block: Rc::new(TypedExpr::Block(TypedBlock::Compiled(index, ty), span)),
+        is_closure: func.is_closure,
+    }
+}
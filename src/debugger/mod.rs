@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+use std::io::{BufRead, Read, Write};
+
+use crate::{
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedStmt},
+        Span,
+    },
+    errors::{location_frame::get_line_number, runtime_error::RuntimeError},
+    interpreter::{resolved_value::ResolvedValue, Inspector},
+    state::{exec_context::ExecContext, io_context::IoContext},
+};
+
+const HELP: &str = "\
+Commands:
+  break <line>    (b)   Stop whenever a statement on the line is about to run.
+  delete <line>   (d)   Remove the breakpoint from the line.
+  step            (s)   Run to the next statement, entering any function called.
+  next            (n)   Run to the next statement in this function or its caller.
+  out             (o)   Run until the current function returns.
+  continue        (c)   Run until the next breakpoint.
+  print <name>    (p)   Show the value of a binding.
+  locals          (l)   Show every binding in scope.
+  backtrace       (bt)  Show the calls in progress, innermost first.
+  quit            (q)   Stop the program.";
+
+// Where to stop next, besides at breakpoints. Depths count the calls in progress.
+#[derive(Clone, Copy)]
+enum Mode {
+    StepInto,
+    StepOver(usize),
+    StepOut(usize),
+    Continue,
+}
+
+/// An interactive debugger, which takes commands from the program's own input between statements.
+/// It starts out stopped at the first statement.
+pub struct Debugger {
+    file_name: String,
+    source: String,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    has_quit: bool,
+}
+
+impl Debugger {
+    pub fn new(file_name: String, source: String) -> Self {
+        Self {
+            file_name,
+            source,
+            breakpoints: BTreeSet::new(),
+            mode: Mode::StepInto,
+            has_quit: false,
+        }
+    }
+
+    /// Whether the program was stopped by the user quitting, rather than by being interrupted.
+    pub fn has_quit(&self) -> bool {
+        self.has_quit
+    }
+
+    fn should_stop(&self, exec: &ExecContext, line: usize) -> bool {
+        let depth = exec.call_stack.len();
+
+        let stepped = match self.mode {
+            Mode::StepInto => true,
+            Mode::StepOver(from) => depth <= from,
+            Mode::StepOut(from) => depth < from,
+            Mode::Continue => false,
+        };
+
+        stepped || self.breakpoints.contains(&line)
+    }
+
+    fn line_count(&self) -> usize {
+        self.source.lines().count()
+    }
+
+    fn location(&self, span: Span) -> String {
+        format!(
+            "{}:{}",
+            self.file_name,
+            get_line_number(&self.source, span.start)
+        )
+    }
+
+    // Handles a single command, returning what to reply with, or `None` to let the program carry on.
+    fn command(&mut self, exec: &ExecContext, span: Span, input: &str) -> Option<String> {
+        let mut words = input.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let arg = words.next();
+        let depth = exec.call_stack.len();
+
+        let output = match (command, arg) {
+            ("step" | "s", None) => {
+                self.mode = Mode::StepInto;
+                return None;
+            }
+            ("next" | "n", None) => {
+                self.mode = Mode::StepOver(depth);
+                return None;
+            }
+            ("out" | "o", None) => {
+                self.mode = Mode::StepOut(depth);
+                return None;
+            }
+            ("continue" | "c", None) => {
+                self.mode = Mode::Continue;
+                return None;
+            }
+            ("break" | "b", Some(line)) => match line.parse() {
+                Ok(line) if (1..=self.line_count()).contains(&line) => {
+                    self.breakpoints.insert(line);
+                    format!("Breakpoint set at {}:{line}.", self.file_name)
+                }
+                _ => format!("No line {line} in {}.", self.file_name),
+            },
+            ("delete" | "d", Some(line)) => match line.parse() {
+                Ok(line) if self.breakpoints.remove(&line) => {
+                    format!("Breakpoint removed from {}:{line}.", self.file_name)
+                }
+                _ => format!("No breakpoint at {}:{line}.", self.file_name),
+            },
+            ("print" | "p", Some(name)) => {
+                match exec.scope_stack.lookup::<RuntimeError>(name, span) {
+                    Ok(entry) => format!("{name} = {}", entry.value.render()),
+                    Err(_) => format!("No binding for \"{name}\" in scope."),
+                }
+            }
+            ("locals" | "l", None) => {
+                let bindings: Vec<_> = exec
+                    .scope_stack
+                    .visible_bindings()
+                    .into_iter()
+                    .filter(|(_, entry)| !is_builtin(&entry.value))
+                    .map(|(name, entry)| format!("{name} = {}", entry.value.render()))
+                    .collect();
+
+                if bindings.is_empty() {
+                    String::from("No bindings in scope.")
+                } else {
+                    bindings.join("\n")
+                }
+            }
+            ("backtrace" | "bt", None) => {
+                let mut lines = vec![format!("At {}", self.location(span))];
+
+                for frame in exec.call_stack.iter().rev() {
+                    let location = self.location(frame.call_site);
+                    lines.push(format!("In {}, called from {location}", frame.name));
+
+                    if frame.elided > 0 {
+                        lines.push(format!("({} earlier tail calls not shown)", frame.elided));
+                    }
+                }
+
+                lines.join("\n")
+            }
+            ("help" | "h", None) => String::from(HELP),
+            ("quit" | "q", None) => {
+                self.has_quit = true;
+                return None;
+            }
+            ("", None) => String::new(),
+            _ => format!(
+                "Unknown command \"{}\". Type \"help\" for a list.",
+                input.trim()
+            ),
+        };
+
+        Some(output)
+    }
+}
+
+impl<R: Read, W: Write> Inspector<R, W> for Debugger {
+    fn before_stmt(
+        &mut self,
+        exec: &ExecContext,
+        io: &mut IoContext<R, W>,
+        stmt: &TypedStmt,
+    ) -> Result<(), RuntimeError> {
+        let span = stmt.expr.span();
+        let line_num = get_line_number(&self.source, span.start);
+
+        if !self.should_stop(exec, line_num) {
+            return Ok(());
+        }
+
+        let line = self.source.lines().nth(line_num - 1).unwrap_or_default();
+        io.write_line(&format!("Stopped at {}", self.location(span)))?;
+        io.write_line(&format!("{line_num} | {}", line.trim()))?;
+
+        loop {
+            write_prompt(io)?;
+
+            let mut input = String::new();
+            let read = io
+                .reader
+                .read_line(&mut input)
+                .map_err(|e| RuntimeError::Io(String::from("read_line"), e.to_string()))?;
+
+            // Running out of commands to read is taken as quitting, so the program can't hang.
+            let reply = match read {
+                0 => {
+                    self.has_quit = true;
+                    None
+                }
+                _ => self.command(exec, span, &input),
+            };
+
+            // Quitting stops the program the same way an interrupt would.
+            if self.has_quit {
+                return Err(RuntimeError::Interrupted);
+            }
+
+            match reply {
+                Some(output) if !output.is_empty() => io.write_line(&output)?,
+                Some(_) => {}
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+fn write_prompt<R: Read, W: Write>(io: &mut IoContext<R, W>) -> Result<(), RuntimeError> {
+    io.writer
+        .write_all(b"(moss) ")
+        .and_then(|_| io.writer.flush())
+        .map_err(|e| RuntimeError::Io(String::from("write"), e.to_string()))
+}
+
+fn is_builtin(value: &ResolvedValue) -> bool {
+    match value {
+        ResolvedValue::Func(func) => {
+            matches!(
+                func.block.as_ref(),
+                TypedExpr::Block(TypedBlock::Builtin(..), _)
+            )
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,153 @@
+use serde_json::{json, Value};
+
+use crate::{ast::Span, state::exec_context::CallFrame};
+
+use super::{
+    location_frame::{get_column_number, get_line_number},
+    parse_error::ParseError,
+    runtime_error::RuntimeError,
+    type_error::TypeError,
+    warning::Warning,
+};
+
+// Diagnostics are rendered as one JSON object each, for editors and other tools to read:
+//
+// {
+//   "severity": "error" | "warning",
+//   "code": "E0217",
+//   "message": "Binding \"x\" not found in scope.",
+//   "file": "main.moss",
+//   "range": { "bytes": { "start": 4, "end": 5 }, "start": { "line": 1, "column": 5 }, ... },
+//   "notes": [{ "message": "...", "range": null }]
+// }
+//
+// Lines and columns are 1-based, with columns counted in characters, as in the human-readable
+// output. Byte ranges are end-exclusive. Diagnostics without a location have a null range.
+
+impl ParseError {
+    /// Renders the error as a JSON diagnostic.
+    pub fn to_json(&self, file_name: &str, source: &str) -> Value {
+        let mut notes = detail_notes(self.detail());
+
+        if let Some(hint) = self.hint(source) {
+            notes.push(note(format!("Hint: {hint}"), None, source));
+        }
+
+        diagnostic(
+            "error",
+            self.code(),
+            self.message(),
+            file_name,
+            range(source, Some(self.span())),
+            notes,
+        )
+    }
+}
+
+impl TypeError {
+    /// Renders the error as a JSON diagnostic.
+    pub fn to_json(&self, file_name: &str, source: &str) -> Value {
+        diagnostic(
+            "error",
+            self.code(),
+            self.message(),
+            file_name,
+            range(source, Some(self.span())),
+            detail_notes(self.detail()),
+        )
+    }
+}
+
+impl RuntimeError {
+    /// Renders the error as a JSON diagnostic, with a note for each call in the backtrace,
+    /// innermost first.
+    pub fn to_json(&self, file_name: &str, source: &str, backtrace: &[CallFrame]) -> Value {
+        let mut notes = detail_notes(self.detail());
+
+        for frame in backtrace.iter().rev() {
+            let mut message = format!("In {}, called from here.", frame.name);
+
+            if frame.elided > 0 {
+                message += &format!(" ({} earlier tail calls not shown)", frame.elided);
+            }
+
+            notes.push(note(message, Some(frame.call_site), source));
+        }
+
+        diagnostic(
+            "error",
+            self.code(),
+            self.message(),
+            file_name,
+            range(source, self.span()),
+            notes,
+        )
+    }
+}
+
+impl Warning {
+    /// Renders the warning as a JSON diagnostic, coded by its lint. Denied lints are errors.
+    pub fn to_json(&self, file_name: &str, source: &str, is_denied: bool) -> Value {
+        let lint = self.lint().name();
+        let silence = format!("Silence with `// allow({lint})`.");
+
+        diagnostic(
+            if is_denied { "error" } else { "warning" },
+            lint,
+            self.message(),
+            file_name,
+            range(source, Some(self.span())),
+            vec![note(silence, None, source)],
+        )
+    }
+}
+
+fn diagnostic(
+    severity: &str,
+    code: &str,
+    message: String,
+    file_name: &str,
+    range: Value,
+    notes: Vec<Value>,
+) -> Value {
+    json!({
+        "severity": severity,
+        "code": code,
+        "message": message,
+        "file": file_name,
+        "range": range,
+        "notes": notes,
+    })
+}
+
+// Details span several lines, such as an expected and a received type, each a note of its own.
+fn detail_notes(detail: Option<String>) -> Vec<Value> {
+    detail
+        .iter()
+        .flat_map(|detail| detail.lines())
+        .map(|line| note(line.to_string(), None, ""))
+        .collect()
+}
+
+fn note(message: String, span: Option<Span>, source: &str) -> Value {
+    json!({ "message": message, "range": range(source, span) })
+}
+
+fn range(source: &str, span: Option<Span>) -> Value {
+    let Some(span) = span else {
+        return Value::Null;
+    };
+
+    json!({
+        "bytes": { "start": span.start, "end": span.end },
+        "start": position(source, span.start),
+        "end": position(source, span.end),
+    })
+}
+
+fn position(source: &str, offset: usize) -> Value {
+    json!({
+        "line": get_line_number(source, offset),
+        "column": get_column_number(source, offset),
+    })
+}
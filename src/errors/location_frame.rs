@@ -0,0 +1,52 @@
+use std::fmt::Formatter;
+
+use crate::ast::Span;
+
+// Renders the first line of the span with its line number, and underlines the spanned columns.
+// Spans which run over multiple lines are underlined to the end of their first line.
+pub(crate) fn write_location_frame(
+    f: &mut Formatter,
+    file_name: &str,
+    source: &str,
+    span: Span,
+) -> std::fmt::Result {
+    let line_num = get_line_number(source, span.start);
+    let col_num = get_column_number(source, span.start);
+    let file_label = format!(" {file_name}:{line_num}:{col_num} ");
+
+    let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[span.start..]
+        .find('\n')
+        .map_or(source.len(), |i| span.start + i);
+    let line = source[line_start..line_end].trim_end_matches('\r');
+
+    let underline_len = source[span.start..span.end.clamp(span.start, line_end)]
+        .chars()
+        .count()
+        .max(1);
+
+    let gutter = " ".repeat(line_num.to_string().len());
+
+    // Tabs are kept in the padding so the carets line up with the source line.
+    let padding: String = source[line_start..span.start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    writeln!(f, "{:-^width$}", file_label, width = 80)?;
+    writeln!(f, "{gutter} |")?;
+    writeln!(f, "{line_num} | {line}")?;
+    writeln!(f, "{gutter} | {padding}{}", "^".repeat(underline_len))?;
+    writeln!(f, "{:-<width$}", "", width = 80)
+}
+
+// Line numbers are 1-based. An offset sitting right after a newline belongs to the next line.
+pub(crate) fn get_line_number(source: &str, byte_offset: usize) -> usize {
+    source[..byte_offset].matches('\n').count() + 1
+}
+
+// Column numbers are 1-based and counted in characters rather than bytes.
+pub(crate) fn get_column_number(source: &str, byte_offset: usize) -> usize {
+    let line_start = source[..byte_offset].rfind('\n').map_or(0, |i| i + 1);
+    source[line_start..byte_offset].chars().count() + 1
+}
@@ -1,8 +1,15 @@
+pub mod json;
+pub(crate) mod location_frame;
+pub mod parse_error;
 pub mod runtime_error;
+pub mod snapshot_error;
 pub mod type_error;
+pub mod warning;
+
+use crate::ast::Span;
 
 // TODO: This is very hacky, this trait probably shouldn't exist.
 pub trait Error {
-    fn scope_binding_not_found(name: &str) -> Self;
-    fn scope_binding_already_exists(name: &str) -> Self;
+    fn scope_binding_not_found(name: &str, span: Span) -> Self;
+    fn scope_binding_already_exists(name: &str, span: Span) -> Self;
 }
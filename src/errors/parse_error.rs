@@ -0,0 +1,170 @@
+use lalrpop_util::lexer::Token;
+
+use crate::ast::Span;
+
+use super::location_frame::write_location_frame;
+
+#[derive(Debug)]
+pub enum ParseError {
+    ExtraToken(String, Span),
+    InvalidToken(Span),
+    UnexpectedEof(Vec<String>, Span),
+    UnexpectedToken(String, Vec<String>, Span),
+}
+
+impl ParseError {
+    pub fn display(self, file_name: String, source: String) -> ParseErrorDisplay {
+        ParseErrorDisplay {
+            error: self,
+            file_name,
+            source,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::ExtraToken(_, span)
+            | ParseError::InvalidToken(span)
+            | ParseError::UnexpectedEof(_, span)
+            | ParseError::UnexpectedToken(_, _, span) => *span,
+        }
+    }
+
+    /// A code which identifies the kind of error. Codes are never reused or renumbered, so tools
+    /// can rely on them; new kinds of error take the next free number.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::ExtraToken(..) => "E0101",
+            ParseError::InvalidToken(_) => "E0102",
+            ParseError::UnexpectedEof(..) => "E0103",
+            ParseError::UnexpectedToken(..) => "E0104",
+        }
+    }
+
+    /// What went wrong, in a sentence.
+    pub fn message(&self) -> String {
+        match self {
+            ParseError::ExtraToken(token, _) => format!("Unexpected extra token `{token}`."),
+            ParseError::InvalidToken(_) => String::from("Invalid token."),
+            ParseError::UnexpectedEof(..) => String::from("Unexpected end of file."),
+            ParseError::UnexpectedToken(token, _, _) => format!("Unexpected token `{token}`."),
+        }
+    }
+
+    /// What the parser expected to find instead, if it expected anything.
+    pub fn detail(&self) -> Option<String> {
+        let expected = self.expected();
+
+        if expected.is_empty() {
+            None
+        } else {
+            Some(format!("Expected: {}", join_expected(expected)))
+        }
+    }
+
+    /// A hint for a common mistake, based on what the parser expected to find and what came before.
+    pub fn hint(&self, source: &str) -> Option<&'static str> {
+        if !self.expected().iter().any(|e| e == "`;`") {
+            return None;
+        }
+
+        let preceding = source[..self.span().start].trim_end();
+
+        if preceding.ends_with('}') {
+            Some("Blocks such as `if`, `loop` and function bodies need a `;` after the closing `}` when used as statements.")
+        } else {
+            Some("Statements must end with a `;`.")
+        }
+    }
+
+    fn expected(&self) -> &[String] {
+        match self {
+            ParseError::UnexpectedEof(expected, _)
+            | ParseError::UnexpectedToken(_, expected, _) => expected,
+            _ => &[],
+        }
+    }
+}
+
+impl From<lalrpop_util::ParseError<usize, Token<'_>, &str>> for ParseError {
+    fn from(error: lalrpop_util::ParseError<usize, Token<'_>, &str>) -> Self {
+        use lalrpop_util::ParseError::*;
+
+        match error {
+            InvalidToken { location } => ParseError::InvalidToken(Span {
+                start: location,
+                end: location + 1,
+            }),
+            UnrecognizedEof { location, expected } => ParseError::UnexpectedEof(
+                describe_expected(expected),
+                Span {
+                    start: location,
+                    end: location,
+                },
+            ),
+            UnrecognizedToken {
+                token: (start, token, end),
+                expected,
+            } => ParseError::UnexpectedToken(
+                token.1.to_string(),
+                describe_expected(expected),
+                Span { start, end },
+            ),
+            ExtraToken {
+                token: (start, token, end),
+            } => ParseError::ExtraToken(token.1.to_string(), Span { start, end }),
+            // The grammar doesn't raise any errors of its own.
+            User { .. } => unreachable!(),
+        }
+    }
+}
+
+// Terminal names from the parser are either quoted literals or the regexes from the grammar's
+// `match` block, neither of which reads well in a diagnostic.
+fn describe_expected(expected: Vec<String>) -> Vec<String> {
+    expected
+        .iter()
+        .map(|terminal| match terminal.as_str() {
+            r##"r#"\"[^\"]*\""#"## => "a string".to_string(),
+            r##"r#"[0-9]+"#"## => "an integer".to_string(),
+            r##"r#"[0-9]+\\.[0-9]+"#"## => "a float".to_string(),
+            r##"r#"[a-zA-Z_][a-zA-Z_0-9]*"#"## => "an identifier".to_string(),
+            literal => format!("`{}`", literal.trim_matches('"')),
+        })
+        .collect()
+}
+
+fn join_expected(expected: &[String]) -> String {
+    match expected {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} or {last}", rest.join(", ")),
+    }
+}
+
+pub struct ParseErrorDisplay {
+    error: ParseError,
+    file_name: String,
+    source: String,
+}
+
+impl std::fmt::Display for ParseErrorDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // 1. Header
+        writeln!(f, "{}", self.error.message())?;
+
+        // 2. Location Frame
+        write_location_frame(f, &self.file_name, &self.source, self.error.span())?;
+
+        // 3. Diagnostic Detail
+        if let Some(detail) = self.error.detail() {
+            writeln!(f, "{detail}")?;
+        }
+
+        if let Some(hint) = self.error.hint(&self.source) {
+            writeln!(f, "Hint: {hint}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,20 +1,187 @@
-use super::Error;
+use crate::{ast::Span, state::exec_context::CallFrame};
+
+use super::{location_frame::write_location_frame, Error};
 
 #[derive(Debug)]
-pub struct RuntimeError {
-    pub message: String,
+pub enum RuntimeError {
+    AssertionFailed,
+    AssertionNotEqual(String, String),
+    BinaryOpOverflow(String, i32, i32, Span),
+    CallDepthExceeded(usize, Span),
+    DivisionZero(String, i32, Span),
+    // Raised by `exit`, which stops the program the same way any error would.
+    Exit(i32),
+    ExitCodeOutOfRange(i32),
+    Interrupted,
+    Io(String, String),
+    ScopeBindingAlreadyExists(String, Span),
+    ScopeBindingNotFound(String, Span),
+    StackLimitExceeded(usize),
+    StepLimitExceeded(u64),
+    UnaryOpOverflow(String, i32, Span),
+    ValueSizeExceeded(usize, Span),
 }
 
-impl Error for RuntimeError {
-    fn scope_binding_not_found(ident: &str) -> Self {
-        RuntimeError {
-            message: format!("Binding for identifier \"{ident}\" not found in scope."),
+impl RuntimeError {
+    /// Renders the error followed by a backtrace of the calls which led to it, innermost first.
+    pub fn display(
+        self,
+        file_name: String,
+        source: String,
+        backtrace: Vec<CallFrame>,
+    ) -> RuntimeErrorDisplay {
+        RuntimeErrorDisplay {
+            error: self,
+            file_name,
+            source,
+            backtrace,
+        }
+    }
+
+    // Errors raised outside of the program itself, such as failed IO, running out of steps or being
+    // interrupted, have no location.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RuntimeError::BinaryOpOverflow(_, _, _, span)
+            | RuntimeError::CallDepthExceeded(_, span)
+            | RuntimeError::DivisionZero(_, _, span)
+            | RuntimeError::ScopeBindingAlreadyExists(_, span)
+            | RuntimeError::ScopeBindingNotFound(_, span)
+            | RuntimeError::UnaryOpOverflow(_, _, span)
+            | RuntimeError::ValueSizeExceeded(_, span) => Some(*span),
+            // Assertions are builtins, so the backtrace shows where they were called from.
+            RuntimeError::AssertionFailed
+            | RuntimeError::AssertionNotEqual(..)
+            | RuntimeError::Exit(_)
+            | RuntimeError::ExitCodeOutOfRange(_)
+            | RuntimeError::Interrupted
+            | RuntimeError::Io(..)
+            | RuntimeError::StackLimitExceeded(_)
+            | RuntimeError::StepLimitExceeded(_) => None,
+        }
+    }
+
+    /// A code which identifies the kind of error. Codes are never reused or renumbered, so tools
+    /// can rely on them; new kinds of error take the next free number.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeError::BinaryOpOverflow(..) => "E0301",
+            RuntimeError::CallDepthExceeded(..) => "E0302",
+            RuntimeError::DivisionZero(..) => "E0303",
+            RuntimeError::Interrupted => "E0304",
+            RuntimeError::Io(..) => "E0305",
+            RuntimeError::ScopeBindingAlreadyExists(..) => "E0306",
+            RuntimeError::ScopeBindingNotFound(..) => "E0307",
+            RuntimeError::StackLimitExceeded(_) => "E0308",
+            RuntimeError::StepLimitExceeded(_) => "E0309",
+            RuntimeError::UnaryOpOverflow(..) => "E0310",
+            RuntimeError::ValueSizeExceeded(..) => "E0311",
+            RuntimeError::AssertionFailed => "E0312",
+            RuntimeError::AssertionNotEqual(..) => "E0313",
+            RuntimeError::Exit(_) => "E0314",
+            RuntimeError::ExitCodeOutOfRange(_) => "E0315",
         }
     }
 
-    fn scope_binding_already_exists(ident: &str) -> Self {
-        RuntimeError {
-            message: format!("Scope binding already exists for identifier \"{ident}\"."),
+    /// What went wrong, in a sentence.
+    pub fn message(&self) -> String {
+        match self {
+            RuntimeError::AssertionFailed => String::from("Assertion failed."),
+            RuntimeError::AssertionNotEqual(..) => {
+                String::from("Assertion failed: values are not equal.")
+            }
+            RuntimeError::BinaryOpOverflow(op, ..) => {
+                format!("Integer overflow in binary operation {op}.")
+            }
+            RuntimeError::CallDepthExceeded(max, _) => {
+                format!("Exceeded the limit of {max} calls in progress.")
+            }
+            RuntimeError::DivisionZero(..) => String::from("Cannot divide by 0."),
+            RuntimeError::Exit(code) => format!("Exited with code {code}."),
+            RuntimeError::ExitCodeOutOfRange(code) => {
+                format!("Exit code {code} is out of range, as codes run from 0 to 255.")
+            }
+            RuntimeError::Interrupted => String::from("Interrupted."),
+            RuntimeError::Io(op, message) => format!("Failed to {op}: {message}"),
+            RuntimeError::ScopeBindingAlreadyExists(ident, _) => {
+                format!("Scope binding already exists for identifier \"{ident}\".")
+            }
+            RuntimeError::ScopeBindingNotFound(ident, _) => {
+                format!("Binding for identifier \"{ident}\" not found in scope.")
+            }
+            RuntimeError::StackLimitExceeded(max) => {
+                format!("Exceeded the limit of {max} stack entries.")
+            }
+            RuntimeError::StepLimitExceeded(max) => format!("Exceeded the limit of {max} steps."),
+            RuntimeError::UnaryOpOverflow(op, ..) => {
+                format!("Integer overflow in unary operation {op}.")
+            }
+            RuntimeError::ValueSizeExceeded(max, _) => {
+                format!("Exceeded the limit of {max} bytes for a single value.")
+            }
+        }
+    }
+
+    /// More about what went wrong, such as the values involved, for the errors which have any.
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            RuntimeError::AssertionNotEqual(left, right) => {
+                Some(format!("Left: {left}\nRight: {right}"))
+            }
+            RuntimeError::BinaryOpOverflow(op, left, right, _) => {
+                Some(format!("Operation: {left} {op} {right}"))
+            }
+            RuntimeError::DivisionZero(op, left, _) => Some(format!("Operation: {left} {op} 0")),
+            RuntimeError::UnaryOpOverflow(op, operand, _) => {
+                Some(format!("Operation: {op}{operand}"))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Error for RuntimeError {
+    fn scope_binding_not_found(ident: &str, span: Span) -> Self {
+        RuntimeError::ScopeBindingNotFound(ident.to_string(), span)
+    }
+
+    fn scope_binding_already_exists(ident: &str, span: Span) -> Self {
+        RuntimeError::ScopeBindingAlreadyExists(ident.to_string(), span)
+    }
+}
+
+pub struct RuntimeErrorDisplay {
+    error: RuntimeError,
+    file_name: String,
+    source: String,
+    backtrace: Vec<CallFrame>,
+}
+
+impl std::fmt::Display for RuntimeErrorDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // 1. Header
+        writeln!(f, "{}", self.error.message())?;
+
+        // 2. Location Frame
+        if let Some(span) = self.error.span() {
+            write_location_frame(f, &self.file_name, &self.source, span)?;
+        }
+
+        // 3. Diagnostic Detail
+        if let Some(detail) = self.error.detail() {
+            writeln!(f, "{detail}")?;
         }
+
+        // 4. Backtrace
+        for frame in self.backtrace.iter().rev() {
+            writeln!(f, "\nIn {}, called from:", frame.name)?;
+            write_location_frame(f, &self.file_name, &self.source, frame.call_site)?;
+
+            if frame.elided > 0 {
+                writeln!(f, "({} earlier tail calls not shown)", frame.elided)?;
+            }
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,21 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// Why an `ExecContext` couldn't be saved to or restored from a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    // The snapshot was taken by a different version of moss, or isn't a snapshot at all.
+    Incompatible,
+    Encoding(String),
+    // The snapshot was taken by this version, but is corrupt or cut short.
+    Decoding(String),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            SnapshotError::Incompatible => write!(f, "Snapshot was not taken by this version."),
+            SnapshotError::Encoding(message) => write!(f, "Failed to encode snapshot: {message}"),
+            SnapshotError::Decoding(message) => write!(f, "Failed to decode snapshot: {message}"),
+        }
+    }
+}
@@ -1,30 +1,30 @@
-use std::fmt::Formatter;
-
 use crate::{
     ast::{typed::typed_expr::TypedExpr, Span},
     typing::Type,
 };
 
-use super::Error;
+use super::{location_frame::write_location_frame, Error};
 
 #[derive(Debug)]
 pub enum TypeError {
-    AmbiguousListType,
-    AssignWrongType(Type, Type),
-    AssignImmutable(String),
-    AssignVoid,
-    BinaryOpWrongTypes(String, Type, Type),
-    DivisionZero,
-    ExpectedTypeReceivedList(Type),
+    AmbiguousListType(Span),
+    AssignWrongType(Type, Type, Span),
+    AssignImmutable(String, Span),
+    AssignVoid(Span),
+    BinaryOpOverflow(String, i32, i32, Span),
+    BinaryOpWrongTypes(String, Type, Type, Span),
+    DivisionZero(Span),
+    ExpectedTypeReceivedList(Type, Span),
     FuncWrongReturnType(Type, Type, Span),
-    IfElseBlockTypeMismatch(Type, Type),
-    IfElseConditionNonBool(Type),
-    InvokeNonFunc(Type),
+    IfElseBlockTypeMismatch(Type, Type, Span),
+    IfElseConditionNonBool(Type, Span),
+    InvokeNonFunc(Type, Span),
     InvokeWrongSignature(Vec<Type>, Vec<TypedExpr>, Span),
-    UnaryOpWrongType(String, Type),
-    ScopeBindingAlreadyExists(String),
-    ScopeBindingNotFound(String),
-    AppliedTypeWrongNumberArgs(String, usize, usize),
+    UnaryOpOverflow(String, i32, Span),
+    UnaryOpWrongType(String, Type, Span),
+    ScopeBindingAlreadyExists(String, Span),
+    ScopeBindingNotFound(String, Span),
+    AppliedTypeWrongNumberArgs(String, usize, usize, Span),
 }
 
 impl TypeError {
@@ -35,82 +35,126 @@ impl TypeError {
             source,
         }
     }
-}
 
-impl Error for TypeError {
-    fn scope_binding_already_exists(ident: &str) -> Self {
-        TypeError::ScopeBindingAlreadyExists(ident.to_string())
-    }
-    fn scope_binding_not_found(name: &str) -> Self {
-        TypeError::ScopeBindingNotFound(name.to_string())
+    pub fn span(&self) -> Span {
+        match self {
+            TypeError::AmbiguousListType(span)
+            | TypeError::AssignWrongType(_, _, span)
+            | TypeError::AssignImmutable(_, span)
+            | TypeError::AssignVoid(span)
+            | TypeError::BinaryOpOverflow(_, _, _, span)
+            | TypeError::BinaryOpWrongTypes(_, _, _, span)
+            | TypeError::DivisionZero(span)
+            | TypeError::ExpectedTypeReceivedList(_, span)
+            | TypeError::FuncWrongReturnType(_, _, span)
+            | TypeError::IfElseBlockTypeMismatch(_, _, span)
+            | TypeError::IfElseConditionNonBool(_, span)
+            | TypeError::InvokeNonFunc(_, span)
+            | TypeError::InvokeWrongSignature(_, _, span)
+            | TypeError::UnaryOpOverflow(_, _, span)
+            | TypeError::UnaryOpWrongType(_, _, span)
+            | TypeError::ScopeBindingAlreadyExists(_, span)
+            | TypeError::ScopeBindingNotFound(_, span)
+            | TypeError::AppliedTypeWrongNumberArgs(_, _, _, span) => *span,
+        }
     }
-}
 
-pub struct TypeErrorDisplay {
-    error: TypeError,
-    file_name: String,
-    source: String,
-}
-
-impl std::fmt::Display for TypeErrorDisplay {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match &self.error {
-            TypeError::AmbiguousListType => write!(f, "Cannot resolve list element type."),
-            TypeError::AppliedTypeWrongNumberArgs(ty, expected, received) => {
-                // 1. Header
-                writeln!(f, "Received wrong number of type arguments for type {ty}. ")?;
+    /// A code which identifies the kind of error. Codes are never reused or renumbered, so tools
+    /// can rely on them; new kinds of error take the next free number.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::AmbiguousListType(_) => "E0201",
+            TypeError::AssignWrongType(..) => "E0202",
+            TypeError::AssignImmutable(..) => "E0203",
+            TypeError::AssignVoid(_) => "E0204",
+            TypeError::BinaryOpOverflow(..) => "E0205",
+            TypeError::BinaryOpWrongTypes(..) => "E0206",
+            TypeError::DivisionZero(_) => "E0207",
+            TypeError::ExpectedTypeReceivedList(..) => "E0208",
+            TypeError::FuncWrongReturnType(..) => "E0209",
+            TypeError::IfElseBlockTypeMismatch(..) => "E0210",
+            TypeError::IfElseConditionNonBool(..) => "E0211",
+            TypeError::InvokeNonFunc(..) => "E0212",
+            TypeError::InvokeWrongSignature(..) => "E0213",
+            TypeError::UnaryOpOverflow(..) => "E0214",
+            TypeError::UnaryOpWrongType(..) => "E0215",
+            TypeError::ScopeBindingAlreadyExists(..) => "E0216",
+            TypeError::ScopeBindingNotFound(..) => "E0217",
+            TypeError::AppliedTypeWrongNumberArgs(..) => "E0218",
+        }
+    }
 
-                // 2. Dianostic Detail
-                writeln!(f, "Expected: {expected}\nReceived: {received}")
+    /// What went wrong, in a sentence.
+    pub fn message(&self) -> String {
+        match self {
+            TypeError::AmbiguousListType(_) => String::from("Cannot resolve list element type."),
+            TypeError::AppliedTypeWrongNumberArgs(ty, _, _, _) => {
+                format!("Received wrong number of type arguments for type {ty}.")
             }
-
-            TypeError::AssignImmutable(ident) => {
-                write!(f, "Cannot re-assign immutable binding \"{ident}\".")
+            TypeError::AssignImmutable(ident, _) => {
+                format!("Cannot re-assign immutable binding \"{ident}\".")
             }
-            TypeError::AssignWrongType(expected, received) => write!(
-                f,
-                "Cannot assign a value of type {received} where type {expected} is expected"
-            ),
-            TypeError::AssignVoid => write!(f, "Cannot assign a value of type Void."),
-            TypeError::BinaryOpWrongTypes(op, a, b) => {
-                write!(f, "Types {a} and {b} do not support binary operation {op}.")
-            }
-            TypeError::DivisionZero => write!(f, "Cannot divide by 0."),
-            TypeError::ExpectedTypeReceivedList(expected) => write!(
-                f,
-                "Expected a value of type {expected}, but received a list of unknown type."
-            ),
-            TypeError::FuncWrongReturnType(expected, received, span) => {
-                // 1. Header
-                writeln!(
-                    f,
-                    "Return type does not match declared return type in function signature."
-                )?;
-
-                // 2. Location Frame
-                write_location_frame(f, &self.file_name, &self.source, span.start, span.end)?;
-
-                // 3. Diagnostic Detail
-                writeln!(f, "Expected: {}\nReceived: {}", expected, received)
+            TypeError::AssignWrongType(expected, received, _) => {
+                format!(
+                    "Cannot assign a value of type {received} where type {expected} is expected."
+                )
             }
-            TypeError::IfElseBlockTypeMismatch(expected, received) => write!(
-                f,
-                "Type mismatch in if-else chain.\n\tExpected: {}\n\tReceived: {}",
-                expected, received
-            ),
-            TypeError::IfElseConditionNonBool(ty) => write!(
-                f,
-                "Expected conditional statement, but received expression of type {ty}"
+            TypeError::AssignVoid(_) => String::from("Cannot assign a value of type Void."),
+            TypeError::BinaryOpOverflow(op, ..) => {
+                format!("Constant binary operation {op} overflows.")
+            }
+            TypeError::BinaryOpWrongTypes(op, a, b, _) => {
+                format!("Types {a} and {b} do not support binary operation {op}.")
+            }
+            TypeError::DivisionZero(_) => String::from("Cannot divide by 0."),
+            TypeError::ExpectedTypeReceivedList(expected, _) => {
+                format!("Expected a value of type {expected}, but received a list of unknown type.")
+            }
+            TypeError::FuncWrongReturnType(..) => String::from(
+                "Return type does not match declared return type in function signature.",
             ),
-            TypeError::InvokeNonFunc(ty) => write!(f, "Cannot invoke non-function of type {ty}"),
-            TypeError::InvokeWrongSignature(param_types, args, span) => {
-                // 1. Header
-                writeln!(f, "Invoked function with the wrong signature.")?;
-
-                // 2. Location Frame
-                write_location_frame(f, &self.file_name, &self.source, span.start, span.end)?;
+            TypeError::IfElseBlockTypeMismatch(..) => {
+                String::from("Type mismatch in if-else chain.")
+            }
+            TypeError::IfElseConditionNonBool(ty, _) => {
+                format!("Expected conditional statement, but received expression of type {ty}.")
+            }
+            TypeError::InvokeNonFunc(ty, _) => {
+                format!("Cannot invoke non-function of type {ty}.")
+            }
+            TypeError::InvokeWrongSignature(..) => {
+                String::from("Invoked function with the wrong signature.")
+            }
+            TypeError::UnaryOpOverflow(op, ..) => {
+                format!("Constant unary operation {op} overflows.")
+            }
+            TypeError::UnaryOpWrongType(op, ty, _) => {
+                format!("Type {ty} does not support unary operation {op}.")
+            }
+            TypeError::ScopeBindingAlreadyExists(ident, _) => {
+                format!("Binding \"{ident}\" already exists in local scope.")
+            }
+            TypeError::ScopeBindingNotFound(ident, _) => {
+                format!("Binding \"{ident}\" not found in scope.")
+            }
+        }
+    }
 
-                // 3. Diagnostic Detail
+    /// More about what went wrong, such as the types involved, for the errors which have any.
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            TypeError::AppliedTypeWrongNumberArgs(_, expected, received, _) => {
+                Some(format!("Expected: {expected}\nReceived: {received}"))
+            }
+            TypeError::FuncWrongReturnType(expected, received, _)
+            | TypeError::IfElseBlockTypeMismatch(expected, received, _) => {
+                Some(format!("Expected: {expected}\nReceived: {received}"))
+            }
+            TypeError::BinaryOpOverflow(op, left, right, _) => {
+                Some(format!("Operation: {left} {op} {right}"))
+            }
+            TypeError::UnaryOpOverflow(op, operand, _) => Some(format!("Operation: {op}{operand}")),
+            TypeError::InvokeWrongSignature(param_types, args, _) => {
                 let param_types_list = param_types
                     .iter()
                     .map(|t| t.to_string())
@@ -123,40 +167,42 @@ impl std::fmt::Display for TypeErrorDisplay {
                     .collect::<Vec<_>>()
                     .join(", ");
 
-                writeln!(
-                    f,
+                Some(format!(
                     "Expected: ({param_types_list})\nReceived: ({arg_types_list})"
-                )
-            }
-            TypeError::UnaryOpWrongType(op, ty) => {
-                write!(f, "Type {ty} does not support unary operation {op}.")
-            }
-            TypeError::ScopeBindingAlreadyExists(ident) => {
-                write!(f, "Binding \"{ident}\" already exists in local scope.")
-            }
-            TypeError::ScopeBindingNotFound(ident) => {
-                write!(f, "Binding \"{ident}\" not found in scope.")
+                ))
             }
+            _ => None,
         }
     }
 }
 
-fn write_location_frame(
-    f: &mut Formatter,
-    file_name: &str,
-    source: &str,
-    start: usize,
-    end: usize,
-) -> std::fmt::Result {
-    let line_num = get_line_number(source, start);
-    let file_label = format!("{}:{line_num}", file_name);
-    let snippet = &source[start..end];
+impl Error for TypeError {
+    fn scope_binding_already_exists(ident: &str, span: Span) -> Self {
+        TypeError::ScopeBindingAlreadyExists(ident.to_string(), span)
+    }
+    fn scope_binding_not_found(name: &str, span: Span) -> Self {
+        TypeError::ScopeBindingNotFound(name.to_string(), span)
+    }
+}
 
-    writeln!(f, "{:-^width$}", file_label, width = 80)?;
-    writeln!(f, "{snippet}")?;
-    writeln!(f, "{:-<width$}", "", width = 80)
+pub struct TypeErrorDisplay {
+    error: TypeError,
+    file_name: String,
+    source: String,
 }
 
-fn get_line_number(source: &str, byte_offset: usize) -> usize {
-    source[..byte_offset].lines().count()
+impl std::fmt::Display for TypeErrorDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // 1. Header
+        writeln!(f, "{}", self.error.message())?;
+
+        // 2. Location Frame
+        write_location_frame(f, &self.file_name, &self.source, self.error.span())?;
+
+        // 3. Diagnostic Detail
+        match self.error.detail() {
+            Some(detail) => writeln!(f, "{detail}"),
+            None => Ok(()),
+        }
+    }
 }
@@ -0,0 +1,118 @@
+use crate::ast::Span;
+
+use super::location_frame::write_location_frame;
+
+/// The name under which a warning can be allowed or denied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Lint {
+    ConstantComparison,
+    EmptyBranch,
+    ShadowedBuiltin,
+    UnusedBinding,
+    UnusedMut,
+    UnusedParam,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 6] = [
+        Lint::ConstantComparison,
+        Lint::EmptyBranch,
+        Lint::ShadowedBuiltin,
+        Lint::UnusedBinding,
+        Lint::UnusedMut,
+        Lint::UnusedParam,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::ConstantComparison => "constant_comparison",
+            Lint::EmptyBranch => "empty_branch",
+            Lint::ShadowedBuiltin => "shadowed_builtin",
+            Lint::UnusedBinding => "unused_binding",
+            Lint::UnusedMut => "unused_mut",
+            Lint::UnusedParam => "unused_param",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Debug)]
+pub enum Warning {
+    ConstantComparison(bool, Span),
+    EmptyBranch(Span),
+    ShadowedBuiltin(String, Span),
+    UnusedBinding(String, Span),
+    UnusedMut(String, Span),
+    UnusedParam(String, Span),
+}
+
+impl Warning {
+    pub fn display(self, file_name: String, source: String) -> WarningDisplay {
+        WarningDisplay {
+            warning: self,
+            file_name,
+            source,
+        }
+    }
+
+    pub fn lint(&self) -> Lint {
+        match self {
+            Warning::ConstantComparison(..) => Lint::ConstantComparison,
+            Warning::EmptyBranch(..) => Lint::EmptyBranch,
+            Warning::ShadowedBuiltin(..) => Lint::ShadowedBuiltin,
+            Warning::UnusedBinding(..) => Lint::UnusedBinding,
+            Warning::UnusedMut(..) => Lint::UnusedMut,
+            Warning::UnusedParam(..) => Lint::UnusedParam,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Warning::ConstantComparison(_, span)
+            | Warning::EmptyBranch(span)
+            | Warning::ShadowedBuiltin(_, span)
+            | Warning::UnusedBinding(_, span)
+            | Warning::UnusedMut(_, span)
+            | Warning::UnusedParam(_, span) => *span,
+        }
+    }
+
+    /// What looks wrong, in a sentence.
+    pub fn message(&self) -> String {
+        match self {
+            Warning::ConstantComparison(result, _) => format!("Comparison is always {result}."),
+            Warning::EmptyBranch(_) => String::from("Branch is empty."),
+            Warning::ShadowedBuiltin(ident, _) => {
+                format!("Binding \"{ident}\" shadows a builtin function.")
+            }
+            Warning::UnusedBinding(ident, _) => format!("Binding \"{ident}\" is never used."),
+            Warning::UnusedMut(ident, _) => {
+                format!("Binding \"{ident}\" is declared mutable but never re-assigned.")
+            }
+            Warning::UnusedParam(ident, _) => format!("Parameter \"{ident}\" is never used."),
+        }
+    }
+}
+
+pub struct WarningDisplay {
+    warning: Warning,
+    file_name: String,
+    source: String,
+}
+
+impl std::fmt::Display for WarningDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // 1. Header
+        writeln!(f, "{}", self.warning.message())?;
+
+        // 2. Location Frame
+        write_location_frame(f, &self.file_name, &self.source, self.warning.span())?;
+
+        // 3. Diagnostic Detail
+        let lint = self.warning.lint().name();
+        writeln!(f, "Lint: {lint} (silence with `// allow({lint})`)")
+    }
+}
@@ -0,0 +1,604 @@
+use crate::{
+    ast::{
+        untyped::{Expr, FuncCall, FuncDeclare, Stmt},
+        Span,
+    },
+    errors::parse_error::ParseError,
+    parser::{
+        parse_program,
+        tokens::{find_token, tokenize},
+    },
+    typing::ProtoType,
+};
+
+const MAX_WIDTH: usize = 100;
+const INDENT: &str = "    ";
+
+/// Reprints a program in the canonical style: blocks always broken over lines and indented by four
+/// spaces, one statement per line, and argument, parameter and list items kept on one line when
+/// they fit, with one per line otherwise. An `else` holding nothing but another `if` becomes an
+/// `elif`. Comments are kept where they are, whether between statements, at the end of a line or
+/// between the items of a list, which then puts each item on a line of its own. Any others inside
+/// an expression move to before its statement.
+pub fn format_program(source: &str) -> Result<String, Vec<ParseError>> {
+    let program = parse_program(source)?;
+    let tokens = tokenize(source).map_err(|error| vec![error])?;
+
+    let Expr::Block(stmts, _) = &program else {
+        unreachable!();
+    };
+
+    let mut printer = Printer {
+        source,
+        tokens: tokens.into_iter().map(|(_, span)| span).collect(),
+        comments: find_comments(source).into_iter().map(Some).collect(),
+        out: String::new(),
+        indent: 0,
+    };
+
+    printer.stmts(stmts, source.len());
+
+    let formatted: Vec<_> = printer.out.lines().map(str::trim_end).collect();
+
+    Ok(format!("{}\n", formatted.join("\n").trim()))
+}
+
+#[derive(Clone)]
+struct Printer<'a> {
+    source: &'a str,
+    tokens: Vec<Span>,
+    // Each comment is taken once it's been printed.
+    comments: Vec<Option<Span>>,
+    out: String,
+    indent: usize,
+}
+
+impl<'a> Printer<'a> {
+    fn new_line(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    fn current_line(&self) -> &str {
+        let start = self.out.rfind('\n').map_or(0, |i| i + 1);
+        &self.out[start..]
+    }
+
+    // A copy to try out a layout on, starting from the current line so its width can be measured.
+    fn trial(&self) -> Self {
+        Self {
+            out: self.current_line().to_string(),
+            ..self.clone()
+        }
+    }
+
+    fn text(&self, span: Span) -> &'a str {
+        &self.source[span.start..span.end]
+    }
+
+    // The start of the last token before the position with the given text.
+    fn rfind_token(&self, before: usize, text: &str) -> usize {
+        let end = self.tokens.partition_point(|token| token.start < before);
+
+        self.tokens[..end]
+            .iter()
+            .rev()
+            .find(|token| self.text(**token) == text)
+            .map_or(0, |token| token.start)
+    }
+
+    fn take_comments(&mut self, start: usize, end: usize) -> Vec<Span> {
+        self.comments
+            .iter_mut()
+            .filter(|comment| comment.is_some_and(|c| c.start >= start && c.start < end))
+            .filter_map(Option::take)
+            .collect()
+    }
+
+    // The comments between a list's brackets, other than those in the blocks or lists within it.
+    fn take_list_comments(
+        &mut self,
+        open: usize,
+        close: usize,
+        nested: &[(usize, usize)],
+    ) -> Vec<Span> {
+        self.comments
+            .iter_mut()
+            .filter(|comment| {
+                comment.is_some_and(|c| {
+                    (open..close).contains(&c.start)
+                        && !nested
+                            .iter()
+                            .any(|(start, end)| (*start..*end).contains(&c.start))
+                })
+            })
+            .filter_map(Option::take)
+            .collect()
+    }
+
+    // Whether a comment comes after code on the same line, rather than on a line of its own.
+    fn is_trailing(&self, comment: Span) -> bool {
+        let line_start = self.source[..comment.start]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        !self.source[line_start..comment.start].trim().is_empty()
+    }
+
+    fn has_comments(&self, start: usize, end: usize) -> bool {
+        self.comments
+            .iter()
+            .flatten()
+            .any(|comment| comment.start >= start && comment.start < end)
+    }
+
+    // Starts a line for the next item in a block, keeping a blank line if there was one before it.
+    fn item_line(&mut self, last: Option<usize>, next: usize) {
+        // Hoisted comments come from after the statement they're put before.
+        if let Some(last) = last.filter(|last| *last < next) {
+            if self.source[last..next].matches('\n').count() > 1 {
+                self.out.push('\n');
+            }
+        }
+
+        self.new_line();
+    }
+
+    fn comment_lines(&mut self, comments: Vec<Span>, last: &mut Option<usize>) {
+        for comment in comments {
+            self.item_line(*last, comment.start);
+            self.out.push_str(self.text(comment));
+            *last = Some(comment.end);
+        }
+    }
+
+    // Prints each statement on a line of its own, along with the comments around them up until
+    // `end`, where the block closes.
+    fn stmts(&mut self, stmts: &[Stmt], end: usize) {
+        let mut last = None;
+
+        for stmt in stmts {
+            let span = stmt.expr.span();
+
+            let leading = self.take_comments(0, span.start);
+            self.comment_lines(leading, &mut last);
+
+            // Comments can only be printed between statements, so any inside an expression, other
+            // than in its blocks and lists, go before it instead.
+            let inner = self.nested_regions(&stmt.expr);
+
+            let hoisted: Vec<_> = self
+                .comments
+                .iter_mut()
+                .filter(|comment| {
+                    comment.is_some_and(|c| {
+                        (span.start..span.end).contains(&c.start)
+                            && !inner
+                                .iter()
+                                .any(|(open, close)| (*open..*close).contains(&c.start))
+                    })
+                })
+                .filter_map(Option::take)
+                .collect();
+            self.comment_lines(hoisted, &mut last);
+
+            self.item_line(last, span.start);
+            self.expr(&stmt.expr);
+            self.out.push(';');
+
+            let semicolon = find_token(self.source, &self.tokens, span.end, ";");
+            last = Some(semicolon + 1);
+
+            // A comment after the statement on the same line stays there, unless the block closes
+            // first, in which case it belongs to whatever comes after.
+            let line_end = self.source[semicolon..]
+                .find('\n')
+                .map_or(self.source.len(), |i| semicolon + i)
+                .min(end);
+
+            if let Some(trailing) = self.take_comments(semicolon, line_end).first() {
+                self.out.push(' ');
+                self.out.push_str(self.text(*trailing));
+                last = Some(trailing.end);
+            }
+        }
+
+        let closing = self.take_comments(0, end);
+        self.comment_lines(closing, &mut last);
+    }
+
+    // Where a block's braces are.
+    fn block_region(&self, block: &Expr) -> (usize, usize) {
+        let span = block.span();
+
+        (
+            self.rfind_token(span.start, "{"),
+            find_token(self.source, &self.tokens, span.end, "}"),
+        )
+    }
+
+    fn block(&mut self, block: &Expr) {
+        let Expr::Block(stmts, _) = block else {
+            unreachable!();
+        };
+
+        let (_, close) = self.block_region(block);
+
+        self.out.push('{');
+
+        let before = self.out.len();
+        self.indent += 1;
+        self.stmts(stmts, close);
+        self.indent -= 1;
+
+        if self.out.len() > before {
+            self.new_line();
+        }
+
+        self.out.push('}');
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Eq(l, r, _) => self.binary(l, "==", r),
+            Expr::Gt(l, r, _) => self.binary(l, ">", r),
+            Expr::Lt(l, r, _) => self.binary(l, "<", r),
+            Expr::Gte(l, r, _) => self.binary(l, ">=", r),
+            Expr::Lte(l, r, _) => self.binary(l, "<=", r),
+            Expr::Add(l, r, _) => self.binary(l, "+", r),
+            Expr::Sub(l, r, _) => self.binary(l, "-", r),
+            Expr::Mult(l, r, _) => self.binary(l, "*", r),
+            Expr::Div(l, r, _) => self.binary(l, "/", r),
+            Expr::Modulo(l, r, _) => self.binary(l, "%", r),
+            Expr::Negate(inner, _) => {
+                self.out.push('-');
+                self.expr(inner);
+            }
+            Expr::Assignment { ident, expr, .. } => {
+                self.out.push_str(&format!("{ident} = "));
+                self.expr(expr);
+            }
+            Expr::Declaration {
+                ident,
+                type_annotation,
+                expr,
+                is_mutable,
+                ..
+            } => {
+                self.out.push_str(if *is_mutable { "mut " } else { "let " });
+                self.out.push_str(ident);
+
+                if let Some(ty) = type_annotation {
+                    self.out.push_str(&format!(": {}", format_type(ty)));
+                }
+
+                self.out.push_str(" = ");
+                self.expr(expr);
+            }
+            Expr::FuncCall(call, span) => {
+                self.expr(&call.func);
+
+                let region = self.call_region(call, *span);
+                let comments =
+                    self.take_list_comments(region.0, region.1, &self.items_regions(&call.args));
+                self.list(
+                    "(",
+                    &call.args,
+                    ")",
+                    comments,
+                    |_, arg| arg.span(),
+                    Self::expr,
+                );
+            }
+            Expr::If(cond, then, _) => {
+                self.out.push_str("if ");
+                self.expr(cond);
+                self.out.push(' ');
+                self.block(then);
+            }
+            Expr::IfElse(cond, then, els, _) => {
+                self.out.push_str("if ");
+                self.expr(cond);
+                self.out.push(' ');
+                self.block(then);
+                self.else_branch(els);
+            }
+            Expr::Block(..) => self.block(expr),
+            Expr::Loop(body, _) => {
+                self.out.push_str("loop ");
+                self.block(body);
+            }
+            Expr::Break(_) => self.out.push_str("break"),
+            // Literals are kept as written, so a float like `1.50` isn't changed by reparsing it.
+            Expr::Literal(_, span) => self.out.push_str(self.text(*span)),
+            Expr::Identifier(ident, _) => self.out.push_str(ident),
+            Expr::FuncDeclare(func, span) => {
+                let (open, close) = if func.is_closure {
+                    ("|", "|")
+                } else {
+                    ("(", ")")
+                };
+                let region = self.params_region(func, *span);
+                let comments = self.take_list_comments(region.0, region.1, &[]);
+                let close = format!("{close}: {} => ", format_type(&func.return_type));
+
+                self.list(
+                    open,
+                    &func.params,
+                    &close,
+                    comments,
+                    |printer, (ident, ty)| Span {
+                        start: printer.rfind_token(ty.span().start, ident),
+                        end: ty.span().end,
+                    },
+                    |printer, (ident, ty)| {
+                        printer
+                            .out
+                            .push_str(&format!("{ident}: {}", format_type(ty)));
+                    },
+                );
+                self.block(&func.block);
+            }
+            Expr::List(items, span) => {
+                let region = self.list_region(*span);
+                let comments =
+                    self.take_list_comments(region.0, region.1, &self.items_regions(items));
+                self.list("[", items, "]", comments, |_, item| item.span(), Self::expr);
+            }
+            Expr::Test(name, body, _) => {
+                self.out.push_str(&format!("test \"{name}\" "));
+                self.block(body);
+            }
+        }
+    }
+
+    fn binary(&mut self, l: &Expr, op: &str, r: &Expr) {
+        self.expr(l);
+        self.out.push_str(&format!(" {op} "));
+        self.expr(r);
+    }
+
+    fn else_branch(&mut self, els: &Expr) {
+        match els {
+            Expr::If(cond, then, _) => {
+                self.out.push_str(" elif ");
+                self.expr(cond);
+                self.out.push(' ');
+                self.block(then);
+            }
+            Expr::IfElse(cond, then, els, _) => {
+                self.out.push_str(" elif ");
+                self.expr(cond);
+                self.out.push(' ');
+                self.block(then);
+                self.else_branch(els);
+            }
+            Expr::Block(stmts, _) if self.is_elif(els, stmts) => self.else_branch(&stmts[0].expr),
+            _ => {
+                self.out.push_str(" else ");
+                self.block(els);
+            }
+        }
+    }
+
+    // An `else` block holding only an `if`, and no comments of its own, reads better as an `elif`.
+    fn is_elif(&self, block: &Expr, stmts: &[Stmt]) -> bool {
+        let [stmt] = stmts else {
+            return false;
+        };
+
+        if !matches!(stmt.expr, Expr::If(..) | Expr::IfElse(..)) {
+            return false;
+        }
+
+        let (open, close) = self.block_region(block);
+        let span = stmt.expr.span();
+
+        !self.has_comments(open, span.start) && !self.has_comments(span.end, close)
+    }
+
+    // Prints the items on one line if they fit, or if all but the last do and only the last runs
+    // over several lines, as a function passed last would. Otherwise each goes on its own line, as
+    // they also do when there are comments between them, which are kept after the item they follow
+    // on the same line, or on a line of their own before the item they precede.
+    fn list<T>(
+        &mut self,
+        open: &str,
+        items: &[T],
+        close: &str,
+        comments: Vec<Span>,
+        span: impl Fn(&Self, &T) -> Span,
+        item: impl Fn(&mut Self, &T),
+    ) {
+        self.out.push_str(open);
+
+        if comments.is_empty() {
+            let mut trial = self.trial();
+            let prefix = trial.out.len();
+            let mut last_start = prefix;
+
+            for (i, it) in items.iter().enumerate() {
+                if i > 0 {
+                    trial.out.push_str(", ");
+                }
+                last_start = trial.out.len();
+                item(&mut trial, it);
+            }
+            trial.out.push_str(close);
+
+            let first_line = trial.out.lines().next().unwrap_or_default();
+            // Leaving room for whatever punctuation follows.
+            let fits =
+                !trial.out[..last_start].contains('\n') && first_line.chars().count() < MAX_WIDTH;
+
+            if fits || items.is_empty() {
+                self.out.push_str(&trial.out[prefix..]);
+                self.comments = trial.comments;
+                return;
+            }
+        }
+
+        let spans: Vec<_> = items.iter().map(|it| span(self, it)).collect();
+
+        // Which item a comment ends the line of, if any, and which it's on a line before, if any.
+        let follows = |comment: &Span| spans.iter().rposition(|s| s.start < comment.start);
+        let precedes = |comment: &Span| spans.iter().position(|s| s.end > comment.start);
+
+        let (trailing, leading): (Vec<_>, Vec<_>) =
+            comments.into_iter().partition(|c| self.is_trailing(*c));
+
+        self.indent += 1;
+        self.trailing_comments(&trailing, |c| follows(c).is_none());
+
+        for (i, it) in items.iter().enumerate() {
+            self.leading_comments(&leading, |c| precedes(c) == Some(i));
+
+            self.new_line();
+            item(self, it);
+
+            if i + 1 < items.len() {
+                self.out.push(',');
+            }
+
+            self.trailing_comments(&trailing, |c| follows(c) == Some(i));
+        }
+
+        self.leading_comments(&leading, |c| precedes(c).is_none());
+        self.indent -= 1;
+
+        self.new_line();
+        self.out.push_str(close);
+    }
+
+    // Puts the first of the comments at the end of the current line, and any more after it on lines
+    // of their own.
+    fn trailing_comments(&mut self, comments: &[Span], pick: impl Fn(&Span) -> bool) {
+        for (i, comment) in comments.iter().filter(|c| pick(c)).enumerate() {
+            if i == 0 {
+                self.out.push(' ');
+            } else {
+                self.new_line();
+            }
+            self.out.push_str(self.text(*comment));
+        }
+    }
+
+    fn leading_comments(&mut self, comments: &[Span], pick: impl Fn(&Span) -> bool) {
+        for comment in comments.iter().filter(|c| pick(c)) {
+            self.new_line();
+            self.out.push_str(self.text(*comment));
+        }
+    }
+
+    // Where the parentheses around a call's arguments are.
+    fn call_region(&self, call: &FuncCall, span: Span) -> (usize, usize) {
+        (
+            find_token(self.source, &self.tokens, call.func.span().end, "("),
+            self.rfind_token(span.end, ")"),
+        )
+    }
+
+    // Where the brackets of a list are.
+    fn list_region(&self, span: Span) -> (usize, usize) {
+        (span.start, self.rfind_token(span.end, "]"))
+    }
+
+    // Where the parentheses or bars around a function's parameters are.
+    fn params_region(&self, func: &FuncDeclare, span: Span) -> (usize, usize) {
+        let close = if func.is_closure { "|" } else { ")" };
+
+        (
+            span.start,
+            find_token(self.source, &self.tokens, span.start + 1, close),
+        )
+    }
+
+    fn items_regions(&self, items: &[Expr]) -> Vec<(usize, usize)> {
+        items
+            .iter()
+            .flat_map(|item| self.nested_regions(item))
+            .collect()
+    }
+
+    // Where the blocks and lists directly within an expression are, not counting those nested in
+    // them. Comments in them are printed along with them.
+    fn nested_regions(&self, expr: &Expr) -> Vec<(usize, usize)> {
+        match expr {
+            Expr::Block(..) => vec![self.block_region(expr)],
+            Expr::Eq(l, r, _)
+            | Expr::Gt(l, r, _)
+            | Expr::Lt(l, r, _)
+            | Expr::Gte(l, r, _)
+            | Expr::Lte(l, r, _)
+            | Expr::Add(l, r, _)
+            | Expr::Sub(l, r, _)
+            | Expr::Mult(l, r, _)
+            | Expr::Div(l, r, _)
+            | Expr::Modulo(l, r, _) => [self.nested_regions(l), self.nested_regions(r)].concat(),
+            Expr::Negate(inner, _) => self.nested_regions(inner),
+            Expr::Assignment { expr, .. } | Expr::Declaration { expr, .. } => {
+                self.nested_regions(expr)
+            }
+            Expr::FuncCall(call, span) => [
+                self.nested_regions(&call.func),
+                vec![self.call_region(call, *span)],
+            ]
+            .concat(),
+            Expr::If(cond, then, _) => {
+                [self.nested_regions(cond), vec![self.block_region(then)]].concat()
+            }
+            Expr::IfElse(cond, then, els, _) => [
+                self.nested_regions(cond),
+                vec![self.block_region(then)],
+                self.nested_regions(els),
+            ]
+            .concat(),
+            Expr::Loop(body, _) | Expr::Test(_, body, _) => vec![self.block_region(body)],
+            Expr::FuncDeclare(func, span) => vec![
+                self.params_region(func, *span),
+                self.block_region(&func.block),
+            ],
+            Expr::List(_, span) => vec![self.list_region(*span)],
+            Expr::Break(_) | Expr::Literal(..) | Expr::Identifier(..) => vec![],
+        }
+    }
+}
+
+fn format_type(ty: &ProtoType) -> String {
+    match ty {
+        ProtoType::Atomic(ident, _) => ident.clone(),
+        ProtoType::Applied(base, args, _) => {
+            let args: Vec<_> = args.iter().map(format_type).collect();
+            format!("{base}<{}>", args.join(", "))
+        }
+    }
+}
+
+// Finds every `//` comment outside of a string, up to the end of its line.
+fn find_comments(source: &str) -> Vec<Span> {
+    let mut comments = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                chars.by_ref().find(|(_, c)| *c == '"');
+            }
+            '/' if chars.peek().is_some_and(|(_, c)| *c == '/') => {
+                let end = source[i..].find('\n').map_or(source.len(), |n| i + n);
+                let text = source[i..end].trim_end();
+
+                comments.push(Span {
+                    start: i,
+                    end: i + text.len(),
+                });
+
+                while chars.peek().is_some_and(|(j, _)| *j < end) {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    comments
+}
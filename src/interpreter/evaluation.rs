@@ -1,27 +1,37 @@
 use std::{
     collections::HashMap,
     io::{Read, Write},
+    rc::Rc,
 };
 
 use crate::{
-    ast::typed::{typed_expr::TypedExpr, TypedFunc, TypedLiteral},
+    ast::{
+        typed::{typed_expr::TypedExpr, TypedFunc, TypedLiteral},
+        Span,
+    },
     builtins::{BuiltinFunc, BuiltinFuncId},
     errors::runtime_error::RuntimeError,
+    scopes::scope_stack::Slot,
     state::{
-        control_flow::ControlFlow, control_op::ControlOp, exec_context::ExecContext,
+        control_flow::ControlFlow,
+        control_op::ControlOp,
+        exec_context::{ArithmeticMode, CallFrame, ExecContext},
         io_context::IoContext,
     },
 };
 
 use super::{
     apply_binary_op, apply_unary_op, mark_loop, push_binary_op, push_block, push_func_call,
-    push_if, push_if_else, push_unary_op, resolved_value::ResolvedValue,
+    push_if, push_if_else, push_unary_op, resolved_value::ResolvedValue, try_apply_binary_op,
 };
 
+// A statement which isn't `Void` ends its block with its value. Otherwise the value is dropped, so
+// a block only ever leaves its own value behind.
 pub fn apply_stmt(exec: &mut ExecContext) -> ControlFlow {
     let value = exec.value_stack.last().unwrap();
 
     if let ResolvedValue::Void = value {
+        exec.value_stack.pop();
         ControlFlow::Continue
     } else {
         ControlFlow::Return
@@ -36,44 +46,115 @@ pub fn eval_expr<R: Read, W: Write>(
 ) -> Result<ControlFlow, RuntimeError> {
     let control_flow = match expr {
         // Binary operations
-        TypedExpr::Eq(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyEq, l, r),
-        TypedExpr::Gt(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyGt, l, r),
-        TypedExpr::Lt(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyLt, l, r),
-        TypedExpr::Gte(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyGte, l, r),
-        TypedExpr::Lte(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyLte, l, r),
-        TypedExpr::Add(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyAdd, l, r),
-        TypedExpr::Sub(l, r, _ty) => push_binary_op(exec, ControlOp::ApplySub, l, r),
-        TypedExpr::Mult(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyMult, l, r),
-        TypedExpr::Div(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyDiv, l, r),
-        TypedExpr::Modulo(l, r, _ty) => push_binary_op(exec, ControlOp::ApplyModulo, l, r),
+        TypedExpr::Eq(l, r, _ty, _span) => push_binary_op(
+            exec,
+            ControlOp::ApplyEq,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Gt(l, r, _ty, _span) => push_binary_op(
+            exec,
+            ControlOp::ApplyGt,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Lt(l, r, _ty, _span) => push_binary_op(
+            exec,
+            ControlOp::ApplyLt,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Gte(l, r, _ty, _span) => push_binary_op(
+            exec,
+            ControlOp::ApplyGte,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Lte(l, r, _ty, _span) => push_binary_op(
+            exec,
+            ControlOp::ApplyLte,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Add(l, r, _ty, span) => push_binary_op(
+            exec,
+            ControlOp::ApplyAdd(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Sub(l, r, _ty, span) => push_binary_op(
+            exec,
+            ControlOp::ApplySub(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Mult(l, r, _ty, span) => push_binary_op(
+            exec,
+            ControlOp::ApplyMult(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Div(l, r, _ty, span) => push_binary_op(
+            exec,
+            ControlOp::ApplyDiv(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
+        TypedExpr::Modulo(l, r, _ty, span) => push_binary_op(
+            exec,
+            ControlOp::ApplyModulo(span),
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+        ),
 
         // Unary operations
-        TypedExpr::Negate(l, _ty) => push_unary_op(exec, ControlOp::ApplyNegate, *l),
-        TypedExpr::Assignment { ident, expr } => {
-            push_unary_op(exec, ControlOp::ApplyAssignment(ident), *expr)
+        TypedExpr::Negate(l, _ty, span) => {
+            push_unary_op(exec, ControlOp::ApplyNegate(span), Rc::unwrap_or_clone(l))
         }
+        TypedExpr::Assignment {
+            ident,
+            slot,
+            expr,
+            span,
+        } => push_unary_op(
+            exec,
+            ControlOp::ApplyAssignment(ident, slot, span),
+            Rc::unwrap_or_clone(expr),
+        ),
         TypedExpr::Declaration {
             ident,
             is_mutable,
             expr,
+            span,
             ..
-        } => push_unary_op(exec, ControlOp::ApplyDeclaration(ident, is_mutable), *expr),
+        } => push_unary_op(
+            exec,
+            ControlOp::ApplyDeclaration(ident, is_mutable, span),
+            Rc::unwrap_or_clone(expr),
+        ),
 
         // Postfix operations
-        TypedExpr::FuncCall(func, _ty) => push_func_call(exec, func),
+        TypedExpr::FuncCall(func, _ty, span) => push_func_call(exec, func, span),
 
         // Control flow
-        TypedExpr::If(cond, then, _ty) => push_if(exec, *cond, then),
-        TypedExpr::IfElse(cond, then, els, _ty) => push_if_else(exec, *cond, then, els),
-        TypedExpr::Block(block) => push_block(exec, io, builtins, TypedExpr::Block(block))?,
-        TypedExpr::Loop(block) => mark_loop(exec, *block),
-        TypedExpr::Break => ControlFlow::Break,
+        TypedExpr::If(cond, then, _ty, _span) => push_if(exec, Rc::unwrap_or_clone(cond), then),
+        TypedExpr::IfElse(cond, then, els, _ty, _span) => {
+            push_if_else(exec, Rc::unwrap_or_clone(cond), then, els)
+        }
+        TypedExpr::Block(block, span) => {
+            push_block(exec, io, builtins, Rc::new(TypedExpr::Block(block, span)))?
+        }
+        TypedExpr::Loop(block, _span) => mark_loop(exec, block),
+        TypedExpr::Break(_span) => ControlFlow::Break,
 
         // Primaries
-        TypedExpr::Literal(literal, _ty) => eval_literal(exec, literal),
-        TypedExpr::Identifier(ident, _ty) => eval_identifier(exec, ident)?,
-        TypedExpr::FuncDeclare(func, _ty) => eval_func_declare(exec, func),
-        TypedExpr::List(items, _ty) => eval_list(exec, items),
+        TypedExpr::Literal(literal, _ty, _span) => eval_literal(exec, literal),
+        TypedExpr::Identifier(ident, slot, _ty, span) => eval_identifier(exec, ident, slot, span)?,
+        TypedExpr::FuncDeclare(func, _ty, _span) => eval_func_declare(exec, func),
+        TypedExpr::List(items, _ty, span) => eval_list(exec, items, span),
+
+        // Programs with type errors are never executed.
+        TypedExpr::Unknown(_span) => unreachable!(),
     };
 
     Ok(control_flow)
@@ -81,56 +162,105 @@ pub fn eval_expr<R: Read, W: Write>(
 
 // Binary operations
 
-pub fn apply_add(exec: &mut ExecContext) -> ControlFlow {
-    apply_binary_op(exec, |l, r| match (l, r) {
-        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => ResolvedValue::Int(l + r),
-        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => ResolvedValue::Float(l + r),
-        (ResolvedValue::String(l), ResolvedValue::String(r)) => ResolvedValue::String(l + &r),
+pub fn apply_add(exec: &mut ExecContext, span: Span) -> Result<ControlFlow, RuntimeError> {
+    let mode = exec.arithmetic;
+
+    try_apply_binary_op(exec, |l, r| match (l, r) {
+        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => {
+            eval_int_op(mode, "+", l, r, span, i32::checked_add, i32::wrapping_add)
+        }
+        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => Ok(ResolvedValue::Float(l + r)),
+        (ResolvedValue::String(l), ResolvedValue::String(r)) => Ok(ResolvedValue::String(l + &r)),
         _ => unreachable!(),
-    });
+    })?;
 
-    ControlFlow::Continue
+    exec.check_value_size(exec.value_stack.last().unwrap(), span)?;
+
+    Ok(ControlFlow::Continue)
 }
 
-pub fn apply_sub(exec: &mut ExecContext) -> ControlFlow {
-    apply_binary_op(exec, |l, r| match (l, r) {
-        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => ResolvedValue::Int(l - r),
-        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => ResolvedValue::Float(l - r),
+pub fn apply_sub(exec: &mut ExecContext, span: Span) -> Result<ControlFlow, RuntimeError> {
+    let mode = exec.arithmetic;
+
+    try_apply_binary_op(exec, |l, r| match (l, r) {
+        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => {
+            eval_int_op(mode, "-", l, r, span, i32::checked_sub, i32::wrapping_sub)
+        }
+        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => Ok(ResolvedValue::Float(l - r)),
         _ => unreachable!(),
-    });
+    })?;
 
-    ControlFlow::Continue
+    Ok(ControlFlow::Continue)
 }
 
-pub fn apply_mult(exec: &mut ExecContext) -> ControlFlow {
-    apply_binary_op(exec, |l, r| match (l, r) {
-        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => ResolvedValue::Int(l * r),
-        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => ResolvedValue::Float(l * r),
+pub fn apply_mult(exec: &mut ExecContext, span: Span) -> Result<ControlFlow, RuntimeError> {
+    let mode = exec.arithmetic;
+
+    try_apply_binary_op(exec, |l, r| match (l, r) {
+        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => {
+            eval_int_op(mode, "*", l, r, span, i32::checked_mul, i32::wrapping_mul)
+        }
+        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => Ok(ResolvedValue::Float(l * r)),
         _ => unreachable!(),
-    });
+    })?;
 
-    ControlFlow::Continue
+    Ok(ControlFlow::Continue)
 }
 
-pub fn apply_div(exec: &mut ExecContext) -> ControlFlow {
-    apply_binary_op(exec, |l, r| match (l, r) {
-        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => ResolvedValue::Int(l / r),
-        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => ResolvedValue::Float(l / r),
+pub fn apply_div(exec: &mut ExecContext, span: Span) -> Result<ControlFlow, RuntimeError> {
+    let mode = exec.arithmetic;
+
+    try_apply_binary_op(exec, |l, r| match (l, r) {
+        (ResolvedValue::Int(l), ResolvedValue::Int(0)) => {
+            Err(RuntimeError::DivisionZero("/".to_string(), l, span))
+        }
+        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => {
+            eval_int_op(mode, "/", l, r, span, i32::checked_div, i32::wrapping_div)
+        }
+        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => Ok(ResolvedValue::Float(l / r)),
         _ => unreachable!(),
-    });
+    })?;
 
-    ControlFlow::Continue
+    Ok(ControlFlow::Continue)
 }
 
-pub fn apply_modulo(exec: &mut ExecContext) -> ControlFlow {
-    apply_binary_op(exec, |l, r| match (l, r) {
-        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => ResolvedValue::Int(l % r),
-        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => ResolvedValue::Float(l % r),
+pub fn apply_modulo(exec: &mut ExecContext, span: Span) -> Result<ControlFlow, RuntimeError> {
+    let mode = exec.arithmetic;
+
+    try_apply_binary_op(exec, |l, r| match (l, r) {
+        (ResolvedValue::Int(l), ResolvedValue::Int(0)) => {
+            Err(RuntimeError::DivisionZero("%".to_string(), l, span))
+        }
+        (ResolvedValue::Int(l), ResolvedValue::Int(r)) => {
+            eval_int_op(mode, "%", l, r, span, i32::checked_rem, i32::wrapping_rem)
+        }
+        (ResolvedValue::Float(l), ResolvedValue::Float(r)) => Ok(ResolvedValue::Float(l % r)),
         _ => unreachable!(),
-    });
+    })?;
 
-    ControlFlow::Continue
+    Ok(ControlFlow::Continue)
+}
+
+// Integer results which don't fit in an `Int` are an error, unless the program opted into
+// wrapping arithmetic. Division by zero is checked by the caller, since it can't wrap.
+fn eval_int_op(
+    mode: ArithmeticMode,
+    op: &str,
+    l: i32,
+    r: i32,
+    span: Span,
+    checked: fn(i32, i32) -> Option<i32>,
+    wrapping: fn(i32, i32) -> i32,
+) -> Result<ResolvedValue, RuntimeError> {
+    let result = match mode {
+        ArithmeticMode::Checked => checked(l, r)
+            .ok_or_else(|| RuntimeError::BinaryOpOverflow(op.to_string(), l, r, span))?,
+        ArithmeticMode::Wrapping => wrapping(l, r),
+    };
+
+    Ok(ResolvedValue::Int(result))
 }
+
 pub fn apply_eq(exec: &mut ExecContext) -> ControlFlow {
     apply_binary_op(exec, |l, r| match (l, r) {
         (ResolvedValue::Int(l), ResolvedValue::Int(r)) => ResolvedValue::Bool(l == r),
@@ -184,9 +314,15 @@ pub fn apply_lte(exec: &mut ExecContext) -> ControlFlow {
 }
 
 // Unary operations
-pub fn apply_negate(exec: &mut ExecContext) -> Result<ControlFlow, RuntimeError> {
-    apply_unary_op(exec, |_scope_stack, v| match v {
-        ResolvedValue::Int(int) => Ok(ResolvedValue::Int(-int)),
+pub fn apply_negate(exec: &mut ExecContext, span: Span) -> Result<ControlFlow, RuntimeError> {
+    apply_unary_op(exec, |exec, v| match v {
+        ResolvedValue::Int(int) => match exec.arithmetic {
+            ArithmeticMode::Checked => int
+                .checked_neg()
+                .map(ResolvedValue::Int)
+                .ok_or_else(|| RuntimeError::UnaryOpOverflow("-".to_string(), int, span)),
+            ArithmeticMode::Wrapping => Ok(ResolvedValue::Int(int.wrapping_neg())),
+        },
         ResolvedValue::Float(float) => Ok(ResolvedValue::Float(-float)),
         _ => unreachable!(),
     })?;
@@ -197,11 +333,16 @@ pub fn apply_negate(exec: &mut ExecContext) -> Result<ControlFlow, RuntimeError>
 pub fn apply_assignment(
     exec: &mut ExecContext,
     ident: String,
+    slot: Option<Slot>,
+    span: Span,
 ) -> Result<ControlFlow, RuntimeError> {
     apply_unary_op(exec, |exec, v| {
-        exec.scope_stack
-            .mutate(&ident, v)
-            .map(|_| ResolvedValue::Void)
+        match slot {
+            Some(slot) => exec.scope_stack.mutate_slot(slot, v),
+            None => exec.scope_stack.mutate(&ident, v, span)?,
+        }
+
+        Ok(ResolvedValue::Void)
     })?;
 
     Ok(ControlFlow::Continue)
@@ -211,10 +352,11 @@ pub fn apply_declaration(
     exec: &mut ExecContext,
     is_mutable: bool,
     ident: String,
+    span: Span,
 ) -> Result<ControlFlow, RuntimeError> {
     apply_unary_op(exec, |exec, v| {
         exec.scope_stack
-            .insert(ident.clone(), is_mutable, v)
+            .insert(ident.clone(), is_mutable, v, span)
             .map(|_| ResolvedValue::Void)
     })?;
 
@@ -223,30 +365,56 @@ pub fn apply_declaration(
 
 // Postfix operations
 
-pub fn apply_func_call(exec: &mut ExecContext, args: Vec<TypedExpr>) -> ControlFlow {
+pub fn apply_func_call(
+    exec: &mut ExecContext,
+    args: Rc<[TypedExpr]>,
+    name: String,
+    is_tail: bool,
+    span: Span,
+) -> ControlFlow {
     let func = match exec.value_stack.pop().unwrap() {
         ResolvedValue::Func(func) => func,
         _ => unreachable!(),
     };
 
-    exec.control_stack.push(ControlOp::PopScope {
-        restore_previous_stack: !func.is_closure,
-    });
+    // Arguments are yet to be evaluated, so the stack is as the caller had it.
+    let frame = CallFrame {
+        value_base: exec.value_stack.len(),
+        declared_name: func.name.clone(),
+        declared_at: func.span,
+        ..CallFrame::new(name, span)
+    };
 
-    exec.control_stack
-        .push(ControlOp::EvalBlock(*func.block.clone()));
+    if is_tail {
+        exec.control_stack
+            .push(ControlOp::ReplaceFrame(func, frame));
 
-    for param in func.params.into_iter() {
-        let (param, _ty) = param;
-        exec.control_stack.push(ControlOp::ApplyBinding(param));
+        for arg in args.iter().rev() {
+            exec.control_stack.push(ControlOp::EvalExpr(arg.clone()));
+        }
+
+        return ControlFlow::Continue;
     }
 
-    exec.control_stack.push(ControlOp::PushScope {
-        create_new_stack: !func.is_closure,
+    exec.control_stack.push(ControlOp::PopCallFrame);
+    exec.control_stack.push(ControlOp::PopFrame);
+
+    exec.control_stack
+        .push(ControlOp::EvalBlock(func.block.clone()));
+
+    let params = func.params.iter().map(|(param, _ty)| param.clone());
+    exec.control_stack
+        .push(ControlOp::ApplyBindings(params.collect(), span));
+
+    exec.control_stack.push(ControlOp::PushFrame {
+        is_closure: func.is_closure,
     });
 
-    for arg in args.into_iter().rev() {
-        exec.control_stack.push(ControlOp::EvalExpr(arg));
+    // Arguments are evaluated in the caller, so the frame is only entered once they're done.
+    exec.control_stack.push(ControlOp::PushCallFrame(frame));
+
+    for arg in args.iter().rev() {
+        exec.control_stack.push(ControlOp::EvalExpr(arg.clone()));
     }
 
     ControlFlow::Continue
@@ -254,7 +422,11 @@ pub fn apply_func_call(exec: &mut ExecContext, args: Vec<TypedExpr>) -> ControlF
 
 // Post-evaluation construction
 
-pub fn apply_list(exec: &mut ExecContext, size: usize) -> ControlFlow {
+pub fn apply_list(
+    exec: &mut ExecContext,
+    size: usize,
+    span: Span,
+) -> Result<ControlFlow, RuntimeError> {
     let mut values = Vec::new();
 
     for _ in 0..size {
@@ -262,9 +434,12 @@ pub fn apply_list(exec: &mut ExecContext, size: usize) -> ControlFlow {
         values.push(item);
     }
 
-    exec.value_stack.push(ResolvedValue::List(values));
+    let list = ResolvedValue::List(values);
+    exec.check_value_size(&list, span)?;
 
-    ControlFlow::Continue
+    exec.value_stack.push(list);
+
+    Ok(ControlFlow::Continue)
 }
 
 // Primaries
@@ -279,26 +454,35 @@ pub fn eval_literal(exec: &mut ExecContext, literal: TypedLiteral) -> ControlFlo
     ControlFlow::Continue
 }
 
-pub fn eval_identifier(exec: &mut ExecContext, ident: String) -> Result<ControlFlow, RuntimeError> {
-    let entry = exec.scope_stack.lookup(&ident)?;
+pub fn eval_identifier(
+    exec: &mut ExecContext,
+    ident: String,
+    slot: Option<Slot>,
+    span: Span,
+) -> Result<ControlFlow, RuntimeError> {
+    let entry = match slot {
+        Some(slot) => exec.scope_stack.lookup_slot(slot),
+        None => exec.scope_stack.lookup(&ident, span)?,
+    };
+
     exec.value_stack.push(entry.value.clone());
 
     Ok(ControlFlow::Continue)
 }
 
 pub fn eval_func_declare(exec: &mut ExecContext, func: TypedFunc) -> ControlFlow {
-    exec.value_stack.push(ResolvedValue::Func(func));
+    exec.value_stack.push(ResolvedValue::Func(Rc::new(func)));
 
     ControlFlow::Continue
 }
 
-pub fn eval_list(exec: &mut ExecContext, items: Vec<TypedExpr>) -> ControlFlow {
+pub fn eval_list(exec: &mut ExecContext, items: Rc<[TypedExpr]>, span: Span) -> ControlFlow {
     let len = items.len();
 
-    exec.control_stack.push(ControlOp::ApplyList(len));
+    exec.control_stack.push(ControlOp::ApplyList(len, span));
 
-    for item in items.into_iter().rev() {
-        exec.control_stack.push(ControlOp::EvalExpr(item));
+    for item in items.iter().rev() {
+        exec.control_stack.push(ControlOp::EvalExpr(item.clone()));
     }
 
     ControlFlow::Continue
@@ -1,14 +1,20 @@
-mod evaluation;
+pub(crate) mod evaluation;
 pub mod resolved_value;
 
 use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::rc::Rc;
 
-use crate::ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedFuncCall, TypedStmt};
+use crate::ast::typed::{
+    typed_block::TypedBlock, typed_expr::TypedExpr, TypedFunc, TypedFuncCall, TypedStmt,
+};
+use crate::ast::Span;
 use crate::builtins::{BuiltinFunc, BuiltinFuncId};
 use crate::errors::runtime_error::RuntimeError;
 use crate::state::{
-    control_flow::ControlFlow, control_op::ControlOp, exec_context::ExecContext,
+    control_flow::ControlFlow,
+    control_op::ControlOp,
+    exec_context::{CallFrame, ExecContext},
     io_context::IoContext,
 };
 
@@ -19,96 +25,264 @@ use evaluation::{
 };
 use resolved_value::ResolvedValue;
 
+/// Where a program got to by the end of a call to `run_for`.
+#[derive(Debug)]
+pub enum RunState {
+    Finished(ResolvedValue),
+    // The program used up its steps. Everything needed to carry on is left in the `ExecContext`.
+    Suspended,
+}
+
+/// Lets a host, such as a debugger or profiler, look in on a program as the interpreter runs it.
+pub trait Inspector<R: Read, W: Write> {
+    /// Called before each statement is evaluated. Returning an error stops the program with it.
+    fn before_stmt(
+        &mut self,
+        _exec: &ExecContext,
+        _io: &mut IoContext<R, W>,
+        _stmt: &TypedStmt,
+    ) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    /// Called as each op is taken off the control stack, before it's applied. Returning an error
+    /// stops the program with it.
+    fn before_op(&mut self, _exec: &ExecContext, _op: &ControlOp) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    /// Called after each step, once its op has been applied.
+    fn after_step(&mut self, _exec: &ExecContext) {}
+}
+
 pub fn interpret_program<R: Read, W: Write>(
     block: TypedExpr,
-    mut exec: ExecContext,
+    exec: &mut ExecContext,
     mut io: IoContext<R, W>,
     builtin_bindings: Vec<(String, TypedExpr)>,
     builtins: HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
 ) -> Result<ResolvedValue, RuntimeError> {
-    // TODO: The logic below has a lot of overlap with push_block(). Should that just be called here?
-    let stmts = match block {
-        TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty)) => stmts,
-        _ => unreachable!(),
-    };
+    load_program(block, exec, builtin_bindings)?;
 
-    exec.control_stack.push(ControlOp::MarkBlockStart);
+    match run(exec, &mut io, &builtins, None, None)? {
+        RunState::Finished(value) => Ok(value),
+        RunState::Suspended => unreachable!(),
+    }
+}
+
+/// Runs a program as `interpret_program` does, stopping before each statement to hand over to the
+/// inspector.
+pub fn inspect_program<R: Read, W: Write>(
+    block: TypedExpr,
+    exec: &mut ExecContext,
+    mut io: IoContext<R, W>,
+    builtin_bindings: Vec<(String, TypedExpr)>,
+    builtins: HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
+    inspector: &mut dyn Inspector<R, W>,
+) -> Result<ResolvedValue, RuntimeError> {
+    load_program(block, exec, builtin_bindings)?;
 
-    for stmt in stmts.into_iter().rev() {
-        exec.control_stack.push(ControlOp::EvalStmt(stmt));
+    match run(exec, &mut io, &builtins, None, Some(inspector))? {
+        RunState::Finished(value) => Ok(value),
+        RunState::Suspended => unreachable!(),
     }
+}
+
+/// Prepares a program to be run in slices with `run_for`.
+pub fn load_program(
+    block: TypedExpr,
+    exec: &mut ExecContext,
+    builtin_bindings: Vec<(String, TypedExpr)>,
+) -> Result<(), RuntimeError> {
+    load_builtins(exec, builtin_bindings)?;
 
+    exec.control_stack
+        .push(ControlOp::EvalBlock(Rc::new(block)));
+
+    Ok(())
+}
+
+pub fn load_builtins(
+    exec: &mut ExecContext,
+    builtin_bindings: Vec<(String, TypedExpr)>,
+) -> Result<(), RuntimeError> {
     // TODO: Inject this into the AST prior to execution instead of doing it here.
     // Evaluate builtins
     for (ident, expr) in builtin_bindings {
-        if let TypedExpr::FuncDeclare(func, _) = expr {
-            let resolved = ResolvedValue::Func(func);
-            exec.scope_stack.insert(ident, false, resolved)?;
+        if let TypedExpr::FuncDeclare(func, _, span) = expr {
+            let resolved = ResolvedValue::Func(Rc::new(func));
+            exec.scope_stack.insert(ident, false, resolved, span)?;
         } else {
             unreachable!();
         }
     }
 
+    Ok(())
+}
+
+/// Runs a block's statements in the innermost scope rather than a scope of their own, so that any
+/// bindings they declare are left in place for whatever runs next, as at the REPL.
+pub fn interpret_in_scope<R: Read, W: Write>(
+    block: TypedExpr,
+    exec: &mut ExecContext,
+    io: &mut IoContext<R, W>,
+    builtins: &HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
+) -> Result<ResolvedValue, RuntimeError> {
+    let TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty), _span) = block else {
+        unreachable!();
+    };
+
+    if stmts.is_empty() {
+        return Ok(ResolvedValue::Void);
+    }
+
+    exec.control_stack.push(ControlOp::MarkBlockStart);
+    for stmt in stmts.iter().rev() {
+        exec.control_stack.push(ControlOp::EvalStmt(stmt.clone()));
+    }
+
+    match run(exec, io, builtins, None, None)? {
+        RunState::Finished(value) => Ok(value),
+        RunState::Suspended => unreachable!(),
+    }
+}
+
+/// Carries on running a loaded program for at most `steps` more steps.
+pub fn run_for<R: Read, W: Write>(
+    exec: &mut ExecContext,
+    io: &mut IoContext<R, W>,
+    builtins: &HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
+    steps: u64,
+) -> Result<RunState, RuntimeError> {
+    run(exec, io, builtins, Some(steps), None)
+}
+
+fn run<R: Read, W: Write>(
+    exec: &mut ExecContext,
+    io: &mut IoContext<R, W>,
+    builtins: &HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
+    steps: Option<u64>,
+    mut inspector: Option<&mut dyn Inspector<R, W>>,
+) -> Result<RunState, RuntimeError> {
+    let until = steps.map(|steps| exec.steps.saturating_add(steps));
+
     while let Some(current_op) = exec.control_stack.pop() {
+        exec.step()?;
+
+        if let Some(inspector) = inspector.as_deref_mut() {
+            inspector.before_op(exec, &current_op)?;
+        }
+
         let control_flow = match current_op {
-            ControlOp::ApplyList(size) => apply_list(&mut exec, size),
-            ControlOp::EvalBlock(block) => push_block(&mut exec, &mut io, &builtins, block)?,
-            ControlOp::EvalStmt(stmt) => push_stmt(&mut exec, stmt)?,
-            ControlOp::EvalExpr(expr) => eval_expr(&mut exec, &mut io, &builtins, expr)?,
-            ControlOp::ApplyStmt => apply_stmt(&mut exec),
-            ControlOp::ApplyAdd => apply_add(&mut exec),
-            ControlOp::ApplySub => apply_sub(&mut exec),
-            ControlOp::ApplyMult => apply_mult(&mut exec),
-            ControlOp::ApplyDiv => apply_div(&mut exec),
-            ControlOp::ApplyModulo => apply_modulo(&mut exec),
-            ControlOp::ApplyEq => apply_eq(&mut exec),
-            ControlOp::ApplyGt => apply_gt(&mut exec),
-            ControlOp::ApplyLt => apply_lt(&mut exec),
-            ControlOp::ApplyGte => apply_gte(&mut exec),
-            ControlOp::ApplyLte => apply_lte(&mut exec),
-
-            ControlOp::ApplyNegate => apply_negate(&mut exec)?,
-            ControlOp::ApplyAssignment(ident) => apply_assignment(&mut exec, ident)?,
-            ControlOp::ApplyDeclaration(ident, is_mutable) => {
-                apply_declaration(&mut exec, is_mutable, ident)?
+            ControlOp::ApplyList(size, span) => apply_list(exec, size, span)?,
+            ControlOp::EvalBlock(block) => push_block(exec, io, builtins, block)?,
+            ControlOp::EvalStmt(stmt) => {
+                if let Some(inspector) = inspector.as_deref_mut() {
+                    inspector.before_stmt(exec, io, &stmt)?;
+                }
+
+                push_stmt(exec, stmt)?
+            }
+            ControlOp::EvalExpr(expr) => eval_expr(exec, io, builtins, expr)?,
+            ControlOp::ApplyStmt => apply_stmt(exec),
+            ControlOp::ApplyAdd(span) => apply_add(exec, span)?,
+            ControlOp::ApplySub(span) => apply_sub(exec, span)?,
+            ControlOp::ApplyMult(span) => apply_mult(exec, span)?,
+            ControlOp::ApplyDiv(span) => apply_div(exec, span)?,
+            ControlOp::ApplyModulo(span) => apply_modulo(exec, span)?,
+            ControlOp::ApplyEq => apply_eq(exec),
+            ControlOp::ApplyGt => apply_gt(exec),
+            ControlOp::ApplyLt => apply_lt(exec),
+            ControlOp::ApplyGte => apply_gte(exec),
+            ControlOp::ApplyLte => apply_lte(exec),
+
+            ControlOp::ApplyNegate(span) => apply_negate(exec, span)?,
+            ControlOp::ApplyAssignment(ident, slot, span) => {
+                apply_assignment(exec, ident, slot, span)?
+            }
+            ControlOp::ApplyDeclaration(ident, is_mutable, span) => {
+                apply_declaration(exec, is_mutable, ident, span)?
+            }
+            ControlOp::ApplyFuncCall(args, name, is_tail, span) => {
+                apply_func_call(exec, args, name, is_tail, span)
             }
-            ControlOp::ApplyFuncCall(args) => apply_func_call(&mut exec, args),
-            ControlOp::ApplyBinding(ident) => apply_binding(&mut exec, ident)?,
-            ControlOp::PushScope { create_new_stack } => {
-                apply_push_scope(&mut exec, create_new_stack)
+            ControlOp::ApplyBindings(idents, span) => apply_bindings(exec, idents, span)?,
+            ControlOp::PopScope => apply_pop_scope(exec),
+            ControlOp::PushFrame { is_closure } => apply_push_frame(exec, is_closure),
+            ControlOp::PopFrame => apply_pop_frame(exec),
+            ControlOp::ReplaceFrame(func, frame) => apply_replace_frame(exec, func, frame)?,
+            ControlOp::ApplyIf(then) => apply_if(exec, then),
+            ControlOp::ApplyIfElse(then, els) => apply_if_else(exec, then, els),
+            ControlOp::PushLoop(block) => {
+                // The last pass through the body is over, and its value isn't needed.
+                exec.value_stack.pop();
+                push_loop(exec, block)
             }
-            ControlOp::PopScope {
-                restore_previous_stack,
-            } => apply_pop_scope(&mut exec, restore_previous_stack),
-            ControlOp::ApplyIf(then) => apply_if(&mut exec, then),
-            ControlOp::ApplyIfElse(then, els) => apply_if_else(&mut exec, then, els),
-            ControlOp::PushLoop(block) => push_loop(&mut exec, block),
+            ControlOp::PushCallFrame(frame) => apply_push_call_frame(exec, frame)?,
+            ControlOp::PopCallFrame => apply_pop_call_frame(exec),
 
             ControlOp::MarkLoopStart => ControlFlow::Continue,
-            ControlOp::MarkBlockStart => ControlFlow::Continue,
+            // Reached only by a block which got to its end without returning a value.
+            ControlOp::MarkBlockStart => {
+                exec.value_stack.push(ResolvedValue::Void);
+                ControlFlow::Continue
+            }
         };
 
+        // Loops only ever end by breaking, with no value.
         if let ControlFlow::Break = control_flow {
-            unwind_until(&mut exec, |op| matches!(op, ControlOp::MarkLoopStart));
+            unwind_until(exec, |op| matches!(op, ControlOp::MarkLoopStart));
+            exec.value_stack.push(ResolvedValue::Void);
         };
 
         if let ControlFlow::Return = control_flow {
-            unwind_until(&mut exec, |op| matches!(op, ControlOp::MarkBlockStart));
+            unwind_until(exec, |op| matches!(op, ControlOp::MarkBlockStart));
         };
+
+        if let Some(inspector) = inspector.as_deref_mut() {
+            inspector.after_step(exec);
+        }
+
+        if until.is_some_and(|until| exec.steps >= until) && !exec.control_stack.is_empty() {
+            return Ok(RunState::Suspended);
+        }
     }
 
-    Ok(exec.value_stack.pop().unwrap())
+    Ok(RunState::Finished(exec.value_stack.pop().unwrap()))
 }
 
 // Pop items from the control stack until the condition is met; generally when a marker is found.
+// Leaving early skips the ops that would normally restore scopes and call frames, so any of those
+// whose matching push already ran are applied on the way out. A push that's still pending (say,
+// for a call whose arguments were being evaluated) cancels out the pop that follows it.
 fn unwind_until<F>(exec: &mut ExecContext, meets_pattern: F)
 where
     F: Fn(&ControlOp) -> bool,
 {
+    let mut pending_frames = 0;
+    let mut pending_call_frames = 0;
+
     while let Some(op) = exec.control_stack.pop() {
         if meets_pattern(&op) {
             break;
         }
+
+        match op {
+            ControlOp::PopScope => {
+                apply_pop_scope(exec);
+            }
+            ControlOp::PushFrame { .. } => pending_frames += 1,
+            ControlOp::PopFrame if pending_frames > 0 => pending_frames -= 1,
+            ControlOp::PopFrame => {
+                apply_pop_frame(exec);
+            }
+            ControlOp::PushCallFrame(_) => pending_call_frames += 1,
+            ControlOp::PopCallFrame if pending_call_frames > 0 => pending_call_frames -= 1,
+            ControlOp::PopCallFrame => {
+                apply_pop_call_frame(exec);
+            }
+            _ => {}
+        }
     }
 }
 
@@ -129,26 +303,37 @@ fn push_unary_op(exec: &mut ExecContext, op: ControlOp, expr: TypedExpr) -> Cont
 fn push_binary_op(
     exec: &mut ExecContext,
     op: ControlOp,
-    left: Box<TypedExpr>,
-    right: Box<TypedExpr>,
+    left: TypedExpr,
+    right: TypedExpr,
 ) -> ControlFlow {
     exec.control_stack.push(op);
-    exec.control_stack.push(ControlOp::EvalExpr(*right));
-    exec.control_stack.push(ControlOp::EvalExpr(*left));
+    exec.control_stack.push(ControlOp::EvalExpr(right));
+    exec.control_stack.push(ControlOp::EvalExpr(left));
 
     ControlFlow::Continue
 }
 
-fn push_func_call(exec: &mut ExecContext, call: TypedFuncCall) -> ControlFlow {
-    exec.control_stack.push(ControlOp::ApplyFuncCall(call.args));
+fn push_func_call(exec: &mut ExecContext, call: TypedFuncCall, span: Span) -> ControlFlow {
+    // Backtraces name functions after the binding they're called through, when there is one.
+    let name = match call.func_expr.as_ref() {
+        TypedExpr::Identifier(ident, ..) => ident.clone(),
+        _ => String::from("<anonymous>"),
+    };
+
+    exec.control_stack.push(ControlOp::ApplyFuncCall(
+        call.args,
+        name,
+        call.is_tail,
+        span,
+    ));
     exec.control_stack
-        .push(ControlOp::EvalExpr(*call.func_expr));
+        .push(ControlOp::EvalExpr(Rc::unwrap_or_clone(call.func_expr)));
 
     ControlFlow::Continue
 }
 
-fn push_if(exec: &mut ExecContext, cond: TypedExpr, then: Box<TypedExpr>) -> ControlFlow {
-    exec.control_stack.push(ControlOp::ApplyIf(*then));
+fn push_if(exec: &mut ExecContext, cond: TypedExpr, then: Rc<TypedExpr>) -> ControlFlow {
+    exec.control_stack.push(ControlOp::ApplyIf(then));
     exec.control_stack.push(ControlOp::EvalExpr(cond));
 
     ControlFlow::Continue
@@ -157,10 +342,10 @@ fn push_if(exec: &mut ExecContext, cond: TypedExpr, then: Box<TypedExpr>) -> Con
 fn push_if_else(
     exec: &mut ExecContext,
     cond: TypedExpr,
-    then: Box<TypedExpr>,
-    els: Box<TypedExpr>,
+    then: Rc<TypedExpr>,
+    els: Rc<TypedExpr>,
 ) -> ControlFlow {
-    exec.control_stack.push(ControlOp::ApplyIfElse(*then, *els));
+    exec.control_stack.push(ControlOp::ApplyIfElse(then, els));
     exec.control_stack.push(ControlOp::EvalExpr(cond));
 
     ControlFlow::Continue
@@ -170,18 +355,21 @@ fn push_block<R: Read, W: Write>(
     exec: &mut ExecContext,
     io: &mut IoContext<R, W>,
     builtins: &HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
-    block: TypedExpr,
+    block: Rc<TypedExpr>,
 ) -> Result<ControlFlow, RuntimeError> {
-    let block = match block {
-        TypedExpr::Block(block) => block,
+    let block = match block.as_ref() {
+        TypedExpr::Block(block, _span) => block,
         _ => unreachable!(),
     };
 
     match block {
         TypedBlock::Interpreted(stmts, _ty) => {
+            exec.scope_stack.push_scope();
+
+            exec.control_stack.push(ControlOp::PopScope);
             exec.control_stack.push(ControlOp::MarkBlockStart);
-            for stmt in stmts.into_iter().rev() {
-                exec.control_stack.push(ControlOp::EvalStmt(stmt));
+            for stmt in stmts.iter().rev() {
+                exec.control_stack.push(ControlOp::EvalStmt(stmt.clone()));
             }
         }
         // TODO: Is it safe to execute right now instead of pushing to the control stack?
@@ -190,39 +378,39 @@ fn push_block<R: Read, W: Write>(
                 .iter()
                 .map(|param| {
                     exec.scope_stack
-                        .lookup::<RuntimeError>(param)
+                        .lookup::<RuntimeError>(param, Span::default())
                         .unwrap()
                         .value
                         .clone()
                 })
                 .collect();
 
-            let func = builtins.get(&builtin_id).unwrap();
+            let func = builtins.get(builtin_id).unwrap();
 
             let result = func(io, args)?;
+
+            // Builtins are always run from within their call, so it's there to blame.
+            let call_site = exec.call_stack.last().unwrap().call_site;
+            exec.check_value_size(&result, call_site)?;
+
             exec.value_stack.push(result);
         }
+        // Only the VM produces compiled blocks.
+        TypedBlock::Compiled(..) => unreachable!(),
     };
 
     Ok(ControlFlow::Continue)
 }
 
-fn mark_loop(exec: &mut ExecContext, block: TypedExpr) -> ControlFlow {
+fn mark_loop(exec: &mut ExecContext, block: Rc<TypedExpr>) -> ControlFlow {
     exec.control_stack.push(ControlOp::MarkLoopStart);
     push_loop(exec, block)
 }
 
-fn push_loop(exec: &mut ExecContext, block: TypedExpr) -> ControlFlow {
+fn push_loop(exec: &mut ExecContext, block: Rc<TypedExpr>) -> ControlFlow {
     exec.control_stack.push(ControlOp::PushLoop(block.clone()));
-    exec.control_stack.push(ControlOp::PopScope {
-        restore_previous_stack: false,
-    });
     exec.control_stack.push(ControlOp::EvalBlock(block));
 
-    exec.control_stack.push(ControlOp::PushScope {
-        create_new_stack: false,
-    });
-
     ControlFlow::Continue
 }
 
@@ -236,6 +424,17 @@ where
     exec.value_stack.push(op(left, right));
 }
 
+fn try_apply_binary_op<F>(exec: &mut ExecContext, op: F) -> Result<(), RuntimeError>
+where
+    F: Fn(ResolvedValue, ResolvedValue) -> Result<ResolvedValue, RuntimeError>,
+{
+    let right = exec.value_stack.pop().unwrap();
+    let left = exec.value_stack.pop().unwrap();
+
+    exec.value_stack.push(op(left, right)?);
+    Ok(())
+}
+
 fn apply_unary_op<F>(exec: &mut ExecContext, op: F) -> Result<(), RuntimeError>
 where
     F: Fn(&mut ExecContext, ResolvedValue) -> Result<ResolvedValue, RuntimeError>,
@@ -246,35 +445,100 @@ where
     Ok(())
 }
 
-fn apply_push_scope(exec: &mut ExecContext, create_new_stack: bool) -> ControlFlow {
-    if create_new_stack {
-        exec.scope_stack.create_new_stack();
-    } else {
-        exec.scope_stack.push_scope()
-    }
+pub fn apply_pop_scope(exec: &mut ExecContext) -> ControlFlow {
+    exec.scope_stack.pop_scope();
 
     ControlFlow::Continue
 }
 
-pub fn apply_pop_scope(exec: &mut ExecContext, restore_previous_stack: bool) -> ControlFlow {
-    if restore_previous_stack {
-        exec.scope_stack.restore_previous_stack();
-    } else {
-        exec.scope_stack.pop_scope();
+fn apply_push_frame(exec: &mut ExecContext, is_closure: bool) -> ControlFlow {
+    exec.scope_stack.push_frame(is_closure);
+
+    ControlFlow::Continue
+}
+
+fn apply_pop_frame(exec: &mut ExecContext) -> ControlFlow {
+    exec.scope_stack.pop_frame();
+
+    ControlFlow::Continue
+}
+
+// Takes over the calling function's frame for a tail call. Everything the caller still had left to
+// do is discarded, leaving its closing ops to close the callee's frame instead.
+fn apply_replace_frame(
+    exec: &mut ExecContext,
+    func: Rc<TypedFunc>,
+    frame: CallFrame,
+) -> Result<ControlFlow, RuntimeError> {
+    while !matches!(exec.control_stack.last(), Some(ControlOp::PopFrame)) {
+        exec.control_stack.pop();
     }
 
+    let args = exec
+        .value_stack
+        .split_off(exec.value_stack.len() - func.params.len());
+
+    // Whatever the caller left on the value stack goes with its frame, and the callee starts from
+    // where the caller did.
+    let value_base = exec
+        .call_stack
+        .last()
+        .map_or(exec.value_stack.len(), |caller| caller.value_base);
+    exec.value_stack.truncate(value_base);
+
+    exec.scope_stack.replace_frame(func.is_closure);
+
+    for ((param, _ty), arg) in func.params.iter().zip(args) {
+        exec.scope_stack
+            .insert(param.clone(), false, arg, frame.call_site)?;
+    }
+
+    exec.push_tail_call_frame(CallFrame {
+        value_base,
+        ..frame
+    });
+
+    exec.control_stack
+        .push(ControlOp::EvalBlock(func.block.clone()));
+
+    Ok(ControlFlow::Continue)
+}
+
+fn apply_push_call_frame(
+    exec: &mut ExecContext,
+    frame: CallFrame,
+) -> Result<ControlFlow, RuntimeError> {
+    exec.push_call_frame(frame)?;
+
+    Ok(ControlFlow::Continue)
+}
+
+fn apply_pop_call_frame(exec: &mut ExecContext) -> ControlFlow {
+    exec.pop_call_frame();
+
     ControlFlow::Continue
 }
 
 // This is not used by the assignment or declaration operations, but instead for things like func call args.
-pub fn apply_binding(exec: &mut ExecContext, ident: String) -> Result<ControlFlow, RuntimeError> {
-    let value = exec.value_stack.pop().unwrap();
-    exec.scope_stack.insert(ident.clone(), false, value)?;
+// Values are bound in the order they were pushed, so each binding ends up in the slot the analyzer
+// gave it.
+pub fn apply_bindings(
+    exec: &mut ExecContext,
+    idents: Vec<String>,
+    span: Span,
+) -> Result<ControlFlow, RuntimeError> {
+    let values = exec
+        .value_stack
+        .split_off(exec.value_stack.len() - idents.len());
+
+    for (ident, value) in idents.into_iter().zip(values) {
+        exec.scope_stack.insert(ident, false, value, span)?;
+    }
 
     Ok(ControlFlow::Continue)
 }
 
-fn apply_if(exec: &mut ExecContext, then_block: TypedExpr) -> ControlFlow {
+fn apply_if(exec: &mut ExecContext, then_block: Rc<TypedExpr>) -> ControlFlow {
     let cond = exec.value_stack.pop().unwrap();
 
     let cond_bool = match cond {
@@ -283,16 +547,9 @@ fn apply_if(exec: &mut ExecContext, then_block: TypedExpr) -> ControlFlow {
     };
 
     if cond_bool {
-        let stmts = match then_block {
-            TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty)) => stmts,
-            _ => unreachable!(),
-        };
-
-        exec.control_stack.push(ControlOp::MarkBlockStart);
-
-        for stmt in stmts.into_iter().rev() {
-            exec.control_stack.push(ControlOp::EvalStmt(stmt));
-        }
+        exec.control_stack.push(ControlOp::EvalBlock(then_block));
+    } else {
+        exec.value_stack.push(ResolvedValue::Void);
     }
 
     ControlFlow::Continue
@@ -300,8 +557,8 @@ fn apply_if(exec: &mut ExecContext, then_block: TypedExpr) -> ControlFlow {
 
 fn apply_if_else(
     exec: &mut ExecContext,
-    then_block: TypedExpr,
-    else_expr: TypedExpr,
+    then_block: Rc<TypedExpr>,
+    else_expr: Rc<TypedExpr>,
 ) -> ControlFlow {
     let cond = exec.value_stack.pop().unwrap();
 
@@ -312,17 +569,15 @@ fn apply_if_else(
 
     let branch = if cond_bool { then_block } else { else_expr };
 
-    match branch {
-        TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty)) => {
-            exec.control_stack.push(ControlOp::MarkBlockStart);
-
-            for stmt in stmts.into_iter().rev() {
-                exec.control_stack.push(ControlOp::EvalStmt(stmt));
-            }
+    match branch.as_ref() {
+        TypedExpr::Block(..) => {
+            exec.control_stack.push(ControlOp::EvalBlock(branch));
 
             ControlFlow::Continue
         }
-        TypedExpr::IfElse(cond, then, els, _ty) => push_if_else(exec, *cond, then, els),
+        TypedExpr::IfElse(cond, then, els, _ty, _span) => {
+            push_if_else(exec, cond.as_ref().clone(), then.clone(), els.clone())
+        }
         u => panic!("{:?}", u),
     }
 }
@@ -1,8 +1,11 @@
 use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
 
 use crate::ast::typed::TypedFunc;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ResolvedValue {
     Int(i32),
     Float(f64),
@@ -10,7 +13,7 @@ pub enum ResolvedValue {
     String(String),
     Bool(bool),
     Void,
-    Func(TypedFunc),
+    Func(Rc<TypedFunc>),
 }
 
 impl ResolvedValue {
@@ -55,6 +58,32 @@ impl ResolvedValue {
             _ => panic!(),
         }
     }
+
+    /// Renders the value as it'd be written in source, so strings are quoted and lists show their
+    /// items, for showing values to someone inspecting a program.
+    pub fn render(&self) -> String {
+        match self {
+            ResolvedValue::Float(float) => format!("{float:?}"),
+            ResolvedValue::List(items) => {
+                let items: Vec<_> = items.iter().map(ResolvedValue::render).collect();
+                format!("[{}]", items.join(", "))
+            }
+            ResolvedValue::String(string) => format!("{string:?}"),
+            value => value.to_string(),
+        }
+    }
+
+    /// Approximately how many bytes the value takes up, not counting the items of a list beyond
+    /// the space they take up within it.
+    pub fn size(&self) -> usize {
+        let inline = std::mem::size_of::<ResolvedValue>();
+
+        match self {
+            ResolvedValue::List(items) => inline + items.len() * inline,
+            ResolvedValue::String(string) => inline + string.len(),
+            _ => inline,
+        }
+    }
 }
 
 impl Display for ResolvedValue {
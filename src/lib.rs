@@ -3,12 +3,25 @@ use lalrpop_util::lalrpop_mod;
 pub mod analyzer;
 pub mod ast;
 pub mod builtins;
+pub mod compiler;
+pub mod debugger;
 pub mod errors;
+pub mod formatter;
 pub mod interpreter;
+pub mod linter;
+pub mod lsp;
+pub mod optimizer;
+pub mod parser;
+pub mod profiler;
+pub mod repl;
 pub mod scopes;
 pub mod state;
+pub mod test_runner;
 pub mod test_util;
+pub mod tracer;
 pub mod typing;
 pub mod util;
+pub mod vm;
 
-lalrpop_mod!(pub grammar);
+// Generated parser code trips lints we don't control.
+lalrpop_mod!(#[allow(clippy::empty_line_after_outer_attr, clippy::ptr_arg)] pub grammar);
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use crate::{ast::Span, errors::warning::Lint};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    fn from_name(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Decides which level each warning is reported at. Levels are taken from, in increasing order of
+/// precedence: the defaults (every lint warns), levels set from the command line, file-wide
+/// directives (`//! allow(unused_binding)`), and directives on the line above a binding
+/// (`// deny(unused_mut)`). The group name `warnings` stands for every lint.
+#[derive(Debug)]
+pub struct LintConfig {
+    levels: HashMap<Lint, LintLevel>,
+    line_levels: HashMap<usize, HashMap<Lint, LintLevel>>,
+    line_starts: Vec<usize>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self {
+            levels: HashMap::new(),
+            line_levels: HashMap::new(),
+            line_starts: vec![0],
+        }
+    }
+
+    /// Sets the level of a lint, or of every lint for `warnings`. Returns `false` for unknown lint
+    /// names.
+    pub fn set_level(&mut self, name: &str, level: LintLevel) -> bool {
+        match resolve_lints(name) {
+            Some(lints) => {
+                self.levels
+                    .extend(lints.into_iter().map(|lint| (lint, level)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reads `allow`, `warn` and `deny` directives from the comments in a program's source.
+    pub fn apply_directives(&mut self, source: &str) {
+        self.line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        // Directives on their own lines wait for the next line containing code.
+        let mut pending = Vec::new();
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line = line.trim();
+
+            if let Some(directive) = line.strip_prefix("//!") {
+                for (lint, level) in parse_directive(directive) {
+                    self.levels.insert(lint, level);
+                }
+            } else if let Some(directive) = line.strip_prefix("//") {
+                pending.extend(parse_directive(directive));
+            } else if !line.is_empty() {
+                self.line_levels
+                    .entry(line_index + 1)
+                    .or_default()
+                    .extend(pending.drain(..));
+            }
+        }
+    }
+
+    pub fn level(&self, lint: Lint, span: Span) -> LintLevel {
+        let line = self
+            .line_starts
+            .partition_point(|&start| start <= span.start);
+
+        self.line_levels
+            .get(&line)
+            .and_then(|levels| levels.get(&lint))
+            .or_else(|| self.levels.get(&lint))
+            .copied()
+            .unwrap_or(LintLevel::Warn)
+    }
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn resolve_lints(name: &str) -> Option<Vec<Lint>> {
+    if name == "warnings" {
+        Some(Lint::ALL.to_vec())
+    } else {
+        Lint::from_name(name).map(|lint| vec![lint])
+    }
+}
+
+// Parses the text following a comment marker, e.g. ` allow(unused_binding, unused_mut)`. Comments
+// which aren't directives, and unknown lint names, are ignored.
+fn parse_directive(comment: &str) -> Vec<(Lint, LintLevel)> {
+    let comment = comment.trim();
+
+    let Some((level, rest)) = comment.split_once('(') else {
+        return vec![];
+    };
+
+    let (Some(level), Some(names)) = (LintLevel::from_name(level.trim()), rest.strip_suffix(')'))
+    else {
+        return vec![];
+    };
+
+    names
+        .split(',')
+        .filter_map(|name| resolve_lints(name.trim()))
+        .flatten()
+        .map(|lint| (lint, level))
+        .collect()
+}
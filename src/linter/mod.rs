@@ -0,0 +1,267 @@
+pub mod config;
+
+use crate::ast::typed::typed_block::TypedBlock;
+use crate::ast::typed::typed_expr::TypedExpr;
+use crate::ast::typed::{TypedFunc, TypedLiteral};
+use crate::ast::Span;
+use crate::errors::warning::Warning;
+use crate::scopes::scope::Scope;
+use crate::typing::Type;
+
+use config::{LintConfig, LintLevel};
+
+#[derive(Debug)]
+struct BindingUsage {
+    is_param: bool,
+    is_mutable: bool,
+    is_used: bool,
+    is_reassigned: bool,
+    span: Span,
+}
+
+/// Walks an analyzed program looking for code which is valid but probably a mistake. Warnings
+/// come back in source order, paired with the level they should be reported at; allowed warnings
+/// are left out.
+pub fn lint_program(
+    program: &TypedExpr,
+    builtin_funcs: &[(String, TypedExpr)],
+    config: &LintConfig,
+) -> Vec<(Warning, LintLevel)> {
+    let builtins: Vec<&str> = builtin_funcs
+        .iter()
+        .map(|(ident, _)| ident.as_str())
+        .collect();
+
+    let mut scopes = Vec::new();
+    let mut warnings = Vec::new();
+
+    lint_expr(&mut scopes, &builtins, &mut warnings, program);
+
+    warnings.sort_by_key(|warning| warning.span().start);
+
+    warnings
+        .into_iter()
+        .map(|warning| {
+            let level = config.level(warning.lint(), warning.span());
+            (warning, level)
+        })
+        .filter(|(_, level)| *level != LintLevel::Allow)
+        .collect()
+}
+
+fn lint_expr(
+    scopes: &mut Vec<Scope<BindingUsage>>,
+    builtins: &[&str],
+    warnings: &mut Vec<Warning>,
+    expr: &TypedExpr,
+) {
+    use TypedExpr::*;
+
+    match expr {
+        Eq(left, right, _, span)
+        | Gt(left, right, _, span)
+        | Lt(left, right, _, span)
+        | Gte(left, right, _, span)
+        | Lte(left, right, _, span) => {
+            if let Some(result) = eval_constant_comparison(expr, left, right) {
+                warnings.push(Warning::ConstantComparison(result, *span));
+            }
+
+            lint_expr(scopes, builtins, warnings, left);
+            lint_expr(scopes, builtins, warnings, right);
+        }
+        Add(left, right, ..)
+        | Sub(left, right, ..)
+        | Mult(left, right, ..)
+        | Div(left, right, ..)
+        | Modulo(left, right, ..) => {
+            lint_expr(scopes, builtins, warnings, left);
+            lint_expr(scopes, builtins, warnings, right);
+        }
+        Negate(inner, ..) => lint_expr(scopes, builtins, warnings, inner),
+        Assignment { ident, expr, .. } => {
+            lint_expr(scopes, builtins, warnings, expr);
+
+            if let Some(usage) = lookup(scopes, ident) {
+                usage.is_reassigned = true;
+            }
+        }
+        Declaration {
+            ident,
+            is_mutable,
+            expr,
+            span,
+            ..
+        } => {
+            if builtins.contains(&ident.as_str()) {
+                warnings.push(Warning::ShadowedBuiltin(ident.clone(), *span));
+            }
+
+            let usage = BindingUsage {
+                is_param: false,
+                is_mutable: *is_mutable,
+                is_used: false,
+                is_reassigned: false,
+                span: *span,
+            };
+
+            // Functions are bound before their bodies are walked, the same way the analyzer allows
+            // them to recurse.
+            if expr.is_func_declare() {
+                insert(scopes, ident, usage);
+                lint_expr(scopes, builtins, warnings, expr);
+            } else {
+                lint_expr(scopes, builtins, warnings, expr);
+                insert(scopes, ident, usage);
+            }
+        }
+        FuncCall(call, ..) => {
+            lint_expr(scopes, builtins, warnings, &call.func_expr);
+
+            for arg in call.args.iter() {
+                lint_expr(scopes, builtins, warnings, arg);
+            }
+        }
+        If(cond, then_block, ..) => {
+            lint_branch(warnings, then_block);
+            lint_expr(scopes, builtins, warnings, cond);
+            lint_expr(scopes, builtins, warnings, then_block);
+        }
+        IfElse(cond, then_block, else_expr, ..) => {
+            lint_branch(warnings, then_block);
+            lint_branch(warnings, else_expr);
+            lint_expr(scopes, builtins, warnings, cond);
+            lint_expr(scopes, builtins, warnings, then_block);
+            lint_expr(scopes, builtins, warnings, else_expr);
+        }
+        Block(TypedBlock::Interpreted(stmts, _), _) => {
+            scopes.push(Scope::new());
+
+            for stmt in stmts.iter() {
+                lint_expr(scopes, builtins, warnings, &stmt.expr);
+            }
+
+            pop_scope(scopes, warnings);
+        }
+        Block(TypedBlock::Builtin(..) | TypedBlock::Compiled(..), _) => {}
+        Loop(block, _) => lint_expr(scopes, builtins, warnings, block),
+        Identifier(ident, ..) => {
+            if let Some(usage) = lookup(scopes, ident) {
+                usage.is_used = true;
+            }
+        }
+        FuncDeclare(func, _, span) => lint_func(scopes, builtins, warnings, func, *span),
+        List(items, ..) => {
+            for item in items.iter() {
+                lint_expr(scopes, builtins, warnings, item);
+            }
+        }
+        Break(_) | Literal(..) | Unknown(_) => {}
+    }
+}
+
+fn lint_func(
+    scopes: &mut Vec<Scope<BindingUsage>>,
+    builtins: &[&str],
+    warnings: &mut Vec<Warning>,
+    func: &TypedFunc,
+    span: Span,
+) {
+    scopes.push(Scope::new());
+
+    // Parameters don't have spans of their own, so warnings about them point at the function.
+    for (ident, _) in &func.params {
+        if builtins.contains(&ident.as_str()) {
+            warnings.push(Warning::ShadowedBuiltin(ident.clone(), span));
+        }
+
+        let usage = BindingUsage {
+            is_param: true,
+            is_mutable: false,
+            is_used: false,
+            is_reassigned: false,
+            span,
+        };
+
+        insert(scopes, ident, usage);
+    }
+
+    lint_expr(scopes, builtins, warnings, &func.block);
+    pop_scope(scopes, warnings);
+}
+
+// Only the branch blocks themselves are checked; `elif` chains show up here as nested `If` and
+// `IfElse` expressions rather than blocks.
+fn lint_branch(warnings: &mut Vec<Warning>, branch: &TypedExpr) {
+    if let TypedExpr::Block(TypedBlock::Interpreted(stmts, _), span) = branch {
+        if stmts.is_empty() {
+            warnings.push(Warning::EmptyBranch(*span));
+        }
+    }
+}
+
+fn pop_scope(scopes: &mut Vec<Scope<BindingUsage>>, warnings: &mut Vec<Warning>) {
+    let scope = scopes.pop().unwrap();
+
+    let mut bindings: Vec<_> = scope.iter().collect();
+    bindings.sort_by_key(|(ident, usage)| (usage.span.start, ident.as_str()));
+
+    for (ident, usage) in bindings {
+        // Bindings can opt out of the unused checks by starting with an underscore.
+        let is_ignored = ident.starts_with('_');
+
+        if !usage.is_used && !is_ignored {
+            if usage.is_param {
+                warnings.push(Warning::UnusedParam(ident.clone(), usage.span));
+            } else {
+                warnings.push(Warning::UnusedBinding(ident.clone(), usage.span));
+            }
+        } else if usage.is_mutable && !usage.is_reassigned {
+            warnings.push(Warning::UnusedMut(ident.clone(), usage.span));
+        }
+    }
+}
+
+fn insert(scopes: &mut [Scope<BindingUsage>], ident: &str, usage: BindingUsage) {
+    if let Some(scope) = scopes.last_mut() {
+        scope.insert(ident.to_string(), usage);
+    }
+}
+
+fn lookup<'a>(scopes: &'a mut [Scope<BindingUsage>], ident: &str) -> Option<&'a mut BindingUsage> {
+    scopes
+        .iter_mut()
+        .rev()
+        .find_map(|scope| scope.get_mut(ident))
+}
+
+// A comparison is constant when both sides are literals, or when a value is compared with itself.
+// Floats are left out of the second case, since NaN never equals itself.
+fn eval_constant_comparison(op: &TypedExpr, left: &TypedExpr, right: &TypedExpr) -> Option<bool> {
+    use std::cmp::Ordering;
+
+    let ordering = match (left, right) {
+        (TypedExpr::Literal(left, ..), TypedExpr::Literal(right, ..)) => match (left, right) {
+            (TypedLiteral::Int(a), TypedLiteral::Int(b)) => a.partial_cmp(b),
+            (TypedLiteral::Float(a), TypedLiteral::Float(b)) => a.partial_cmp(b),
+            (TypedLiteral::String(a), TypedLiteral::String(b)) => a.partial_cmp(b),
+            (TypedLiteral::Bool(a), TypedLiteral::Bool(b)) => a.partial_cmp(b),
+            _ => None,
+        },
+        (TypedExpr::Identifier(a, _, ty, _), TypedExpr::Identifier(b, ..))
+            if a == b && *ty != Type::Float =>
+        {
+            Some(Ordering::Equal)
+        }
+        _ => None,
+    }?;
+
+    match op {
+        TypedExpr::Eq(..) => Some(ordering == Ordering::Equal),
+        TypedExpr::Gt(..) => Some(ordering == Ordering::Greater),
+        TypedExpr::Lt(..) => Some(ordering == Ordering::Less),
+        TypedExpr::Gte(..) => Some(ordering != Ordering::Less),
+        TypedExpr::Lte(..) => Some(ordering != Ordering::Greater),
+        _ => None,
+    }
+}
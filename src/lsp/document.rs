@@ -0,0 +1,525 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::{
+    analyzer::analyze_program_with_errors,
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr},
+        untyped::Expr,
+        Span,
+    },
+    builtins::{get_builtin_func_bindings, get_builtin_type_bindings},
+    parser::{
+        parse_program,
+        tokens::{find_token, tokenize},
+    },
+    typing::Type,
+};
+
+/// An error in a document, with what to say about it.
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Something a name can be completed to.
+pub struct Completion {
+    pub name: String,
+    pub ty: Option<Type>,
+}
+
+// A `let` binding, parameter or builtin. Builtins aren't declared anywhere in the document.
+struct Binding {
+    name: String,
+    span: Option<Span>,
+    // Where the binding can be referred to by name, ignoring functions which can't see it.
+    visible: Range<usize>,
+    ty: Option<Type>,
+}
+
+struct Reference {
+    span: Span,
+    binding: Option<usize>,
+}
+
+/// A document open in the editor, analyzed as it was last changed.
+pub struct Document {
+    source: String,
+    diagnostics: Vec<Diagnostic>,
+    bindings: Vec<Binding>,
+    references: Vec<Reference>,
+    // The bodies of non-closure functions, which can't see the bindings outside of them.
+    barriers: Vec<Span>,
+}
+
+impl Document {
+    pub fn new(source: String) -> Self {
+        let mut document = Self {
+            source,
+            diagnostics: Vec::new(),
+            bindings: Vec::new(),
+            references: Vec::new(),
+            barriers: Vec::new(),
+        };
+
+        let parsed = match parse_program(&document.source) {
+            Ok(parsed) => parsed,
+            Err(errors) => {
+                document.diagnostics = errors
+                    .into_iter()
+                    .map(|error| Diagnostic {
+                        span: error.span(),
+                        message: with_detail(error.message(), error.detail()),
+                    })
+                    .collect();
+
+                return document;
+            }
+        };
+
+        let (analyzed, errors) = analyze_program_with_errors(
+            parsed.clone(),
+            get_builtin_func_bindings(),
+            get_builtin_type_bindings(),
+        );
+
+        document.diagnostics = errors
+            .into_iter()
+            .map(|error| Diagnostic {
+                span: error.span(),
+                message: with_detail(error.message(), error.detail()),
+            })
+            .collect();
+
+        let mut types = Types::default();
+        types.collect(&analyzed);
+
+        let tokens = tokenize(&document.source)
+            .map(|tokens| tokens.into_iter().map(|(_, span)| span).collect())
+            .unwrap_or_default();
+
+        let mut resolver = Resolver {
+            document: &mut document,
+            tokens,
+            types,
+            scopes: Vec::new(),
+            frames: vec![(0, false)],
+        };
+
+        resolver.declare_builtins();
+        resolver.expr(&parsed);
+
+        document
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The name at the offset, along with its type if that's known.
+    pub fn hover(&self, offset: usize) -> Option<(Span, String)> {
+        let (span, binding) = self.name_at(offset)?;
+        let binding = &self.bindings[binding?];
+        let ty = binding.ty.as_ref().filter(|ty| **ty != Type::Unknown)?;
+
+        Some((span, format!("{}: {ty}", binding.name)))
+    }
+
+    /// Where the binding named at the offset is declared.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        let (_, binding) = self.name_at(offset)?;
+
+        self.bindings[binding?].span
+    }
+
+    /// Every name visible at the offset, innermost first. If the document couldn't be parsed, this
+    /// is just the builtins and whatever names appear in it.
+    pub fn completions(&self, offset: usize) -> Vec<Completion> {
+        if self.bindings.is_empty() {
+            return self.fallback_completions(offset);
+        }
+
+        let mut seen = Vec::new();
+
+        self.bindings
+            .iter()
+            .rev()
+            .filter(|binding| self.is_visible(binding, offset))
+            .filter(|binding| {
+                let is_new = !seen.contains(&binding.name);
+                seen.push(binding.name.clone());
+                is_new
+            })
+            .map(|binding| Completion {
+                name: binding.name.clone(),
+                ty: binding.ty.clone(),
+            })
+            .collect()
+    }
+
+    fn fallback_completions(&self, offset: usize) -> Vec<Completion> {
+        let mut completions: Vec<_> = get_builtin_func_bindings()
+            .into_iter()
+            .map(|(name, func)| Completion {
+                name,
+                ty: Some(func.ty()),
+            })
+            .collect();
+
+        let tokens = tokenize(&self.source).unwrap_or_default();
+
+        for (_, span) in tokens {
+            let name = &self.source[span.start..span.end];
+            let is_name = name.starts_with(|c: char| c.is_alphabetic() || c == '_');
+
+            // The name being typed isn't worth suggesting.
+            if is_name
+                && !(span.start..=span.end).contains(&offset)
+                && !completions.iter().any(|c| c.name == name)
+            {
+                completions.push(Completion {
+                    name: name.to_string(),
+                    ty: None,
+                });
+            }
+        }
+
+        completions
+    }
+
+    fn is_visible(&self, binding: &Binding, offset: usize) -> bool {
+        let declared = binding.span.map(|span| span.start);
+
+        binding.visible.contains(&offset)
+            && self.barriers.iter().all(|barrier| {
+                let inside = |at: usize| (barrier.start..barrier.end).contains(&at);
+                !inside(offset) || declared.is_some_and(inside)
+            })
+    }
+
+    // The name at the offset, either where a binding is declared or where it's referred to, along
+    // with the binding if it could be found. The offset may be just past the end of the name.
+    fn name_at(&self, offset: usize) -> Option<(Span, Option<usize>)> {
+        let contains = |span: Span| (span.start..=span.end).contains(&offset);
+
+        self.references
+            .iter()
+            .find(|reference| contains(reference.span))
+            .map(|reference| (reference.span, reference.binding))
+            .or_else(|| {
+                self.bindings
+                    .iter()
+                    .position(|binding| binding.span.is_some_and(contains))
+                    .map(|index| (self.bindings[index].span.unwrap(), Some(index)))
+            })
+    }
+
+    /// The line and character of an offset, with characters counted in UTF-16 code units.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line_start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = self.source[..offset].matches('\n').count();
+
+        (line, self.source[line_start..offset].encode_utf16().count())
+    }
+
+    /// The offset of a line and character, clamped to the end of the line.
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let line_start: usize = self
+            .source
+            .split_inclusive('\n')
+            .take(line)
+            .map(str::len)
+            .sum();
+
+        let mut units = 0;
+
+        for (i, c) in self.source[line_start..].char_indices() {
+            if units >= character || c == '\n' {
+                return line_start + i;
+            }
+            units += c.len_utf16();
+        }
+
+        self.source.len()
+    }
+}
+
+fn with_detail(message: String, detail: Option<String>) -> String {
+    match detail {
+        Some(detail) => format!("{message}\n{detail}"),
+        None => message,
+    }
+}
+
+// The types the analyzer found for each declaration and function's parameters, keyed by span.
+#[derive(Default)]
+struct Types {
+    declarations: HashMap<(usize, usize), Type>,
+    params: HashMap<(usize, usize), Vec<Type>>,
+}
+
+impl Types {
+    fn collect(&mut self, expr: &TypedExpr) {
+        let key = (expr.span().start, expr.span().end);
+
+        match expr {
+            TypedExpr::Eq(l, r, ..)
+            | TypedExpr::Gt(l, r, ..)
+            | TypedExpr::Lt(l, r, ..)
+            | TypedExpr::Gte(l, r, ..)
+            | TypedExpr::Lte(l, r, ..)
+            | TypedExpr::Add(l, r, ..)
+            | TypedExpr::Sub(l, r, ..)
+            | TypedExpr::Mult(l, r, ..)
+            | TypedExpr::Div(l, r, ..)
+            | TypedExpr::Modulo(l, r, ..) => {
+                self.collect(l);
+                self.collect(r);
+            }
+            TypedExpr::Negate(inner, ..) => self.collect(inner),
+            TypedExpr::Assignment { expr, .. } => self.collect(expr),
+            TypedExpr::Declaration { expr, .. } => {
+                self.declarations.insert(key, expr.ty());
+                self.collect(expr);
+            }
+            TypedExpr::FuncCall(call, ..) => {
+                self.collect(&call.func_expr);
+                call.args.iter().for_each(|arg| self.collect(arg));
+            }
+            TypedExpr::If(cond, then, ..) => {
+                self.collect(cond);
+                self.collect(then);
+            }
+            TypedExpr::IfElse(cond, then, els, ..) => {
+                self.collect(cond);
+                self.collect(then);
+                self.collect(els);
+            }
+            TypedExpr::Block(TypedBlock::Interpreted(stmts, _), _) => {
+                stmts.iter().for_each(|stmt| self.collect(&stmt.expr));
+            }
+            TypedExpr::Loop(block, _) => self.collect(block),
+            TypedExpr::FuncDeclare(func, ..) => {
+                let params = func.params.iter().map(|(_, ty)| ty.clone()).collect();
+                self.params.insert(key, params);
+                self.collect(&func.block);
+            }
+            TypedExpr::List(items, ..) => items.iter().for_each(|item| self.collect(item)),
+            TypedExpr::Block(..)
+            | TypedExpr::Break(_)
+            | TypedExpr::Identifier(..)
+            | TypedExpr::Literal(..)
+            | TypedExpr::Unknown(_) => {}
+        }
+    }
+}
+
+// Works out which binding each name refers to, following the same scoping rules as the analyzer,
+// so it can be done even where the analyzer gave up on an expression.
+struct Resolver<'a> {
+    document: &'a mut Document,
+    tokens: Vec<Span>,
+    types: Types,
+    // The bindings declared in each scope, by index.
+    scopes: Vec<Vec<usize>>,
+    // The first scope of each frame, and whether it's a closure's.
+    frames: Vec<(usize, bool)>,
+}
+
+impl Resolver<'_> {
+    fn text(&self, span: Span) -> &str {
+        &self.document.source[span.start..span.end]
+    }
+
+    // The tokens within the span.
+    fn tokens_in(&self, span: Span) -> &[Span] {
+        let start = self
+            .tokens
+            .partition_point(|token| token.start < span.start);
+        let end = self.tokens.partition_point(|token| token.start < span.end);
+
+        &self.tokens[start..end]
+    }
+
+    fn declare_builtins(&mut self) {
+        let end = self.document.source.len();
+
+        self.scopes.push(Vec::new());
+
+        for (name, func) in get_builtin_func_bindings() {
+            self.declare(name, None, 0, Some(func.ty()));
+            self.document.bindings.last_mut().unwrap().visible.end = end;
+        }
+    }
+
+    fn declare(&mut self, name: String, span: Option<Span>, from: usize, ty: Option<Type>) {
+        let index = self.document.bindings.len();
+
+        self.document.bindings.push(Binding {
+            name,
+            span,
+            visible: from..usize::MAX,
+            ty,
+        });
+        self.scopes.last_mut().unwrap().push(index);
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        // Lookups see into the enclosing frames only through closures.
+        let outermost = self
+            .frames
+            .iter()
+            .rev()
+            .find(|(_, is_closure)| !is_closure)
+            .map_or(0, |(scope, _)| *scope);
+
+        self.scopes[outermost..]
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .copied()
+            .find(|binding| self.document.bindings[*binding].name == name)
+    }
+
+    fn refer(&mut self, name: &str, span: Span) {
+        let binding = self.lookup(name);
+        self.document.references.push(Reference { span, binding });
+    }
+
+    fn block(&mut self, block: &Expr, params: Vec<(String, Span, Option<Type>)>) {
+        let Expr::Block(stmts, span) = block else {
+            unreachable!();
+        };
+
+        // The program itself has no braces, so its bindings are visible to the end.
+        let end = if self.scopes.len() == 1 {
+            self.document.source.len()
+        } else {
+            find_token(&self.document.source, &self.tokens, span.end, "}")
+        };
+
+        self.scopes.push(Vec::new());
+
+        // Parameters are visible from the function's opening brace.
+        let start = self.tokens[..self
+            .tokens
+            .partition_point(|token| token.start < span.start)]
+            .iter()
+            .rposition(|token| self.text(*token) == "{")
+            .map_or(span.start, |i| self.tokens[i].end);
+
+        for (name, param, ty) in params {
+            self.declare(name, Some(param), start, ty);
+        }
+
+        for stmt in stmts {
+            self.expr(&stmt.expr);
+        }
+
+        for binding in self.scopes.pop().unwrap() {
+            self.document.bindings[binding].visible.end = end;
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Eq(l, r, _)
+            | Expr::Gt(l, r, _)
+            | Expr::Lt(l, r, _)
+            | Expr::Gte(l, r, _)
+            | Expr::Lte(l, r, _)
+            | Expr::Add(l, r, _)
+            | Expr::Sub(l, r, _)
+            | Expr::Mult(l, r, _)
+            | Expr::Div(l, r, _)
+            | Expr::Modulo(l, r, _) => {
+                self.expr(l);
+                self.expr(r);
+            }
+            Expr::Negate(inner, _) => self.expr(inner),
+            Expr::Assignment { ident, expr, span } => {
+                self.expr(expr);
+
+                if let Some(name) = self.tokens_in(*span).first() {
+                    self.refer(ident, *name);
+                }
+            }
+            Expr::Declaration {
+                ident, expr, span, ..
+            } => {
+                // The name comes straight after `let` or `mut`.
+                let name = self.tokens_in(*span).get(1).copied();
+                let ty = self
+                    .types
+                    .declarations
+                    .get(&(span.start, span.end))
+                    .cloned();
+
+                // Functions can refer to themselves, but other values can't.
+                if expr.is_func_declare() {
+                    self.declare(ident.clone(), name, span.start, ty);
+                    self.expr(expr);
+                } else {
+                    self.expr(expr);
+                    self.declare(ident.clone(), name, span.end, ty);
+                }
+            }
+            Expr::FuncCall(call, _) => {
+                self.expr(&call.func);
+                call.args.iter().for_each(|arg| self.expr(arg));
+            }
+            Expr::If(cond, then, _) => {
+                self.expr(cond);
+                self.block(then, Vec::new());
+            }
+            Expr::IfElse(cond, then, els, _) => {
+                self.expr(cond);
+                self.block(then, Vec::new());
+
+                match els.as_ref() {
+                    Expr::Block(..) => self.block(els, Vec::new()),
+                    _ => self.expr(els),
+                }
+            }
+            Expr::Block(..) => self.block(expr, Vec::new()),
+            Expr::Loop(body, _) | Expr::Test(_, body, _) => self.block(body, Vec::new()),
+            Expr::Identifier(name, span) => self.refer(name, *span),
+            Expr::FuncDeclare(func, span) => {
+                if !func.is_closure {
+                    self.document.barriers.push(*span);
+                }
+
+                let types = self.types.params.get(&(span.start, span.end));
+                let tokens = self.tokens_in(Span {
+                    start: span.start,
+                    end: func.block.span().start,
+                });
+
+                // Each parameter is the next name followed by a `:`.
+                let mut names = tokens
+                    .windows(2)
+                    .filter(|pair| self.text(pair[1]) == ":")
+                    .map(|pair| pair[0]);
+
+                let params = func
+                    .params
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, (name, _))| {
+                        let span = names.find(|span| self.text(*span) == name)?;
+                        let ty = types.and_then(|types| types.get(i)).cloned();
+
+                        Some((name.clone(), span, ty))
+                    })
+                    .collect();
+
+                self.frames.push((self.scopes.len(), func.is_closure));
+                self.block(&func.block, params);
+                self.frames.pop();
+            }
+            Expr::List(items, _) => items.iter().for_each(|item| self.expr(item)),
+            Expr::Break(_) | Expr::Literal(..) => {}
+        }
+    }
+}
@@ -0,0 +1,314 @@
+pub mod document;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::{ast::Span, typing::Type};
+
+use document::Document;
+
+// Error codes from JSON-RPC.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// Kinds of completion item, as numbered by the protocol.
+const FUNCTION_KIND: u8 = 3;
+const VARIABLE_KIND: u8 = 6;
+
+type RequestResult = Result<Value, (i64, String)>;
+
+// Where in which document a request is about.
+struct Cursor<'a> {
+    uri: &'a str,
+    document: &'a Document,
+    offset: usize,
+}
+
+/// A language server, which keeps every document the editor has open analyzed as it changes.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    is_shut_down: bool,
+}
+
+/// Serves requests read from `reader` until the editor says to exit, or stops sending any. Returns
+/// whether the editor asked for a shutdown first, as a well-behaved one always does.
+pub fn run<R: BufRead, W: Write>(mut reader: R, mut writer: W) -> io::Result<bool> {
+    let mut server = Server::default();
+
+    while let Some(body) = read_message(&mut reader)? {
+        let message: Value = match serde_json::from_slice(&body) {
+            Ok(message) => message,
+            Err(error) => {
+                let response = error_response(Value::Null, PARSE_ERROR, error.to_string());
+                write_message(&mut writer, &response)?;
+                continue;
+            }
+        };
+
+        if message["method"] == "exit" {
+            break;
+        }
+
+        for outgoing in server.handle(message) {
+            write_message(&mut writer, &outgoing)?;
+        }
+    }
+
+    Ok(server.is_shut_down)
+}
+
+impl Server {
+    /// Handles one message from the editor, returning any messages to send back.
+    pub fn handle(&mut self, message: Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        let params = &message["params"];
+
+        let Some(id) = message.get("id").cloned() else {
+            return self.notify(&method, params);
+        };
+
+        // Responses to requests of our own, which are never made.
+        if message.get("method").is_none() {
+            return Vec::new();
+        }
+
+        let result = match method.as_str() {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    // The whole document is sent with every change.
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": {
+                    "name": "moss",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => {
+                self.is_shut_down = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/completion" => self.completion(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method \"{method}\"."))),
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, message),
+        };
+
+        vec![response]
+    }
+
+    // Notifications get no response, but changes to a document are answered with its diagnostics.
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str(),
+            // Only full syncs are asked for, so the last change holds the whole document.
+            "textDocument/didChange" => params["contentChanges"]
+                .as_array()
+                .and_then(|changes| changes.last())
+                .and_then(|change| change["text"].as_str()),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, Vec::new())];
+            }
+            _ => None,
+        };
+
+        let Some(text) = text else {
+            return Vec::new();
+        };
+
+        let document = Document::new(text.to_string());
+
+        let diagnostics = document
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                json!({
+                    "range": range(&document, diagnostic.span),
+                    // Everything the analyzer reports is an error.
+                    "severity": 1,
+                    "source": "moss",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+
+        self.documents.insert(uri.to_string(), document);
+
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+
+    // The document and offset a request is about. A document which isn't open has nothing in it.
+    fn locate(&self, params: &Value) -> Result<Option<Cursor<'_>>, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str();
+        let line = params["position"]["line"].as_u64();
+        let character = params["position"]["character"].as_u64();
+
+        let (Some(uri), Some(line), Some(character)) = (uri, line, character) else {
+            return Err((
+                INVALID_PARAMS,
+                String::from("Expected a text document and position."),
+            ));
+        };
+
+        Ok(self
+            .documents
+            .get_key_value(uri)
+            .map(|(uri, document)| Cursor {
+                uri,
+                document,
+                offset: document.offset(line as usize, character as usize),
+            }))
+    }
+
+    fn hover(&self, params: &Value) -> RequestResult {
+        let Some(Cursor {
+            document, offset, ..
+        }) = self.locate(params)?
+        else {
+            return Ok(Value::Null);
+        };
+
+        Ok(match document.hover(offset) {
+            Some((span, signature)) => json!({
+                "contents": {
+                    "kind": "markdown",
+                    "value": format!("```moss\n{signature}\n```"),
+                },
+                "range": range(document, span),
+            }),
+            None => Value::Null,
+        })
+    }
+
+    fn definition(&self, params: &Value) -> RequestResult {
+        let Some(Cursor {
+            uri,
+            document,
+            offset,
+        }) = self.locate(params)?
+        else {
+            return Ok(Value::Null);
+        };
+
+        Ok(match document.definition(offset) {
+            Some(span) => json!({ "uri": uri, "range": range(document, span) }),
+            None => Value::Null,
+        })
+    }
+
+    fn completion(&self, params: &Value) -> RequestResult {
+        let Some(Cursor {
+            document, offset, ..
+        }) = self.locate(params)?
+        else {
+            return Ok(json!([]));
+        };
+
+        let items: Vec<_> = document
+            .completions(offset)
+            .into_iter()
+            .map(|completion| {
+                let kind = match completion.ty {
+                    Some(Type::Func(_)) => FUNCTION_KIND,
+                    _ => VARIABLE_KIND,
+                };
+
+                let mut item = json!({ "label": completion.name, "kind": kind });
+
+                if let Some(ty) = completion.ty.filter(|ty| *ty != Type::Unknown) {
+                    item["detail"] = json!(ty.to_string());
+                }
+
+                item
+            })
+            .collect();
+
+        Ok(json!(items))
+    }
+}
+
+fn range(document: &Document, span: Span) -> Value {
+    let (start_line, start_character) = document.position(span.start);
+    let (end_line, end_character) = document.position(span.end);
+
+    json!({
+        "start": { "line": start_line, "character": start_character },
+        "end": { "line": end_line, "character": end_character },
+    })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Reads the body of the next message, or `None` once there are no more. Each is preceded by a
+/// header giving its length, as in HTTP.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Expected a Content-Length header.",
+        ));
+    };
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Some(body))
+}
+
+/// Writes a message, preceded by the header giving its length.
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()
+}
@@ -1,75 +1,887 @@
 pub mod analyzer;
 mod ast;
 pub mod builtins;
+mod compiler;
+mod debugger;
 mod errors;
+mod formatter;
 pub mod interpreter;
+mod linter;
+mod lsp;
+mod optimizer;
+mod parser;
+mod profiler;
+mod repl;
 mod scopes;
 mod state;
+mod test_runner;
 pub mod test_util;
+mod tracer;
 mod typing;
 mod util;
+mod vm;
 
-lalrpop_mod!(pub grammar);
+// Generated parser code trips lints we don't control.
+lalrpop_mod!(#[allow(clippy::empty_line_after_outer_attr, clippy::ptr_arg)] pub grammar);
 
+use ast::{typed::typed_expr::TypedExpr, untyped::Expr};
 use builtins::{get_builtin_func_bindings, get_builtin_funcs, get_builtin_type_bindings};
-use grammar::ProgramParser;
+use debugger::Debugger;
+use errors::{
+    location_frame::{get_column_number, get_line_number},
+    runtime_error::RuntimeError,
+};
+use formatter::format_program;
 use lalrpop_util::lalrpop_mod;
-use scopes::scope_stack::ScopeStack;
-use state::{exec_context::ExecContext, io_context::IoContext};
+use linter::{
+    config::{LintConfig, LintLevel},
+    lint_program,
+};
+use parser::{parse_program, tokens::tokenize};
+use profiler::Profiler;
+use repl::Repl;
+use serde_json::Value;
+use state::{
+    exec_context::{ArithmeticMode, CallFrame, ExecContext},
+    interrupt::InterruptHandle,
+    io_context::IoContext,
+};
 use std::{
     env, fs,
-    io::{self, BufReader, BufWriter},
+    io::{self, BufReader, BufWriter, Read},
+    ops::RangeInclusive,
+    path::Path,
+    process, slice,
+    sync::OnceLock,
 };
+use test_runner::{LoadFailure, TestFailure};
+use tracer::{TraceFilter, Tracer};
+
+const USAGE: &str = "\
+Usage:
+  moss                             Start a REPL.
+  moss [run] [options] <source> [args]
+                                   Run a program, passing it any arguments after the source.
+  moss check [--wrapping] <source> Check a program for errors without running it.
+  moss ast [--typed] <source>      Print a program's syntax tree.
+  moss tokens <source>             Print the tokens a program is made of.
+  moss lint [levels] <source>      Check a program for likely mistakes.
+  moss debug <source>              Step through a program as it runs.
+  moss fmt [--check] <source>      Format a program, in place for a file or to stdout otherwise.
+  moss lsp                         Serve the Language Server Protocol over stdin and stdout.
+  moss test <path>                 Run the tests in a file, or in every file under a directory.
+
+A source is the path to a file, `-` to read it from stdin, or `-e <code>`.
+
+Options for any command:
+  --error-format human|json        Write errors for people to read, or one JSON object per line.
+
+Run options:
+  --wrapping                       Wrap integer arithmetic instead of failing on overflow.
+  --backend interpreter|vm         Choose how the program is run. Defaults to the interpreter.
+  --profile                        Print the time and steps spent in each function to stderr.
+  --profile-folded <path>          Profile, and write the steps taken under each stack of calls to
+                                   the file, folded for flame graph tools.
+  --trace                          Log each step the interpreter takes to stderr.
+  --trace-calls                    Trace only the steps which call or return from functions.
+  --trace-function <name>          Trace only the steps taken in the named function.
+  --trace-lines <from>[-<to>]      Trace only the steps taken on the lines.
+  --trace-buffer <count>           Keep only the latest steps traced, and log them if the program
+                                   fails.
+
+Format options:
+  --check                          Change nothing, but fail if the program isn't formatted.
+
+Lint levels:
+  --allow <lint>, --warn <lint>, --deny <lint>";
+
+// Each class of failure exits with a status of its own, so scripts can tell them apart.
+#[derive(Clone, Copy)]
+enum Failure {
+    Usage = 2,
+    Load = 3,
+    Parse = 4,
+    Type = 5,
+    Runtime = 6,
+    Lint = 7,
+    Unformatted = 8,
+    TestFailed = 9,
+    // As for any process stopped by Ctrl-C.
+    Interrupted = 130,
+}
+
+impl Failure {
+    fn exit(self) -> ! {
+        process::exit(self as i32)
+    }
+}
+
+// How diagnostics are written to stderr.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+static ERROR_FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let path = &args[1];
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let _ = ERROR_FORMAT.set(take_error_format(&mut args));
 
-    let code = fs::read_to_string(path);
+    let Some(command) = args.first() else {
+        return run_repl();
+    };
 
-    let code = match code {
-        Ok(code) => code,
-        Err(err) => {
-            println!("Error loading file: {}", err);
-            return;
+    match command.as_str() {
+        "run" => run(&args[1..]),
+        "check" => check(&args[1..]),
+        "ast" => ast(&args[1..]),
+        "tokens" => tokens(&args[1..]),
+        "lint" => lint(&args[1..]),
+        "debug" => debug(&args[1..]),
+        "fmt" => fmt(&args[1..]),
+        "lsp" => serve_lsp(&args[1..]),
+        "test" => test(&args[1..]),
+        "help" | "--help" | "-h" => println!("{USAGE}"),
+        // `moss <source>` is short for `moss run <source>`.
+        _ => run(&args),
+    }
+}
+
+fn run(args: &[String]) {
+    let mut exec = ExecContext::new();
+    let mut use_vm = false;
+    let mut profile = false;
+    let mut folded_path = None;
+    let mut trace = false;
+    let mut trace_filter = TraceFilter::default();
+    let mut trace_buffer = None;
+
+    let (source, script_args) = parse_args_and_rest(args, |option, rest| match option {
+        "--wrapping" => {
+            exec.arithmetic = ArithmeticMode::Wrapping;
+            true
+        }
+        "--backend" => {
+            match rest.next().map(String::as_str) {
+                Some("interpreter") => use_vm = false,
+                Some("vm") => use_vm = true,
+                backend => usage_error(&format!(
+                    "Unknown backend: \"{}\"",
+                    backend.unwrap_or_default()
+                )),
+            }
+            true
+        }
+        "--profile" => {
+            profile = true;
+            true
+        }
+        "--profile-folded" => {
+            match rest.next() {
+                Some(path) => folded_path = Some(path.clone()),
+                None => usage_error("Expected a path to write folded stacks to."),
+            }
+            profile = true;
+            true
+        }
+        "--trace" => {
+            trace = true;
+            true
         }
+        "--trace-calls" => {
+            trace_filter.calls_only = true;
+            trace = true;
+            true
+        }
+        "--trace-function" => {
+            match rest.next() {
+                Some(name) => trace_filter.function = Some(name.clone()),
+                None => usage_error("Expected the name of a function to trace."),
+            }
+            trace = true;
+            true
+        }
+        "--trace-lines" => {
+            match rest.next().and_then(|lines| parse_line_range(lines)) {
+                Some(lines) => trace_filter.lines = Some(lines),
+                None => usage_error("Expected a line, or a range of lines such as 3-10, to trace."),
+            }
+            trace = true;
+            true
+        }
+        "--trace-buffer" => {
+            match rest.next().and_then(|count| count.parse().ok()) {
+                Some(count) if count > 0 => trace_buffer = Some(count),
+                _ => usage_error("Expected a number of steps to keep traced."),
+            }
+            trace = true;
+            true
+        }
+        _ => false,
+    });
+
+    // The profiler and tracer follow the interpreter's steps, which the VM doesn't take.
+    if profile && use_vm {
+        usage_error("Only the interpreter can be profiled.");
+    }
+
+    if trace && use_vm {
+        usage_error("Only the interpreter can be traced.");
+    }
+
+    if trace && profile {
+        usage_error("A program can't be traced and profiled at once.");
+    }
+
+    let (name, code) = source.load();
+
+    let parsed = parse(&name, &code);
+    let analyzed = analyze(&name, &code, parsed);
+    let optimized = optimize(&name, &code, analyzed, exec.arithmetic);
+
+    handle_ctrl_c(exec.interrupt.clone());
+
+    let io = IoContext {
+        reader: BufReader::new(io::stdin().lock()),
+        writer: BufWriter::new(io::stdout().lock()),
+        args: script_args.to_vec(),
     };
 
-    let parsed = ProgramParser::new().parse(&code);
+    let mut profiler = profile.then(|| Profiler::new(name.clone(), code.clone()));
+    let mut tracer =
+        trace.then(|| Tracer::new(code.clone(), trace_filter, io::stderr(), trace_buffer));
 
-    if let Err(error) = parsed {
-        println!("Parse Error: {}", error);
-        return;
+    let run_result = if let Some(profiler) = &mut profiler {
+        interpreter::inspect_program(
+            optimized,
+            &mut exec,
+            io,
+            get_builtin_func_bindings(),
+            get_builtin_funcs(),
+            profiler,
+        )
+    } else if let Some(tracer) = &mut tracer {
+        interpreter::inspect_program(
+            optimized,
+            &mut exec,
+            io,
+            get_builtin_func_bindings(),
+            get_builtin_funcs(),
+            tracer,
+        )
+    } else if use_vm {
+        let compiled = compiler::compile_program(optimized);
+
+        vm::run_program(
+            &compiled,
+            &mut exec,
+            io,
+            get_builtin_func_bindings(),
+            get_builtin_funcs(),
+        )
+    } else {
+        interpreter::interpret_program(
+            optimized,
+            &mut exec,
+            io,
+            get_builtin_func_bindings(),
+            get_builtin_funcs(),
+        )
+    };
+
+    // Programs which fail are profiled up to where they failed.
+    if let Some(profiler) = profiler {
+        eprint!("{}", profiler.table());
+
+        if let Some(path) = folded_path {
+            if let Err(err) = fs::write(path, profiler.folded_stacks()) {
+                eprintln!("Error writing file: {}", err);
+                Failure::Load.exit();
+            }
+        }
+    }
+
+    if let Err(error) = run_result {
+        // The steps leading up to a failure are what a trace buffer is kept for. Exiting on purpose
+        // isn't a failure.
+        if let Some(tracer) = &mut tracer {
+            if !matches!(error, RuntimeError::Exit(_)) {
+                let _ = tracer.dump();
+            }
+        }
+
+        runtime_error(error, name, code, exec.call_stack);
+    }
+}
+
+// Parses a line, or an inclusive range of lines such as `3-10`.
+fn parse_line_range(lines: &str) -> Option<RangeInclusive<usize>> {
+    let (from, to) = lines.split_once('-').unwrap_or((lines, lines));
+    let (from, to) = (from.parse().ok()?, to.parse().ok()?);
+
+    (from <= to).then_some(from..=to)
+}
+
+fn check(args: &[String]) {
+    let mut arithmetic = ArithmeticMode::Checked;
+
+    let source = parse_args(args, |option, _| match option {
+        "--wrapping" => {
+            arithmetic = ArithmeticMode::Wrapping;
+            true
+        }
+        _ => false,
+    });
+
+    let (name, code) = source.load();
+
+    // The optimizer finds some errors of its own, such as constant division by zero.
+    let parsed = parse(&name, &code);
+    let analyzed = analyze(&name, &code, parsed);
+    optimize(&name, &code, analyzed, arithmetic);
+}
+
+fn ast(args: &[String]) {
+    let mut typed = false;
+
+    let source = parse_args(args, |option, _| match option {
+        "--typed" => {
+            typed = true;
+            true
+        }
+        _ => false,
+    });
+
+    let (name, code) = source.load();
+    let parsed = parse(&name, &code);
+
+    if typed {
+        println!("{:#?}", analyze(&name, &code, parsed));
+    } else {
+        println!("{:#?}", parsed);
     }
+}
+
+fn tokens(args: &[String]) {
+    let (name, code) = parse_args(args, |_, _| false).load();
+
+    let tokens = match tokenize(&code) {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            let json = error.to_json(&name, &code);
+            report(format!("Parse Error: {}", error.display(name, code)), json);
+            Failure::Parse.exit();
+        }
+    };
+
+    for (kind, span) in tokens {
+        let line = get_line_number(&code, span.start);
+        let column = get_column_number(&code, span.start);
+        let location = format!("{line}:{column}");
+
+        println!("{location:<8}{kind:<12}{}", &code[span.start..span.end]);
+    }
+}
+
+fn lint(args: &[String]) {
+    let mut config = LintConfig::new();
+
+    let source = parse_args(args, |option, rest| {
+        let level = match option {
+            "--allow" => LintLevel::Allow,
+            "--warn" => LintLevel::Warn,
+            "--deny" => LintLevel::Deny,
+            _ => return false,
+        };
+
+        let name = rest.next().map(String::as_str).unwrap_or_default();
 
-    let analyzed = analyzer::analyze_program(
-        parsed.unwrap(),
+        if !config.set_level(name, level) {
+            usage_error(&format!("Unknown lint: \"{name}\""));
+        }
+
+        true
+    });
+
+    let (name, code) = source.load();
+
+    let parsed = parse(&name, &code);
+    let analyzed = analyze(&name, &code, parsed);
+
+    config.apply_directives(&code);
+
+    let warnings = lint_program(&analyzed, &get_builtin_func_bindings(), &config);
+    let mut denied = 0;
+
+    for (warning, level) in warnings {
+        let is_denied = level == LintLevel::Deny;
+        let json = warning.to_json(&name, &code, is_denied);
+        let warning = warning.display(name.clone(), code.clone());
+
+        if is_denied {
+            denied += 1;
+            report(format!("Lint Error: {}", warning), json);
+        } else {
+            report(format!("Warning: {}", warning), json);
+        }
+    }
+
+    if denied > 0 {
+        Failure::Lint.exit();
+    }
+}
+
+fn debug(args: &[String]) {
+    let source = parse_args(args, |_, _| false);
+
+    if let Source::Stdin = source {
+        usage_error(
+            "The debugger reads its commands from stdin, so can't read the program from it.",
+        );
+    }
+
+    let (name, code) = source.load();
+
+    // The program isn't optimized, so that every binding in the source is still there to inspect.
+    let parsed = parse(&name, &code);
+    let analyzed = analyze(&name, &code, parsed);
+
+    let mut exec = ExecContext::new();
+    let mut debugger = Debugger::new(name.clone(), code.clone());
+
+    handle_ctrl_c(exec.interrupt.clone());
+
+    let io = IoContext {
+        reader: BufReader::new(io::stdin().lock()),
+        writer: BufWriter::new(io::stdout().lock()),
+        args: Vec::new(),
+    };
+
+    let run_result = interpreter::inspect_program(
+        analyzed,
+        &mut exec,
+        io,
         get_builtin_func_bindings(),
-        get_builtin_type_bindings(),
+        get_builtin_funcs(),
+        &mut debugger,
     );
 
-    if let Err(error) = analyzed {
-        println!("Type Error: {}", error.display(path.clone(), code));
+    match run_result {
+        Ok(_) => println!("Program finished."),
+        Err(_) if debugger.has_quit() => {}
+        Err(error) => runtime_error(error, name, code, exec.call_stack),
+    }
+}
+
+fn fmt(args: &[String]) {
+    let mut check = false;
+
+    let source = parse_args(args, |option, _| match option {
+        "--check" => {
+            check = true;
+            true
+        }
+        _ => false,
+    });
+
+    let path = match &source {
+        Source::Path(path) => Some(path.clone()),
+        _ => None,
+    };
+
+    let (name, code) = source.load();
+
+    let formatted = format_program(&code).unwrap_or_else(|errors| {
+        for error in errors {
+            let json = error.to_json(&name, &code);
+            report(
+                format!("Parse Error: {}", error.display(name.clone(), code.clone())),
+                json,
+            );
+        }
+        Failure::Parse.exit();
+    });
+
+    if check {
+        if formatted != code {
+            eprintln!("{name} is not formatted.");
+            Failure::Unformatted.exit();
+        }
         return;
     }
 
-    let run_result = interpreter::interpret_program(
-        analyzed.unwrap(),
-        ExecContext {
-            control_stack: Vec::new(),
-            value_stack: Vec::new(),
-            scope_stack: ScopeStack::new(),
-        },
-        IoContext {
-            reader: BufReader::new(io::stdin().lock()),
-            writer: BufWriter::new(io::stdout().lock()),
-        },
+    match path {
+        Some(path) if formatted != code => {
+            if let Err(err) = fs::write(&path, formatted) {
+                eprintln!("Error writing file: {}", err);
+                Failure::Load.exit();
+            }
+        }
+        Some(_) => {}
+        None => print!("{formatted}"),
+    }
+}
+
+fn serve_lsp(args: &[String]) {
+    if let Some(arg) = args.first() {
+        usage_error(&format!("Unexpected argument: \"{arg}\""));
+    }
+
+    match lsp::run(io::stdin().lock(), io::stdout().lock()) {
+        Ok(true) => {}
+        // Exiting without being asked to shut down first is an error, by the protocol.
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            eprintln!("Error communicating with the editor: {}", err);
+            Failure::Load.exit();
+        }
+    }
+}
+
+fn test(args: &[String]) {
+    let path = match args {
+        [arg, ..] if arg.starts_with('-') => usage_error(&format!("Unknown option: \"{arg}\"")),
+        [path] => path,
+        [] => usage_error("Expected a path to find tests in."),
+        _ => usage_error("Expected a single path."),
+    };
+
+    let files = test_runner::discover(Path::new(path)).unwrap_or_else(|err| {
+        eprintln!("Error finding tests: {}", err);
+        Failure::Load.exit();
+    });
+
+    let mut passed = 0;
+    // What failed, the diagnostics saying why, and anything it printed first.
+    let mut failures = Vec::new();
+
+    for file in files {
+        let name = file.display().to_string();
+
+        let code = fs::read_to_string(&file).unwrap_or_else(|err| {
+            eprintln!("Error loading file: {}", err);
+            Failure::Load.exit();
+        });
+
+        let outcomes = match test_runner::run_tests(&code) {
+            Ok(outcomes) => outcomes,
+            Err(failure) => {
+                let diagnostics = match failure {
+                    LoadFailure::Parse(errors) => errors
+                        .into_iter()
+                        .map(|error| {
+                            let json = error.to_json(&name, &code);
+                            let error = error.display(name.clone(), code.clone());
+                            (format!("Parse Error: {error}"), json)
+                        })
+                        .collect(),
+                    LoadFailure::Type(errors) => errors
+                        .into_iter()
+                        .map(|error| {
+                            let json = error.to_json(&name, &code);
+                            let error = error.display(name.clone(), code.clone());
+                            (format!("Type Error: {error}"), json)
+                        })
+                        .collect(),
+                };
+
+                println!("FAIL {name}");
+                failures.push((name, diagnostics, String::new()));
+                continue;
+            }
+        };
+
+        for outcome in outcomes {
+            let line = get_line_number(&code, outcome.span.start);
+            let column = get_column_number(&code, outcome.span.start);
+            let test = format!("{} ({name}:{line}:{column})", outcome.name);
+
+            match outcome.failure {
+                None => {
+                    println!("PASS {test}");
+                    passed += 1;
+                }
+                Some(TestFailure { error, backtrace }) => {
+                    let json = error.to_json(&name, &code, &backtrace);
+                    let error = error.display(name.clone(), code.clone(), backtrace);
+
+                    println!("FAIL {test}");
+                    failures.push((
+                        test,
+                        vec![(format!("Runtime Error: {error}"), json)],
+                        outcome.output,
+                    ));
+                }
+            }
+        }
+    }
+
+    for (test, diagnostics, output) in &failures {
+        println!("\n---- {test} ----");
+        print!("{output}");
+
+        for (human, json) in diagnostics {
+            println!("{}", format_diagnostic(human, json).trim_end());
+        }
+    }
+
+    println!("\n{passed} passed; {} failed.", failures.len());
+
+    if !failures.is_empty() {
+        Failure::TestFailed.exit();
+    }
+}
+
+fn run_repl() {
+    let mut repl = Repl::new();
+
+    handle_ctrl_c(repl.interrupt_handle());
+
+    let io = IoContext {
+        reader: BufReader::new(io::stdin().lock()),
+        writer: BufWriter::new(io::stdout().lock()),
+        args: Vec::new(),
+    };
+
+    if let Err(error) = repl::run(&mut repl, io) {
+        runtime_error(error, String::new(), String::new(), Vec::new());
+    }
+}
+
+// Where a program's source is read from.
+enum Source {
+    Path(String),
+    Stdin,
+    Inline(String),
+}
+
+impl Source {
+    // Reads the code, along with the name errors should refer to it by.
+    fn load(self) -> (String, String) {
+        match self {
+            Source::Path(path) => match fs::read_to_string(&path) {
+                Ok(code) => (path, code),
+                Err(err) => {
+                    eprintln!("Error loading file: {}", err);
+                    Failure::Load.exit();
+                }
+            },
+            Source::Stdin => {
+                let mut code = String::new();
+
+                if let Err(err) = io::stdin().read_to_string(&mut code) {
+                    eprintln!("Error reading stdin: {}", err);
+                    Failure::Load.exit();
+                }
+
+                (String::from("<stdin>"), code)
+            }
+            Source::Inline(code) => (String::from("<inline>"), code),
+        }
+    }
+}
+
+// Like `parse_args`, but stops at the source, returning it along with the arguments after it.
+fn parse_args_and_rest<'a>(
+    args: &'a [String],
+    mut option: impl FnMut(&str, &mut slice::Iter<'a, String>) -> bool,
+) -> (Source, &'a [String]) {
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let source = match arg.as_str() {
+            "-" => Source::Stdin,
+            "-e" => match args.next() {
+                Some(code) => Source::Inline(code.clone()),
+                None => usage_error("Expected code to evaluate after -e."),
+            },
+            arg if arg.starts_with("--") => {
+                if !option(arg, &mut args) {
+                    usage_error(&format!("Unknown option: \"{arg}\""));
+                }
+                continue;
+            }
+            path => Source::Path(path.to_string()),
+        };
+
+        return (source, args.as_slice());
+    }
+
+    usage_error("Expected a source to read the program from.")
+}
+
+// Finds the source among a subcommand's arguments. Anything else starting with `--` is handed to
+// `option`, along with the arguments after it to take a value from, and is a usage error unless
+// `option` returns true.
+fn parse_args<'a>(
+    args: &'a [String],
+    mut option: impl FnMut(&str, &mut slice::Iter<'a, String>) -> bool,
+) -> Source {
+    let mut source = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let arg_source = match arg.as_str() {
+            "-" => Source::Stdin,
+            "-e" => match args.next() {
+                Some(code) => Source::Inline(code.clone()),
+                None => usage_error("Expected code to evaluate after -e."),
+            },
+            arg if arg.starts_with("--") => {
+                if !option(arg, &mut args) {
+                    usage_error(&format!("Unknown option: \"{arg}\""));
+                }
+                continue;
+            }
+            path => Source::Path(path.to_string()),
+        };
+
+        if source.replace(arg_source).is_some() {
+            usage_error("Expected a single source.");
+        }
+    }
+
+    source.unwrap_or_else(|| usage_error("Expected a source to read the program from."))
+}
+
+// `--error-format` goes with any command, so it's taken out of the arguments before the command
+// sees them. Code given with `-e` is left alone, whatever it looks like.
+fn take_error_format(args: &mut Vec<String>) -> ErrorFormat {
+    let mut format = ErrorFormat::Human;
+    let mut i = 0;
+
+    while i < args.len() {
+        let value = match args[i].as_str() {
+            "-e" => {
+                i += 2;
+                continue;
+            }
+            "--error-format" => {
+                args.remove(i);
+                (i < args.len()).then(|| args.remove(i))
+            }
+            arg => match arg.strip_prefix("--error-format=") {
+                Some(value) => {
+                    let value = value.to_string();
+                    args.remove(i);
+                    Some(value)
+                }
+                None => {
+                    i += 1;
+                    continue;
+                }
+            },
+        };
+
+        format = match value.as_deref() {
+            Some("human") => ErrorFormat::Human,
+            Some("json") => ErrorFormat::Json,
+            value => usage_error(&format!(
+                "Unknown error format: \"{}\"",
+                value.unwrap_or_default()
+            )),
+        };
+    }
+
+    format
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    Failure::Usage.exit();
+}
+
+fn parse(name: &str, code: &str) -> Expr {
+    parse_program(code).unwrap_or_else(|errors| {
+        for error in errors {
+            let json = error.to_json(name, code);
+            report(
+                format!("Parse Error: {}", error.display(name.into(), code.into())),
+                json,
+            );
+        }
+        Failure::Parse.exit();
+    })
+}
+
+fn analyze(name: &str, code: &str, parsed: Expr) -> TypedExpr {
+    analyzer::analyze_program(
+        parsed,
         get_builtin_func_bindings(),
-        get_builtin_funcs(),
+        get_builtin_type_bindings(),
+    )
+    .unwrap_or_else(|errors| {
+        for error in errors {
+            let json = error.to_json(name, code);
+            report(
+                format!("Type Error: {}", error.display(name.into(), code.into())),
+                json,
+            );
+        }
+        Failure::Type.exit();
+    })
+}
+
+fn optimize(name: &str, code: &str, analyzed: TypedExpr, arithmetic: ArithmeticMode) -> TypedExpr {
+    optimizer::optimize_program(analyzed, arithmetic).unwrap_or_else(|errors| {
+        for error in errors {
+            let json = error.to_json(name, code);
+            report(
+                format!("Type Error: {}", error.display(name.into(), code.into())),
+                json,
+            );
+        }
+        Failure::Type.exit();
+    })
+}
+
+fn runtime_error(error: RuntimeError, name: String, code: String, backtrace: Vec<CallFrame>) -> ! {
+    // The program asked to stop, so there's nothing to report.
+    if let RuntimeError::Exit(code) = error {
+        process::exit(code);
+    }
+
+    let failure = match error {
+        RuntimeError::Interrupted => Failure::Interrupted,
+        _ => Failure::Runtime,
+    };
+
+    let json = error.to_json(&name, &code, &backtrace);
+    report(
+        format!("Runtime Error: {}", error.display(name, code, backtrace)),
+        json,
     );
+    failure.exit();
+}
 
-    if let Err(error) = run_result {
-        println!("Runtime Error: {}", error.message);
-        return;
+// Writes a diagnostic to stderr, in whichever format was asked for.
+fn report(human: String, json: Value) {
+    eprintln!("{}", format_diagnostic(&human, &json));
+}
+
+// A diagnostic in whichever format was asked for.
+fn format_diagnostic(human: &str, json: &Value) -> String {
+    match ERROR_FORMAT.get() {
+        Some(ErrorFormat::Json) => json.to_string(),
+        _ => human.to_string(),
     }
 }
+
+// The first Ctrl-C stops the program at its next step, so it can report where it got to. A second
+// exits straight away, for programs stuck somewhere they can't be stopped, like waiting on input.
+// Should the handler fail to install, Ctrl-C just kills the process as usual.
+fn handle_ctrl_c(interrupt: InterruptHandle) {
+    let handler = move || {
+        if interrupt.is_interrupted() {
+            unsafe { libc::_exit(Failure::Interrupted as i32) };
+        }
+
+        interrupt.interrupt();
+    };
+
+    // Safety: the handler only touches an atomic flag or exits, both of which are safe to do from
+    // a signal handler.
+    let _ = unsafe { signal_hook_registry::register(libc::SIGINT, handler) };
+}
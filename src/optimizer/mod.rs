@@ -0,0 +1,500 @@
+use std::rc::Rc;
+
+use crate::ast::typed::typed_block::TypedBlock;
+use crate::ast::typed::typed_expr::TypedExpr;
+use crate::ast::typed::{TypedFunc, TypedFuncCall, TypedLiteral, TypedStmt};
+use crate::ast::Span;
+use crate::errors::type_error::TypeError;
+use crate::scopes::scope::Scope;
+use crate::state::exec_context::ArithmeticMode;
+use crate::typing::Type;
+
+// Immutable bindings whose values are known before the program runs. Bindings which aren't
+// constant are still recorded, as `None`, so that they hide constants of the same name in
+// enclosing scopes.
+type ConstantScopes = Vec<Scope<Option<TypedLiteral>>>;
+
+type BinaryOpConstructor = fn(Rc<TypedExpr>, Rc<TypedExpr>, Type, Span) -> TypedExpr;
+
+/// Rewrites an analyzed program ahead of execution. Arithmetic and comparisons on literals are
+/// folded, immutable bindings of literals are inlined, and `if` expressions with a literal
+/// condition are replaced by the branch that always runs. Integer operations which would overflow
+/// or divide by zero are reported here, since they can't succeed at runtime either.
+pub fn optimize_program(
+    program: TypedExpr,
+    arithmetic: ArithmeticMode,
+) -> Result<TypedExpr, Vec<TypeError>> {
+    let mut constants = ConstantScopes::new();
+    let mut errors = Vec::new();
+
+    let optimized = fold_expr(&mut constants, arithmetic, &mut errors, program);
+
+    if errors.is_empty() {
+        Ok(optimized)
+    } else {
+        Err(errors)
+    }
+}
+
+fn fold_expr(
+    constants: &mut ConstantScopes,
+    arithmetic: ArithmeticMode,
+    errors: &mut Vec<TypeError>,
+    expr: TypedExpr,
+) -> TypedExpr {
+    use TypedExpr::*;
+
+    match expr {
+        // Binary operations
+        Eq(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "==",
+            Eq,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Gt(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            ">",
+            Gt,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Lt(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "<",
+            Lt,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Gte(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            ">=",
+            Gte,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Lte(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "<=",
+            Lte,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Add(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "+",
+            Add,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Sub(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "-",
+            Sub,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Mult(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "*",
+            Mult,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Div(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "/",
+            Div,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+        Modulo(l, r, ty, span) => fold_binary_op(
+            constants,
+            arithmetic,
+            errors,
+            "%",
+            Modulo,
+            Rc::unwrap_or_clone(l),
+            Rc::unwrap_or_clone(r),
+            ty,
+            span,
+        ),
+
+        // Unary operations
+        Negate(inner, ty, span) => fold_negate(
+            constants,
+            arithmetic,
+            errors,
+            Rc::unwrap_or_clone(inner),
+            ty,
+            span,
+        ),
+        Assignment {
+            ident,
+            slot,
+            expr,
+            span,
+        } => Assignment {
+            ident,
+            slot,
+            expr: Rc::new(fold_expr(
+                constants,
+                arithmetic,
+                errors,
+                Rc::unwrap_or_clone(expr),
+            )),
+            span,
+        },
+        Declaration {
+            ident,
+            slot,
+            is_mutable,
+            expr,
+            ty,
+            span,
+        } => {
+            let expr = fold_expr(constants, arithmetic, errors, Rc::unwrap_or_clone(expr));
+
+            let constant = match &expr {
+                Literal(literal, ..) if !is_mutable => Some(literal.clone()),
+                _ => None,
+            };
+
+            if let Some(scope) = constants.last_mut() {
+                scope.insert(ident.clone(), constant);
+            }
+
+            Declaration {
+                ident,
+                slot,
+                is_mutable,
+                expr: Rc::new(expr),
+                ty,
+                span,
+            }
+        }
+
+        // Postfix operations
+        FuncCall(call, ty, span) => {
+            let call = TypedFuncCall {
+                func_expr: Rc::new(fold_expr(
+                    constants,
+                    arithmetic,
+                    errors,
+                    Rc::unwrap_or_clone(call.func_expr),
+                )),
+                args: call
+                    .args
+                    .iter()
+                    .cloned()
+                    .map(|arg| fold_expr(constants, arithmetic, errors, arg))
+                    .collect(),
+                is_tail: call.is_tail,
+            };
+
+            FuncCall(call, ty, span)
+        }
+
+        // Control flow
+        If(cond, then_block, ty, span) => {
+            let cond = fold_expr(constants, arithmetic, errors, Rc::unwrap_or_clone(cond));
+
+            match cond {
+                Literal(TypedLiteral::Bool(true), ..) => fold_expr(
+                    constants,
+                    arithmetic,
+                    errors,
+                    Rc::unwrap_or_clone(then_block),
+                ),
+                // Nothing runs, which is the same as running an empty block.
+                Literal(TypedLiteral::Bool(false), ..) => {
+                    Block(TypedBlock::Interpreted(Rc::new([]), ty), then_block.span())
+                }
+                cond => If(
+                    Rc::new(cond),
+                    Rc::new(fold_expr(
+                        constants,
+                        arithmetic,
+                        errors,
+                        Rc::unwrap_or_clone(then_block),
+                    )),
+                    ty,
+                    span,
+                ),
+            }
+        }
+        IfElse(cond, then_block, else_expr, ty, span) => {
+            let cond = fold_expr(constants, arithmetic, errors, Rc::unwrap_or_clone(cond));
+
+            match cond {
+                Literal(TypedLiteral::Bool(true), ..) => fold_expr(
+                    constants,
+                    arithmetic,
+                    errors,
+                    Rc::unwrap_or_clone(then_block),
+                ),
+                Literal(TypedLiteral::Bool(false), ..) => fold_expr(
+                    constants,
+                    arithmetic,
+                    errors,
+                    Rc::unwrap_or_clone(else_expr),
+                ),
+                cond => IfElse(
+                    Rc::new(cond),
+                    Rc::new(fold_expr(
+                        constants,
+                        arithmetic,
+                        errors,
+                        Rc::unwrap_or_clone(then_block),
+                    )),
+                    Rc::new(fold_expr(
+                        constants,
+                        arithmetic,
+                        errors,
+                        Rc::unwrap_or_clone(else_expr),
+                    )),
+                    ty,
+                    span,
+                ),
+            }
+        }
+        Block(TypedBlock::Interpreted(stmts, ty), span) => {
+            constants.push(Scope::new());
+
+            let stmts = stmts
+                .iter()
+                .cloned()
+                .map(|stmt| TypedStmt {
+                    expr: fold_expr(constants, arithmetic, errors, stmt.expr),
+                })
+                .collect();
+
+            constants.pop();
+
+            Block(TypedBlock::Interpreted(stmts, ty), span)
+        }
+        Loop(block, span) => Loop(
+            Rc::new(fold_expr(
+                constants,
+                arithmetic,
+                errors,
+                Rc::unwrap_or_clone(block),
+            )),
+            span,
+        ),
+
+        // Primaries
+        Identifier(ident, slot, ty, span) => {
+            let constant = constants
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(&ident))
+                .cloned()
+                .flatten();
+
+            match constant {
+                Some(literal) => Literal(literal, ty, span),
+                None => Identifier(ident, slot, ty, span),
+            }
+        }
+        FuncDeclare(func, ty, span) => {
+            // Closures see the bindings in scope where they're called rather than where they're
+            // declared, so nothing from the enclosing scopes is inlined into function bodies.
+            let mut func_constants = ConstantScopes::new();
+
+            let func = TypedFunc {
+                block: Rc::new(fold_expr(
+                    &mut func_constants,
+                    arithmetic,
+                    errors,
+                    Rc::unwrap_or_clone(func.block),
+                )),
+                ..func
+            };
+
+            FuncDeclare(func, ty, span)
+        }
+        List(items, ty, span) => List(
+            items
+                .iter()
+                .cloned()
+                .map(|item| fold_expr(constants, arithmetic, errors, item))
+                .collect(),
+            ty,
+            span,
+        ),
+
+        expr @ (Block(TypedBlock::Builtin(..) | TypedBlock::Compiled(..), _)
+        | Break(_)
+        | Literal(..)
+        | Unknown(_)) => expr,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fold_binary_op(
+    constants: &mut ConstantScopes,
+    arithmetic: ArithmeticMode,
+    errors: &mut Vec<TypeError>,
+    op: &str,
+    constructor: BinaryOpConstructor,
+    left: TypedExpr,
+    right: TypedExpr,
+    ty: Type,
+    span: Span,
+) -> TypedExpr {
+    let left = fold_expr(constants, arithmetic, errors, left);
+    let right = fold_expr(constants, arithmetic, errors, right);
+
+    if let (TypedExpr::Literal(l, ..), TypedExpr::Literal(r, ..)) = (&left, &right) {
+        match eval_binary_op(arithmetic, op, l, r, span) {
+            Ok(Some(literal)) => return TypedExpr::Literal(literal, ty, span),
+            Ok(None) => {}
+            Err(error) => errors.push(error),
+        }
+    }
+
+    constructor(Rc::new(left), Rc::new(right), ty, span)
+}
+
+fn fold_negate(
+    constants: &mut ConstantScopes,
+    arithmetic: ArithmeticMode,
+    errors: &mut Vec<TypeError>,
+    inner: TypedExpr,
+    ty: Type,
+    span: Span,
+) -> TypedExpr {
+    let inner = fold_expr(constants, arithmetic, errors, inner);
+
+    let literal = match &inner {
+        TypedExpr::Literal(TypedLiteral::Int(int), ..) => match arithmetic {
+            ArithmeticMode::Checked => match int.checked_neg() {
+                Some(negated) => Some(TypedLiteral::Int(negated)),
+                None => {
+                    errors.push(TypeError::UnaryOpOverflow("-".to_string(), *int, span));
+                    None
+                }
+            },
+            ArithmeticMode::Wrapping => Some(TypedLiteral::Int(int.wrapping_neg())),
+        },
+        TypedExpr::Literal(TypedLiteral::Float(float), ..) => Some(TypedLiteral::Float(-float)),
+        _ => None,
+    };
+
+    match literal {
+        Some(literal) => TypedExpr::Literal(literal, ty, span),
+        None => TypedExpr::Negate(Rc::new(inner), ty, span),
+    }
+}
+
+// Evaluates an operation on two literals the same way the interpreter would, or returns `None` if
+// the operation isn't one that gets folded.
+fn eval_binary_op(
+    arithmetic: ArithmeticMode,
+    op: &str,
+    left: &TypedLiteral,
+    right: &TypedLiteral,
+    span: Span,
+) -> Result<Option<TypedLiteral>, TypeError> {
+    let literal = match (left, right) {
+        (TypedLiteral::Int(l), TypedLiteral::Int(r)) => match op {
+            "==" => TypedLiteral::Bool(l == r),
+            ">" => TypedLiteral::Bool(l > r),
+            "<" => TypedLiteral::Bool(l < r),
+            ">=" => TypedLiteral::Bool(l >= r),
+            "<=" => TypedLiteral::Bool(l <= r),
+            _ => TypedLiteral::Int(eval_int_op(arithmetic, op, *l, *r, span)?),
+        },
+        (TypedLiteral::Float(l), TypedLiteral::Float(r)) => match op {
+            "==" => TypedLiteral::Bool(l == r),
+            ">" => TypedLiteral::Bool(l > r),
+            "<" => TypedLiteral::Bool(l < r),
+            ">=" => TypedLiteral::Bool(l >= r),
+            "<=" => TypedLiteral::Bool(l <= r),
+            "+" => TypedLiteral::Float(l + r),
+            "-" => TypedLiteral::Float(l - r),
+            "*" => TypedLiteral::Float(l * r),
+            "/" => TypedLiteral::Float(l / r),
+            "%" => TypedLiteral::Float(l % r),
+            _ => return Ok(None),
+        },
+        (TypedLiteral::String(l), TypedLiteral::String(r)) => match op {
+            "==" => TypedLiteral::Bool(l == r),
+            "+" => TypedLiteral::String(format!("{l}{r}")),
+            _ => return Ok(None),
+        },
+        (TypedLiteral::Bool(l), TypedLiteral::Bool(r)) => match op {
+            "==" => TypedLiteral::Bool(l == r),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(literal))
+}
+
+fn eval_int_op(
+    arithmetic: ArithmeticMode,
+    op: &str,
+    l: i32,
+    r: i32,
+    span: Span,
+) -> Result<i32, TypeError> {
+    if (op == "/" || op == "%") && r == 0 {
+        return Err(TypeError::DivisionZero(span));
+    }
+
+    let (checked, wrapped) = match op {
+        "+" => (l.checked_add(r), l.wrapping_add(r)),
+        "-" => (l.checked_sub(r), l.wrapping_sub(r)),
+        "*" => (l.checked_mul(r), l.wrapping_mul(r)),
+        "/" => (l.checked_div(r), l.wrapping_div(r)),
+        "%" => (l.checked_rem(r), l.wrapping_rem(r)),
+        _ => unreachable!(),
+    };
+
+    match arithmetic {
+        ArithmeticMode::Checked => {
+            checked.ok_or_else(|| TypeError::BinaryOpOverflow(op.to_string(), l, r, span))
+        }
+        ArithmeticMode::Wrapping => Ok(wrapped),
+    }
+}
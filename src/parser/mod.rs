@@ -0,0 +1,25 @@
+pub mod tokens;
+
+use crate::{ast::untyped::Expr, errors::parse_error::ParseError, grammar::ProgramParser};
+
+pub fn parse_program(code: &str) -> Result<Expr, Vec<ParseError>> {
+    let mut recovered = Vec::new();
+    let parsed = ProgramParser::new().parse(&mut recovered, code);
+
+    let mut errors: Vec<ParseError> = recovered
+        .into_iter()
+        .map(|recovery| ParseError::from(recovery.error))
+        .collect();
+
+    match parsed {
+        Ok(program) if errors.is_empty() => return Ok(program),
+        Ok(_) => {}
+        Err(error) => errors.push(ParseError::from(error)),
+    }
+
+    // Recovered errors are collected as the statements they're in reduce, which needn't be the
+    // order they appear in.
+    errors.sort_by_key(|error| error.span().start);
+
+    Err(errors)
+}
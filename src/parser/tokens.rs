@@ -0,0 +1,109 @@
+use std::fmt::{Display, Formatter, Result};
+
+use lalrpop_util::lexer::MatcherBuilder;
+
+use crate::{ast::Span, errors::parse_error::ParseError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Bool,
+    Float,
+    Identifier,
+    Int,
+    Keyword,
+    Str,
+    Symbol,
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let name = match self {
+            TokenKind::Bool => "bool",
+            TokenKind::Float => "float",
+            TokenKind::Identifier => "identifier",
+            TokenKind::Int => "int",
+            TokenKind::Keyword => "keyword",
+            TokenKind::Str => "string",
+            TokenKind::Symbol => "symbol",
+        };
+
+        // Padded, so kinds can be lined up in columns.
+        f.pad(name)
+    }
+}
+
+// The terminals from the `match` block in grammar.lalrpop, which the generated lexer is built from
+// but doesn't expose. Patterns without a kind are skipped. Of two matches the same length, the one
+// listed last wins, so keywords come after identifiers.
+const TERMINALS: &[(&str, Option<TokenKind>)] = &[
+    (r"\s*", None),
+    (r"//[^\n\r]*[\n\r]*", None),
+    (r"[0-9]+", Some(TokenKind::Int)),
+    (r"[0-9]+\.[0-9]+", Some(TokenKind::Float)),
+    (r#""[^"]*""#, Some(TokenKind::Str)),
+    (r"[a-zA-Z_][a-zA-Z_0-9]*", Some(TokenKind::Identifier)),
+    ("let", Some(TokenKind::Keyword)),
+    ("mut", Some(TokenKind::Keyword)),
+    ("if", Some(TokenKind::Keyword)),
+    ("elif", Some(TokenKind::Keyword)),
+    ("else", Some(TokenKind::Keyword)),
+    ("loop", Some(TokenKind::Keyword)),
+    ("break", Some(TokenKind::Keyword)),
+    ("test", Some(TokenKind::Keyword)),
+    ("true", Some(TokenKind::Bool)),
+    ("false", Some(TokenKind::Bool)),
+    (r"\{", Some(TokenKind::Symbol)),
+    (r"\}", Some(TokenKind::Symbol)),
+    (r"\(", Some(TokenKind::Symbol)),
+    (r"\)", Some(TokenKind::Symbol)),
+    (r"\[", Some(TokenKind::Symbol)),
+    (r"\]", Some(TokenKind::Symbol)),
+    (";", Some(TokenKind::Symbol)),
+    (":", Some(TokenKind::Symbol)),
+    (r"\.", Some(TokenKind::Symbol)),
+    (",", Some(TokenKind::Symbol)),
+    (r"\|", Some(TokenKind::Symbol)),
+    ("=", Some(TokenKind::Symbol)),
+    ("==", Some(TokenKind::Symbol)),
+    (">", Some(TokenKind::Symbol)),
+    ("<", Some(TokenKind::Symbol)),
+    ("<=", Some(TokenKind::Symbol)),
+    (">=", Some(TokenKind::Symbol)),
+    (r"\+", Some(TokenKind::Symbol)),
+    ("-", Some(TokenKind::Symbol)),
+    (r"\*", Some(TokenKind::Symbol)),
+    ("/", Some(TokenKind::Symbol)),
+    ("%", Some(TokenKind::Symbol)),
+    ("=>", Some(TokenKind::Symbol)),
+];
+
+/// Splits source code into the tokens the parser reads it as, leaving out whitespace and comments.
+pub fn tokenize(code: &str) -> std::result::Result<Vec<(TokenKind, Span)>, ParseError> {
+    let terminals = TERMINALS
+        .iter()
+        .map(|(pattern, kind)| (*pattern, kind.is_none()));
+
+    // The patterns are fixed, so they always build.
+    let builder = MatcherBuilder::new(terminals).unwrap();
+
+    builder
+        .matcher::<&str>(code)
+        .map(|token| {
+            let (start, token, end) = token?;
+            let kind = TERMINALS[token.0].1.unwrap();
+
+            Ok((kind, Span { start, end }))
+        })
+        .collect()
+}
+
+/// The start of the first token at or after the position with the given text, or the end of the
+/// source if there isn't one. The tokens are spans into the source, in order, as from `tokenize`.
+pub fn find_token(source: &str, tokens: &[Span], from: usize, text: &str) -> usize {
+    let first = tokens.partition_point(|token| token.start < from);
+
+    tokens[first..]
+        .iter()
+        .find(|token| &source[token.start..token.end] == text)
+        .map_or(source.len(), |token| token.start)
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use crate::{
+    errors::location_frame::{get_column_number, get_line_number},
+    interpreter::Inspector,
+    state::exec_context::{CallFrame, ExecContext},
+};
+
+// What the code outside of any function is profiled as.
+const MAIN: &str = "<main>";
+
+/// What was measured for a single function, under the name it was declared with.
+#[derive(Clone, Debug, Default)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    /// Time spent in the function, including in whatever it called.
+    pub total_time: Duration,
+    /// Time spent in the function's own body.
+    pub self_time: Duration,
+    /// Steps taken in the function's own body.
+    pub steps: u64,
+}
+
+// A call in progress.
+struct Entry {
+    frame: CallFrame,
+    function: usize,
+    node: usize,
+    entered: Instant,
+    // Whether the function wasn't already in progress further down the stack. Only the outermost
+    // of a function's recursive calls adds to its total time, so no time is counted twice.
+    is_outermost: bool,
+}
+
+// A distinct stack of calls, as the function called on top of the stack below it.
+struct Node {
+    parent: Option<usize>,
+    function: usize,
+    steps: u64,
+}
+
+/// Measures where a program spends its time, function by function, by looking in on it after each
+/// step the interpreter takes. Functions are known by the binding they were declared with, however
+/// they're called, or by where they were declared when they were declared anonymously.
+pub struct Profiler {
+    file_name: String,
+    source: String,
+    functions: Vec<FunctionProfile>,
+    function_ids: HashMap<String, usize>,
+    // How many calls to each function are in progress.
+    active: Vec<usize>,
+    nodes: Vec<Node>,
+    node_ids: HashMap<(usize, usize), usize>,
+    // The call stack as of the last step, on top of the program itself.
+    stack: Vec<Entry>,
+    last_step: Instant,
+}
+
+impl Profiler {
+    pub fn new(file_name: String, source: String) -> Self {
+        let now = Instant::now();
+
+        let main = FunctionProfile {
+            name: MAIN.to_string(),
+            calls: 1,
+            ..FunctionProfile::default()
+        };
+
+        let entry = Entry {
+            // Never compared against, as the program has no frame of its own.
+            frame: CallFrame::new(MAIN.to_string(), Default::default()),
+            function: 0,
+            node: 0,
+            entered: now,
+            is_outermost: true,
+        };
+
+        let root = Node {
+            parent: None,
+            function: 0,
+            steps: 0,
+        };
+
+        Self {
+            file_name,
+            source,
+            functions: vec![main],
+            function_ids: HashMap::from([(MAIN.to_string(), 0)]),
+            active: vec![1],
+            nodes: vec![root],
+            node_ids: HashMap::new(),
+            stack: vec![entry],
+            last_step: now,
+        }
+    }
+
+    /// Every function that was called, the most time spent in first. Calls still in progress, as
+    /// when the program failed, count up to the last step.
+    pub fn functions(&self) -> Vec<FunctionProfile> {
+        let mut functions = self.functions.clone();
+
+        for entry in self.stack.iter().filter(|entry| entry.is_outermost) {
+            functions[entry.function].total_time += self.last_step - entry.entered;
+        }
+
+        functions.sort_by(|a, b| {
+            b.total_time
+                .cmp(&a.total_time)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        functions
+    }
+
+    /// The measurements as a table, one function to a row.
+    pub fn table(&self) -> String {
+        let functions = self.functions();
+
+        let width = functions
+            .iter()
+            .map(|function| function.name.len())
+            .max()
+            .unwrap_or_default()
+            .max("Function".len());
+
+        let mut table = format!(
+            "{:<width$}  {:>8}  {:>12}  {:>12}  {:>10}\n",
+            "Function", "Calls", "Total (ms)", "Self (ms)", "Steps"
+        );
+
+        for function in functions {
+            table += &format!(
+                "{:<width$}  {:>8}  {:>12.3}  {:>12.3}  {:>10}\n",
+                function.name,
+                function.calls,
+                function.total_time.as_secs_f64() * 1000.0,
+                function.self_time.as_secs_f64() * 1000.0,
+                function.steps,
+            );
+        }
+
+        table
+    }
+
+    /// The steps taken under each stack of calls, in the folded format flame graph tools read: the
+    /// functions called, outermost first and separated by semicolons, then the number of steps.
+    pub fn folded_stacks(&self) -> String {
+        let mut lines: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|node| node.steps > 0)
+            .map(|node| {
+                let mut names = vec![self.functions[node.function].name.as_str()];
+                let mut parent = node.parent;
+
+                while let Some(id) = parent {
+                    names.push(&self.functions[self.nodes[id].function].name);
+                    parent = self.nodes[id].parent;
+                }
+
+                names.reverse();
+                format!("{} {}\n", names.join(";"), node.steps)
+            })
+            .collect();
+
+        lines.sort();
+        lines.concat()
+    }
+
+    fn enter(&mut self, frame: &CallFrame, now: Instant) {
+        let name = self.name(frame);
+
+        let function = *self.function_ids.entry(name).or_insert_with_key(|name| {
+            self.functions.push(FunctionProfile {
+                name: name.clone(),
+                ..FunctionProfile::default()
+            });
+            self.active.push(0);
+            self.functions.len() - 1
+        });
+
+        let parent = self.stack.last().unwrap().node;
+
+        let node = *self
+            .node_ids
+            .entry((parent, function))
+            .or_insert_with(|| {
+                self.nodes.push(Node {
+                    parent: Some(parent),
+                    function,
+                    steps: 0,
+                });
+                self.nodes.len() - 1
+            });
+
+        self.functions[function].calls += 1;
+        self.active[function] += 1;
+
+        self.stack.push(Entry {
+            frame: frame.clone(),
+            function,
+            node,
+            entered: now,
+            is_outermost: self.active[function] == 1,
+        });
+    }
+
+    fn leave(&mut self, now: Instant) {
+        let entry = self.stack.pop().unwrap();
+
+        self.active[entry.function] -= 1;
+
+        if entry.is_outermost {
+            self.functions[entry.function].total_time += now - entry.entered;
+        }
+    }
+
+    fn name(&self, frame: &CallFrame) -> String {
+        if let Some(name) = &frame.declared_name {
+            return name.clone();
+        }
+
+        let line = get_line_number(&self.source, frame.declared_at.start);
+        let column = get_column_number(&self.source, frame.declared_at.start);
+
+        format!("<anonymous at {}:{line}:{column}>", self.file_name)
+    }
+}
+
+impl<R: Read, W: Write> Inspector<R, W> for Profiler {
+    fn after_step(&mut self, exec: &ExecContext) {
+        let now = Instant::now();
+        let elapsed = now - self.last_step;
+        self.last_step = now;
+
+        // The step was taken in whichever function was running when it started.
+        let current = self.stack.last().unwrap();
+        self.nodes[current.node].steps += 1;
+
+        let function = &mut self.functions[current.function];
+        function.self_time += elapsed;
+        function.steps += 1;
+
+        // A step only ever changes the top of the stack, by calling, returning, making a tail call
+        // or unwinding, so the frames below whatever's changed are left as they were.
+        let mut kept = (self.stack.len() - 1).min(exec.call_stack.len());
+
+        while kept > 0 && self.stack[kept].frame != exec.call_stack[kept - 1] {
+            kept -= 1;
+        }
+
+        while self.stack.len() > kept + 1 {
+            self.leave(now);
+        }
+
+        for frame in &exec.call_stack[kept..] {
+            self.enter(frame, now);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+use std::rc::Rc;
+
+use crate::{
+    analyzer::Session,
+    ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr},
+    builtins::{
+        get_builtin_func_bindings, get_builtin_funcs, get_builtin_type_bindings, BuiltinFunc,
+        BuiltinFuncId,
+    },
+    errors::runtime_error::RuntimeError,
+    interpreter::{self, resolved_value::ResolvedValue},
+    parser::parse_program,
+    state::{exec_context::ExecContext, interrupt::InterruptHandle, io_context::IoContext},
+};
+
+// Errors are rendered against the input they came from, under this name.
+const FILE_NAME: &str = "<repl>";
+
+/// Everything kept alive between inputs at the REPL: the analyzer's scopes, and the interpreter's
+/// context along with the bindings in it.
+pub struct Repl {
+    analyzer: Session,
+    exec: ExecContext,
+    // Set once an input calls `exit`, after which there's nothing more to read.
+    exit_code: Option<i32>,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        // The builtins are all distinct, so they can't fail to be declared.
+        let analyzer =
+            Session::new(get_builtin_func_bindings(), get_builtin_type_bindings()).unwrap();
+
+        let mut exec = ExecContext::new();
+        interpreter::load_builtins(&mut exec, get_builtin_func_bindings()).unwrap();
+
+        // Matches the scope the analyzer session keeps inputs in, so their slots line up.
+        exec.scope_stack.push_scope();
+
+        Self {
+            analyzer,
+            exec,
+            exit_code: None,
+        }
+    }
+
+    /// The code an input asked to exit with, if any has.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// The handle which stops whatever input is running. It stays the same across a `:reset`.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.exec.interrupt.clone()
+    }
+
+    /// Handles one complete input, either a command or statements to run, returning what to reply
+    /// with. Each statement is run in turn, and replied to with its value if it has one. Inputs
+    /// which fail leave no bindings behind, though any assignments they made to existing bindings
+    /// before failing are kept.
+    pub fn eval<R: Read, W: Write>(
+        &mut self,
+        io: &mut IoContext<R, W>,
+        builtins: &HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
+        input: &str,
+    ) -> String {
+        let input = input.trim();
+
+        if input.is_empty() {
+            return String::new();
+        }
+
+        if let Some(command) = input.strip_prefix(':') {
+            return self.command(command);
+        }
+
+        let source = terminate(input);
+
+        let parsed = match parse_program(&source) {
+            Ok(parsed) => parsed,
+            Err(errors) => {
+                return render_errors("Parse Error", errors, |e| {
+                    e.display(FILE_NAME.to_string(), source.clone())
+                })
+            }
+        };
+
+        let mark = self.analyzer.mark();
+
+        let analyzed = match self.analyzer.analyze(parsed) {
+            Ok(analyzed) => analyzed,
+            Err(errors) => {
+                return render_errors("Type Error", errors, |e| {
+                    e.display(FILE_NAME.to_string(), source.clone())
+                })
+            }
+        };
+
+        let TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty), span) = analyzed else {
+            unreachable!();
+        };
+
+        let scope_mark = self.exec.scope_stack.mark();
+
+        // An interrupt sent while waiting for input was meant for nothing in particular.
+        self.exec.interrupt.take();
+
+        // Statements are run one at a time, as one with a value would otherwise end the input, as
+        // it would a block, before the declarations after it were made.
+        let mut replies = Vec::new();
+
+        for stmt in stmts.iter() {
+            let ty = stmt.expr.ty();
+            let block = TypedBlock::Interpreted(Rc::new([stmt.clone()]), ty.clone());
+
+            match interpreter::interpret_in_scope(
+                TypedExpr::Block(block, span),
+                &mut self.exec,
+                io,
+                builtins,
+            ) {
+                Ok(ResolvedValue::Void) => {}
+                Ok(value) => replies.push(format!("{}: {ty}", value.render())),
+                Err(RuntimeError::Exit(code)) => {
+                    self.exit_code = Some(code);
+                    break;
+                }
+                Err(error) => {
+                    let backtrace = std::mem::take(&mut self.exec.call_stack);
+
+                    self.exec.control_stack.clear();
+                    self.exec.value_stack.clear();
+                    self.exec.scope_stack.unwind_to(scope_mark);
+                    self.analyzer.unwind_to(mark);
+
+                    replies.push(format!(
+                        "Runtime Error: {}",
+                        error.display(FILE_NAME.to_string(), source, backtrace)
+                    ));
+                    break;
+                }
+            }
+        }
+
+        replies.join("\n")
+    }
+
+    fn command(&mut self, command: &str) -> String {
+        let (name, arg) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+
+        match name {
+            "type" if !arg.trim().is_empty() => self.type_of(arg.trim()),
+            "reset" if arg.trim().is_empty() => {
+                let interrupt = self.exec.interrupt.clone();
+
+                *self = Self::new();
+                self.exec.interrupt = interrupt;
+
+                String::from("Session reset.")
+            }
+            _ => format!("Unknown command \":{command}\". Commands are :type <expr> and :reset."),
+        }
+    }
+
+    // Analyzes the input without running it or keeping anything it declares.
+    fn type_of(&mut self, input: &str) -> String {
+        let source = terminate(input);
+
+        let parsed = match parse_program(&source) {
+            Ok(parsed) => parsed,
+            Err(errors) => {
+                return render_errors("Parse Error", errors, |e| {
+                    e.display(FILE_NAME.to_string(), source.clone())
+                })
+            }
+        };
+
+        let mark = self.analyzer.mark();
+        let analyzed = self.analyzer.analyze(parsed);
+        self.analyzer.unwind_to(mark);
+
+        match analyzed {
+            Ok(analyzed) => analyzed.ty().to_string(),
+            Err(errors) => render_errors("Type Error", errors, |e| {
+                e.display(FILE_NAME.to_string(), source.clone())
+            }),
+        }
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads inputs until there are none left or one calls `exit`, replying to each. An input runs over
+/// as many lines as it takes to close every brace, bracket and parenthesis it opens.
+pub fn run<R: Read, W: Write>(
+    repl: &mut Repl,
+    mut io: IoContext<R, W>,
+) -> Result<(), RuntimeError> {
+    let builtins = get_builtin_funcs();
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() { "> " } else { "... " };
+        write_prompt(&mut io, prompt)?;
+
+        let read = io
+            .reader
+            .read_line(&mut input)
+            .map_err(|e| RuntimeError::Io(String::from("read_line"), e.to_string()))?;
+
+        if read == 0 {
+            return io.write_line("");
+        }
+
+        if is_open(&input) {
+            continue;
+        }
+
+        let reply = repl.eval(&mut io, &builtins, &input);
+        input.clear();
+
+        if !reply.is_empty() {
+            io.write_line(reply.trim_end())?;
+        }
+
+        // Exiting unwinds out of the REPL too, for whoever started it to exit with the code.
+        if let Some(code) = repl.exit_code() {
+            return Err(RuntimeError::Exit(code));
+        }
+    }
+}
+
+fn write_prompt<R: Read, W: Write>(
+    io: &mut IoContext<R, W>,
+    prompt: &str,
+) -> Result<(), RuntimeError> {
+    io.writer
+        .write_all(prompt.as_bytes())
+        .and_then(|_| io.writer.flush())
+        .map_err(|e| RuntimeError::Io(String::from("write"), e.to_string()))
+}
+
+// Whether the input opens more delimiters than it closes, skipping over strings and comments.
+fn is_open(input: &str) -> bool {
+    let mut depth = 0;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth -= 1,
+            '"' => {
+                chars.by_ref().find(|c| *c == '"');
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            _ => {}
+        }
+    }
+
+    depth > 0
+}
+
+// Statements must end with a semicolon, but there's no need to type the one at the end of an input.
+fn terminate(input: &str) -> String {
+    if input.ends_with(';') {
+        input.to_string()
+    } else {
+        format!("{input};")
+    }
+}
+
+fn render_errors<E, D: std::fmt::Display>(
+    kind: &str,
+    errors: Vec<E>,
+    display: impl Fn(E) -> D,
+) -> String {
+    errors
+        .into_iter()
+        .map(|error| format!("{kind}: {}", display(error)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
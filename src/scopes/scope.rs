@@ -15,6 +15,12 @@ impl<T> Scope<T> {
     }
 }
 
+impl<T> Default for Scope<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Deref for Scope<T> {
     type Target = HashMap<String, T>;
 
@@ -1,82 +1,311 @@
 use core::fmt;
 use core::fmt::Debug;
 use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Formatter;
 
-use super::scope::Scope;
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Span;
 use crate::errors::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ScopeEntry<T> {
     pub is_mutable: bool,
     pub value: T,
 }
 
-// This idea of current and previous isn't enough; if you have a function call within a funciton call,
-// the originating stack will be lost.
+/// Where a binding lives within its frame: the scope, counted from the frame's outermost, and the
+/// binding's position in order of declaration within that scope.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
+}
+
+// Bindings are stored in order of declaration so they can be read by slot, with names kept
+// alongside for lookups which can't be resolved ahead of time.
+#[derive(Serialize, Deserialize)]
+struct IndexedScope<T> {
+    names: HashMap<String, usize>,
+    entries: Vec<ScopeEntry<T>>,
+}
+
+impl<T> IndexedScope<T> {
+    fn new() -> Self {
+        Self {
+            names: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    fn get(&self, ident: &str) -> Option<usize> {
+        self.names.get(ident).copied()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Frame<T> {
+    scopes: Vec<IndexedScope<T>>,
+    // Bindings left behind by frames this one replaced in a tail call, visible only by name.
+    inherited: HashMap<String, ScopeEntry<T>>,
+    is_closure: bool,
+}
+
+impl<T> Frame<T> {
+    fn new(is_closure: bool) -> Self {
+        Self {
+            scopes: vec![IndexedScope::new()],
+            inherited: HashMap::new(),
+            is_closure,
+        }
+    }
+
+    fn find(&self, ident: &str) -> Option<Slot> {
+        self.scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(depth, scope)| scope.get(ident).map(|index| Slot { depth, index }))
+    }
+
+    fn get(&self, ident: &str) -> Option<&ScopeEntry<T>> {
+        match self.find(ident) {
+            Some(slot) => Some(&self.scopes[slot.depth].entries[slot.index]),
+            // Most frames never inherit anything, so skip hashing the name again for them.
+            None if self.inherited.is_empty() => None,
+            None => self.inherited.get(ident),
+        }
+    }
+
+    fn get_mut(&mut self, ident: &str) -> Option<&mut ScopeEntry<T>> {
+        match self.find(ident) {
+            Some(slot) => Some(&mut self.scopes[slot.depth].entries[slot.index]),
+            None => self.inherited.get_mut(ident),
+        }
+    }
+
+    // Every binding visible in the frame by name, each shadowing any older one of the same name.
+    fn into_bindings(self) -> HashMap<String, ScopeEntry<T>> {
+        let mut bindings = self.inherited;
+
+        for scope in self.scopes {
+            let mut entries: Vec<_> = scope.entries.into_iter().map(Some).collect();
+
+            for (name, index) in scope.names {
+                bindings.insert(name, entries[index].take().unwrap());
+            }
+        }
+
+        bindings
+    }
+}
+
+/// A point to unwind a `ScopeStack` back to: how many frames there were, how many scopes the
+/// innermost held, and how many bindings were in the innermost of those.
+#[derive(Clone, Copy, Debug)]
+pub struct ScopeMark {
+    frames: usize,
+    scopes: usize,
+    bindings: usize,
+}
+
+/// A stack of call frames, each holding its own stack of scopes. Name lookups see through closure
+/// frames into their caller, but stop at any other frame, so a non-closure function can't observe
+/// the bindings of whoever called it. Slots always refer to the innermost frame.
+#[derive(Serialize, Deserialize)]
 pub struct ScopeStack<T> {
-    current: Vec<Scope<ScopeEntry<T>>>,
-    previous: Option<Vec<Scope<ScopeEntry<T>>>>,
+    frames: Vec<Frame<T>>,
 }
 
 impl<T: Debug> ScopeStack<T> {
     pub fn new() -> Self {
         Self {
-            current: vec![Scope::new()],
-            previous: None,
+            frames: vec![Frame::new(false)],
         }
     }
 
     pub fn push_scope(&mut self) {
-        self.current.push(Scope::new());
+        self.current_mut().scopes.push(IndexedScope::new());
     }
 
     pub fn pop_scope(&mut self) {
-        self.current.pop();
+        self.current_mut().scopes.pop();
+    }
+
+    /// Enters a new frame; the caller's scopes are kept beneath it until `pop_frame`.
+    pub fn push_frame(&mut self, is_closure: bool) {
+        self.frames.push(Frame::new(is_closure));
+    }
+
+    /// Replaces the innermost frame with a new one, for a tail call. A closure would have seen the
+    /// old frame's bindings beneath its own, so they're kept visible to it by name; only one binding
+    /// per name is kept, so any number of tail calls run in constant space.
+    pub fn replace_frame(&mut self, is_closure: bool) {
+        // Tail calls are only made from within a function, so the outermost frame is never replaced.
+        let old = self.frames.pop().unwrap();
+
+        let frame = if is_closure {
+            let is_closure = old.is_closure;
+
+            Frame {
+                inherited: old.into_bindings(),
+                ..Frame::new(is_closure)
+            }
+        } else {
+            Frame::new(false)
+        };
+
+        self.frames.push(frame);
+    }
+
+    pub fn pop_frame(&mut self) {
+        // The outermost frame holds the program's own bindings and is never left.
+        if self.frames.len() > 1 {
+            self.frames.pop();
+        }
     }
 
-    pub fn create_new_stack(&mut self) {
-        self.previous = Some(std::mem::take(&mut self.current));
-        self.current = vec![Scope::new()]; // New isolated scope stack
+    pub fn frame_depth(&self) -> usize {
+        self.frames.len()
     }
 
-    pub fn restore_previous_stack(&mut self) {
-        if let Some(prev) = self.previous.take() {
-            self.current = prev;
+    // The number of scopes in the innermost frame.
+    pub fn scope_depth(&self) -> usize {
+        self.current().scopes.len()
+    }
+
+    pub fn mark(&self) -> ScopeMark {
+        let scopes = &self.current().scopes;
+
+        ScopeMark {
+            frames: self.frames.len(),
+            scopes: scopes.len(),
+            bindings: scopes.last().map_or(0, |scope| scope.entries.len()),
         }
     }
 
-    pub fn lookup<E: Error>(&self, ident: &str) -> Result<&ScopeEntry<T>, E> {
-        for scope in self.current.iter().rev() {
-            if let Some(entry) = scope.get(ident) {
-                return Ok(entry);
+    /// Returns to how things were at the mark, leaving any frames and scopes entered since and
+    /// forgetting any bindings declared since. Values assigned to older bindings are kept.
+    pub fn unwind_to(&mut self, mark: ScopeMark) {
+        self.frames.truncate(mark.frames);
+
+        let scopes = &mut self.current_mut().scopes;
+        scopes.truncate(mark.scopes);
+
+        if let Some(scope) = scopes.last_mut() {
+            scope.entries.truncate(mark.bindings);
+            scope.names.retain(|_, index| *index < mark.bindings);
+        }
+    }
+
+    fn current(&self) -> &Frame<T> {
+        self.frames.last().unwrap()
+    }
+
+    fn current_mut(&mut self) -> &mut Frame<T> {
+        self.frames.last_mut().unwrap()
+    }
+
+    // The frames a name lookup can see, innermost first.
+    fn visible_frames(&self) -> impl Iterator<Item = usize> + '_ {
+        let outermost = self
+            .frames
+            .iter()
+            .rposition(|frame| !frame.is_closure)
+            .unwrap_or(0);
+
+        (outermost..self.frames.len()).rev()
+    }
+
+    /// Finds the slot for a binding, if it's declared in the innermost frame.
+    pub fn resolve(&self, ident: &str) -> Option<Slot> {
+        self.current().find(ident)
+    }
+
+    pub fn lookup<E: Error>(&self, ident: &str, span: Span) -> Result<&ScopeEntry<T>, E> {
+        self.visible_frames()
+            .find_map(|frame| self.frames[frame].get(ident))
+            .ok_or_else(|| E::scope_binding_not_found(ident, span))
+    }
+
+    /// Every binding a name lookup could find, innermost first, leaving out any which are shadowed.
+    pub fn visible_bindings(&self) -> Vec<(&str, &ScopeEntry<T>)> {
+        let mut seen = HashSet::new();
+        let mut bindings = Vec::new();
+
+        for frame in self.visible_frames().map(|frame| &self.frames[frame]) {
+            for scope in frame.scopes.iter().rev() {
+                let mut names: Vec<_> = scope.names.iter().collect();
+                names.sort_by_key(|(_, index)| **index);
+
+                for (name, index) in names {
+                    if seen.insert(name.as_str()) {
+                        bindings.push((name.as_str(), &scope.entries[*index]));
+                    }
+                }
+            }
+
+            let mut inherited: Vec<_> = frame.inherited.iter().collect();
+            inherited.sort_by_key(|(name, _)| name.as_str());
+
+            for (name, entry) in inherited {
+                if seen.insert(name.as_str()) {
+                    bindings.push((name.as_str(), entry));
+                }
             }
         }
 
-        Err(E::scope_binding_not_found(ident))
+        bindings
     }
 
-    pub fn insert<E: Error>(&mut self, ident: String, is_mutable: bool, value: T) -> Result<(), E> {
-        let curr_scope = self.current.last_mut().unwrap();
+    pub fn lookup_slot(&self, slot: Slot) -> &ScopeEntry<T> {
+        &self.current().scopes[slot.depth].entries[slot.index]
+    }
 
-        match curr_scope.entry(ident.clone()) {
+    pub fn insert<E: Error>(
+        &mut self,
+        ident: String,
+        is_mutable: bool,
+        value: T,
+        span: Span,
+    ) -> Result<Slot, E> {
+        let depth = self.scope_depth() - 1;
+        let curr_scope = self.current_mut().scopes.last_mut().unwrap();
+        let index = curr_scope.entries.len();
+
+        match curr_scope.names.entry(ident) {
             Entry::Vacant(v) => {
-                v.insert(ScopeEntry { value, is_mutable });
-                Ok(())
+                v.insert(index);
+                curr_scope.entries.push(ScopeEntry { value, is_mutable });
+                Ok(Slot { depth, index })
             }
-            Entry::Occupied(_) => Err(E::scope_binding_already_exists(&ident)),
+            Entry::Occupied(o) => Err(E::scope_binding_already_exists(o.key(), span)),
         }
     }
 
-    pub fn mutate<E: Error>(&mut self, ident: &str, value: T) -> Result<(), E> {
-        for scope in self.current.iter_mut().rev() {
-            if let Some(entry) = scope.get_mut(ident) {
-                entry.value = value;
-                return Ok(());
+    pub fn mutate<E: Error>(&mut self, ident: &str, value: T, span: Span) -> Result<(), E> {
+        let frame = self
+            .visible_frames()
+            .find(|frame| self.frames[*frame].get(ident).is_some());
+
+        match frame {
+            Some(frame) => {
+                self.frames[frame].get_mut(ident).unwrap().value = value;
+                Ok(())
             }
+            None => Err(E::scope_binding_not_found(ident, span)),
         }
+    }
 
-        Err(E::scope_binding_not_found(ident))
+    pub fn mutate_slot(&mut self, slot: Slot, value: T) {
+        self.current_mut().scopes[slot.depth].entries[slot.index].value = value;
+    }
+}
+
+impl<T: Debug> Default for ScopeStack<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -84,23 +313,24 @@ impl<T: Debug> Debug for ScopeStack<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(f, "ScopeStack {{")?;
 
-        writeln!(f, "  current: [")?;
-        for (i, scope) in self.current.iter().enumerate() {
-            writeln!(f, "    Scope {}: {:?}", i, scope)?;
-        }
-        writeln!(f, "  ]")?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            let kind = if frame.is_closure { " (closure)" } else { "" };
+
+            writeln!(f, "  Frame {}{}: [", i, kind)?;
+            for (j, scope) in frame.scopes.iter().enumerate() {
+                let mut names: Vec<_> = scope.names.iter().collect();
+                names.sort_by_key(|(_, index)| **index);
 
-        match &self.previous {
-            Some(prev_scopes) => {
-                writeln!(f, "  previous: [")?;
-                for (i, scope) in prev_scopes.iter().enumerate() {
-                    writeln!(f, "    Scope {}: {:?}", i, scope)?;
+                writeln!(f, "    Scope {}: {{", j)?;
+                for (name, index) in names {
+                    writeln!(f, "      \"{}\": {:?}", name, scope.entries[*index])?;
                 }
-                writeln!(f, "  ]")?;
+                writeln!(f, "    }}")?;
             }
-            None => {
-                writeln!(f, "  previous: None")?;
+            for (name, entry) in &frame.inherited {
+                writeln!(f, "    (inherited) \"{}\": {:?}", name, entry)?;
             }
+            writeln!(f, "  ]")?;
         }
 
         writeln!(f, "}}")
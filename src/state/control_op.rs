@@ -1,46 +1,190 @@
-use crate::ast::typed::{typed_expr::TypedExpr, TypedStmt};
+use crate::{
+    ast::{
+        typed::{typed_expr::TypedExpr, TypedFunc, TypedLiteral, TypedStmt},
+        Span,
+    },
+    scopes::scope_stack::Slot,
+};
 
-#[derive(Clone, Debug)]
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use super::exec_context::CallFrame;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ControlOp {
-    EvalBlock(TypedExpr),
+    EvalBlock(Rc<TypedExpr>),
     EvalStmt(TypedStmt),
     ApplyStmt,
     EvalExpr(TypedExpr),
 
     // Binary operations
-    ApplyAdd,
-    ApplySub,
-    ApplyMult,
-    ApplyDiv,
+    ApplyAdd(Span),
+    ApplySub(Span),
+    ApplyMult(Span),
+    ApplyDiv(Span),
     ApplyEq,
     ApplyGt,
     ApplyLt,
     ApplyGte,
     ApplyLte,
-    ApplyModulo,
+    ApplyModulo(Span),
 
     // Postfix operations
-    ApplyFuncCall(Vec<TypedExpr>),
+    ApplyFuncCall(Rc<[TypedExpr]>, String, bool, Span),
 
     // Control flow
-    ApplyIf(TypedExpr),
-    ApplyIfElse(TypedExpr, TypedExpr),
-    PushLoop(TypedExpr),
+    ApplyIf(Rc<TypedExpr>),
+    ApplyIfElse(Rc<TypedExpr>, Rc<TypedExpr>),
+    PushLoop(Rc<TypedExpr>),
 
     // Unary operations
-    ApplyAssignment(String),
-    ApplyNegate,
-    ApplyDeclaration(String, bool),
+    ApplyAssignment(String, Option<Slot>, Span),
+    ApplyNegate(Span),
+    ApplyDeclaration(String, bool, Span),
 
-    // Scope
-    ApplyBinding(String),
-    PushScope { create_new_stack: bool },
-    PopScope { restore_previous_stack: bool },
+    // Scope. Blocks enter their scope as they're evaluated, so only leaving it needs an op.
+    ApplyBindings(Vec<String>, Span),
+    PopScope,
+    PushFrame { is_closure: bool },
+    PopFrame,
+    ReplaceFrame(Rc<TypedFunc>, CallFrame),
+
+    // Call stack
+    PushCallFrame(CallFrame),
+    PopCallFrame,
 
     // Markers
     MarkLoopStart,
     MarkBlockStart,
 
     // Post-evaluation construction
-    ApplyList(usize),
+    ApplyList(usize, Span),
+}
+
+impl ControlOp {
+    /// Where in the source the op comes from, for those which come from anywhere in particular.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ControlOp::EvalBlock(expr)
+            | ControlOp::ApplyIf(expr)
+            | ControlOp::ApplyIfElse(expr, _)
+            | ControlOp::PushLoop(expr) => Some(expr.span()),
+            ControlOp::EvalExpr(expr) => Some(expr.span()),
+            ControlOp::EvalStmt(stmt) => Some(stmt.expr.span()),
+            ControlOp::ApplyAdd(span)
+            | ControlOp::ApplySub(span)
+            | ControlOp::ApplyMult(span)
+            | ControlOp::ApplyDiv(span)
+            | ControlOp::ApplyModulo(span)
+            | ControlOp::ApplyFuncCall(.., span)
+            | ControlOp::ApplyAssignment(.., span)
+            | ControlOp::ApplyNegate(span)
+            | ControlOp::ApplyDeclaration(.., span)
+            | ControlOp::ApplyBindings(_, span)
+            | ControlOp::ApplyList(_, span) => Some(*span),
+            ControlOp::ReplaceFrame(_, frame) | ControlOp::PushCallFrame(frame) => {
+                Some(frame.call_site)
+            }
+            ControlOp::ApplyStmt
+            | ControlOp::ApplyEq
+            | ControlOp::ApplyGt
+            | ControlOp::ApplyLt
+            | ControlOp::ApplyGte
+            | ControlOp::ApplyLte
+            | ControlOp::PopScope
+            | ControlOp::PushFrame { .. }
+            | ControlOp::PopFrame
+            | ControlOp::PopCallFrame
+            | ControlOp::MarkLoopStart
+            | ControlOp::MarkBlockStart => None,
+        }
+    }
+
+    /// Whether the op enters or leaves a function.
+    pub fn is_call(&self) -> bool {
+        matches!(
+            self,
+            ControlOp::PushCallFrame(_) | ControlOp::ReplaceFrame(..) | ControlOp::PopCallFrame
+        )
+    }
+}
+
+// A short description of the op, without the expressions it carries, which can be whole programs.
+impl Display for ControlOp {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ControlOp::EvalBlock(_) => write!(f, "EvalBlock"),
+            ControlOp::EvalStmt(_) => write!(f, "EvalStmt"),
+            ControlOp::ApplyStmt => write!(f, "ApplyStmt"),
+            ControlOp::EvalExpr(expr) => write!(f, "EvalExpr {}", describe_expr(expr)),
+            ControlOp::ApplyAdd(_) => write!(f, "ApplyAdd"),
+            ControlOp::ApplySub(_) => write!(f, "ApplySub"),
+            ControlOp::ApplyMult(_) => write!(f, "ApplyMult"),
+            ControlOp::ApplyDiv(_) => write!(f, "ApplyDiv"),
+            ControlOp::ApplyEq => write!(f, "ApplyEq"),
+            ControlOp::ApplyGt => write!(f, "ApplyGt"),
+            ControlOp::ApplyLt => write!(f, "ApplyLt"),
+            ControlOp::ApplyGte => write!(f, "ApplyGte"),
+            ControlOp::ApplyLte => write!(f, "ApplyLte"),
+            ControlOp::ApplyModulo(_) => write!(f, "ApplyModulo"),
+            ControlOp::ApplyFuncCall(_, name, true, _) => write!(f, "ApplyFuncCall {name} (tail)"),
+            ControlOp::ApplyFuncCall(_, name, false, _) => write!(f, "ApplyFuncCall {name}"),
+            ControlOp::ApplyIf(_) => write!(f, "ApplyIf"),
+            ControlOp::ApplyIfElse(..) => write!(f, "ApplyIfElse"),
+            ControlOp::PushLoop(_) => write!(f, "PushLoop"),
+            ControlOp::ApplyAssignment(ident, ..) => write!(f, "ApplyAssignment {ident}"),
+            ControlOp::ApplyNegate(_) => write!(f, "ApplyNegate"),
+            ControlOp::ApplyDeclaration(ident, ..) => write!(f, "ApplyDeclaration {ident}"),
+            ControlOp::ApplyBindings(idents, _) => {
+                write!(f, "ApplyBindings {}", idents.join(", "))
+            }
+            ControlOp::PopScope => write!(f, "PopScope"),
+            ControlOp::PushFrame { is_closure: true } => write!(f, "PushFrame (closure)"),
+            ControlOp::PushFrame { is_closure: false } => write!(f, "PushFrame"),
+            ControlOp::PopFrame => write!(f, "PopFrame"),
+            ControlOp::ReplaceFrame(_, frame) => write!(f, "ReplaceFrame {}", frame.name),
+            ControlOp::PushCallFrame(frame) => write!(f, "PushCallFrame {}", frame.name),
+            ControlOp::PopCallFrame => write!(f, "PopCallFrame"),
+            ControlOp::MarkLoopStart => write!(f, "MarkLoopStart"),
+            ControlOp::MarkBlockStart => write!(f, "MarkBlockStart"),
+            ControlOp::ApplyList(size, _) => write!(f, "ApplyList {size}"),
+        }
+    }
+}
+
+fn describe_expr(expr: &TypedExpr) -> String {
+    match expr {
+        TypedExpr::Eq(..) => String::from("Eq"),
+        TypedExpr::Gt(..) => String::from("Gt"),
+        TypedExpr::Lt(..) => String::from("Lt"),
+        TypedExpr::Gte(..) => String::from("Gte"),
+        TypedExpr::Lte(..) => String::from("Lte"),
+        TypedExpr::Add(..) => String::from("Add"),
+        TypedExpr::Sub(..) => String::from("Sub"),
+        TypedExpr::Mult(..) => String::from("Mult"),
+        TypedExpr::Div(..) => String::from("Div"),
+        TypedExpr::Modulo(..) => String::from("Modulo"),
+        TypedExpr::Negate(..) => String::from("Negate"),
+        TypedExpr::Assignment { ident, .. } => format!("Assignment {ident}"),
+        TypedExpr::Declaration { ident, .. } => format!("Declaration {ident}"),
+        TypedExpr::FuncCall(..) => String::from("FuncCall"),
+        TypedExpr::If(..) => String::from("If"),
+        TypedExpr::IfElse(..) => String::from("IfElse"),
+        TypedExpr::Block(..) => String::from("Block"),
+        TypedExpr::Loop(..) => String::from("Loop"),
+        TypedExpr::Break(_) => String::from("Break"),
+        TypedExpr::Literal(literal, ..) => match literal {
+            TypedLiteral::Int(int) => format!("Literal {int}"),
+            TypedLiteral::Float(float) => format!("Literal {float}"),
+            TypedLiteral::String(string) => format!("Literal \"{string}\""),
+            TypedLiteral::Bool(bool) => format!("Literal {bool}"),
+        },
+        TypedExpr::Identifier(ident, ..) => format!("Identifier {ident}"),
+        TypedExpr::FuncDeclare(..) => String::from("FuncDeclare"),
+        TypedExpr::List(items, ..) => format!("List {}", items.len()),
+        TypedExpr::Unknown(_) => String::from("Unknown"),
+    }
 }
@@ -1,9 +1,170 @@
-use crate::{interpreter::resolved_value::ResolvedValue, scopes::scope_stack::ScopeStack};
+use serde::{Deserialize, Serialize};
 
-use super::control_op::ControlOp;
+use crate::{
+    ast::Span, errors::runtime_error::RuntimeError, interpreter::resolved_value::ResolvedValue,
+    scopes::scope_stack::ScopeStack,
+};
 
+use super::{control_op::ControlOp, interrupt::InterruptHandle};
+
+/// How integer arithmetic behaves when the result doesn't fit in an `Int`. Division by zero is an
+/// error in either mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArithmeticMode {
+    #[default]
+    Checked,
+    Wrapping,
+}
+
+/// Limits on what a program may use before it's stopped with an error, for running programs which
+/// can't be trusted to finish on their own. Everything is unlimited by default.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Limits {
+    /// The most steps, either control ops or VM instructions, a program may take.
+    pub max_steps: Option<u64>,
+    /// The most entries either the control stack or the value stack may hold.
+    pub max_stack_depth: Option<usize>,
+    /// The most calls which may be in progress at once.
+    pub max_call_depth: Option<usize>,
+    /// Roughly the most bytes a single value may take up. A list counts only its own storage, as
+    /// each of its items was held to the same limit when it was made.
+    pub max_value_size: Option<usize>,
+}
+
+/// An entry in the shadow call stack, used to render backtraces for runtime errors.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallFrame {
+    pub name: String,
+    pub call_site: Span,
+    /// Whether the function was entered through a tail call, and so shares its caller's frame.
+    pub is_tail: bool,
+    /// How many tail calls were made before this one and then replaced by it.
+    pub elided: usize,
+    /// How deep the interpreter's value stack was when the call was made. Everything above belongs
+    /// to the call, so a tail call can clear it away.
+    pub value_base: usize,
+    /// The binding the called function was declared with, if it wasn't declared anonymously.
+    pub declared_name: Option<String>,
+    /// Where the called function was declared.
+    pub declared_at: Span,
+}
+
+impl CallFrame {
+    pub fn new(name: String, call_site: Span) -> Self {
+        Self {
+            name,
+            call_site,
+            is_tail: false,
+            elided: 0,
+            value_base: 0,
+            declared_name: None,
+            declared_at: Span::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct ExecContext {
     pub control_stack: Vec<ControlOp>,
     pub value_stack: Vec<ResolvedValue>,
     pub scope_stack: ScopeStack<ResolvedValue>,
+    pub call_stack: Vec<CallFrame>,
+    pub arithmetic: ArithmeticMode,
+    pub limits: Limits,
+    pub steps: u64,
+    // A restored context starts out with a handle of its own, not connected to any host.
+    #[serde(skip)]
+    pub interrupt: InterruptHandle,
+}
+
+impl ExecContext {
+    pub fn new() -> Self {
+        Self {
+            control_stack: Vec::new(),
+            value_stack: Vec::new(),
+            scope_stack: ScopeStack::new(),
+            call_stack: Vec::new(),
+            arithmetic: ArithmeticMode::default(),
+            limits: Limits::default(),
+            steps: 0,
+            interrupt: InterruptHandle::new(),
+        }
+    }
+}
+
+impl ExecContext {
+    /// Counts a single step of execution, checking it against the step and stack limits and for
+    /// an interrupt.
+    pub fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+
+        if self.interrupt.take() {
+            return Err(RuntimeError::Interrupted);
+        }
+
+        if let Some(max) = self.limits.max_steps {
+            if self.steps > max {
+                return Err(RuntimeError::StepLimitExceeded(max));
+            }
+        }
+
+        if let Some(max) = self.limits.max_stack_depth {
+            if self.control_stack.len() > max || self.value_stack.len() > max {
+                return Err(RuntimeError::StackLimitExceeded(max));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn check_value_size(&self, value: &ResolvedValue, span: Span) -> Result<(), RuntimeError> {
+        match self.limits.max_value_size {
+            Some(max) if value.size() > max => Err(RuntimeError::ValueSizeExceeded(max, span)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn push_call_frame(&mut self, frame: CallFrame) -> Result<(), RuntimeError> {
+        if let Some(max) = self.limits.max_call_depth {
+            if self.call_stack.len() >= max {
+                return Err(RuntimeError::CallDepthExceeded(max, frame.call_site));
+            }
+        }
+
+        self.call_stack.push(frame);
+        Ok(())
+    }
+
+    /// Records a tail call. The function making it keeps its entry so backtraces still show where
+    /// it was called from, but a chain of tail calls only ever keeps its latest, so the call stack
+    /// doesn't grow with it.
+    pub fn push_tail_call_frame(&mut self, frame: CallFrame) {
+        let frame = CallFrame {
+            is_tail: true,
+            ..frame
+        };
+
+        match self.call_stack.last_mut() {
+            Some(last) if last.is_tail => {
+                *last = CallFrame {
+                    elided: last.elided + 1,
+                    ..frame
+                }
+            }
+            _ => self.call_stack.push(frame),
+        }
+    }
+
+    /// Leaves the current function, along with any tail call it handed its frame to.
+    pub fn pop_call_frame(&mut self) {
+        if let Some(CallFrame { is_tail: true, .. }) = self.call_stack.pop() {
+            self.call_stack.pop();
+        }
+    }
+}
+
+impl Default for ExecContext {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,29 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A flag which stops a running program at its next step, raised by the host from any thread.
+/// Clones share the same flag, so one can be handed off before the program starts.
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    // Lowers the flag if it was raised, so the interrupt is acted on only once and the context can
+    // be used to run again afterwards.
+    pub(crate) fn take(&self) -> bool {
+        self.is_interrupted() && self.0.swap(false, Ordering::Relaxed)
+    }
+}
@@ -5,6 +5,8 @@ use crate::errors::runtime_error::RuntimeError;
 pub struct IoContext<R: Read, W: Write> {
     pub reader: BufReader<R>,
     pub writer: BufWriter<W>,
+    /// The arguments the program was given, after its source.
+    pub args: Vec<String>,
 }
 
 impl<R: Read, W: Write> IoContext<R, W> {
@@ -40,7 +42,5 @@ impl<R: Read, W: Write> IoContext<R, W> {
 }
 
 fn io_error(op: &str, e: std::io::Error) -> RuntimeError {
-    RuntimeError {
-        message: format!("Failed to {} in IoContext: {}", op, e),
-    }
+    RuntimeError::Io(op.to_string(), e.to_string())
 }
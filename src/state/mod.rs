@@ -1,4 +1,6 @@
 pub mod control_flow;
 pub mod control_op;
 pub mod exec_context;
+pub mod interrupt;
 pub mod io_context;
+pub mod snapshot;
@@ -0,0 +1,28 @@
+use crate::errors::snapshot_error::SnapshotError;
+
+use super::exec_context::ExecContext;
+
+// Anything in a snapshot might change shape between versions, so one is only ever restored by the
+// version which took it.
+const HEADER: &[u8] = concat!("moss snapshot ", env!("CARGO_PKG_VERSION"), "\n").as_bytes();
+
+impl ExecContext {
+    /// Saves everything needed to carry on running a program later, possibly from another process.
+    /// Only the interpreter can be resumed, by passing the restored context back to `run_for`.
+    pub fn save(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut bytes = HEADER.to_vec();
+
+        bincode::serialize_into(&mut bytes, self)
+            .map_err(|error| SnapshotError::Encoding(error.to_string()))?;
+
+        Ok(bytes)
+    }
+
+    pub fn restore(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let body = bytes
+            .strip_prefix(HEADER)
+            .ok_or(SnapshotError::Incompatible)?;
+
+        bincode::deserialize(body).map_err(|error| SnapshotError::Decoding(error.to_string()))
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::{
+    analyzer::analyze_program,
+    ast::{
+        typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedStmt},
+        untyped::{Expr, Stmt},
+        Span,
+    },
+    builtins::{get_builtin_func_bindings, get_builtin_funcs, get_builtin_type_bindings},
+    errors::{parse_error::ParseError, runtime_error::RuntimeError, type_error::TypeError},
+    interpreter::{interpret_in_scope, load_builtins},
+    parser::parse_program,
+    state::{
+        exec_context::{CallFrame, ExecContext},
+        io_context::IoContext,
+    },
+};
+
+/// How a single test went.
+#[derive(Debug)]
+pub struct TestOutcome {
+    pub name: String,
+    pub span: Span,
+    pub failure: Option<TestFailure>,
+    /// Whatever the test printed.
+    pub output: String,
+}
+
+/// The error a test failed with, and the calls it was made in.
+#[derive(Debug)]
+pub struct TestFailure {
+    pub error: RuntimeError,
+    pub backtrace: Vec<CallFrame>,
+}
+
+/// Why none of a file's tests could be run.
+#[derive(Debug)]
+pub enum LoadFailure {
+    Parse(Vec<ParseError>),
+    Type(Vec<TypeError>),
+}
+
+/// Finds every `.moss` file at the path, searching directories recursively, in a stable order.
+pub fn discover(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut entries: Vec<_> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    let mut files = Vec::new();
+
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(discover(&entry)?);
+        } else if entry.extension().is_some_and(|ext| ext == "moss") {
+            files.push(entry);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Runs each test in a program, in the order they're declared. Every test is run on its own in a
+/// fresh context, after whatever else the program does before declaring it, though none of the
+/// other tests. Input is always empty. A program which doesn't parse or analyze has its errors
+/// returned instead.
+pub fn run_tests(source: &str) -> Result<Vec<TestOutcome>, LoadFailure> {
+    let parsed = parse_program(source).map_err(LoadFailure::Parse)?;
+
+    // Tests are checked along with the rest of the program, so each can be run without checking.
+    analyze_program(
+        parsed.clone(),
+        get_builtin_func_bindings(),
+        get_builtin_type_bindings(),
+    )
+    .map_err(LoadFailure::Type)?;
+
+    let Expr::Block(stmts, span) = parsed else {
+        unreachable!();
+    };
+
+    let outcomes = stmts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, stmt)| match &stmt.expr {
+            Expr::Test(name, body, test_span) => {
+                let mut program: Vec<_> = stmts[..i]
+                    .iter()
+                    .filter(|stmt| !matches!(stmt.expr, Expr::Test(..)))
+                    .cloned()
+                    .collect();
+
+                // The body is run as a block of its own, so it can shadow anything before it.
+                program.push(Stmt {
+                    expr: *body.clone(),
+                });
+
+                let (failure, output) = run_test(Expr::Block(program, span));
+
+                Some(TestOutcome {
+                    name: name.clone(),
+                    span: *test_span,
+                    failure,
+                    output,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+
+    Ok(outcomes)
+}
+
+fn run_test(program: Expr) -> (Option<TestFailure>, String) {
+    // The whole program was analyzed without errors, and this is only part of it.
+    let analyzed = analyze_program(
+        program,
+        get_builtin_func_bindings(),
+        get_builtin_type_bindings(),
+    )
+    .unwrap_or_else(|_| unreachable!());
+
+    let TypedExpr::Block(TypedBlock::Interpreted(stmts, _ty), span) = analyzed else {
+        unreachable!();
+    };
+
+    let mut exec = ExecContext::new();
+    let mut output = Vec::new();
+
+    // Builtins never fail to load.
+    load_builtins(&mut exec, get_builtin_func_bindings()).unwrap();
+
+    // Matches the scope the program's statements were analyzed in, so their slots line up.
+    exec.scope_stack.push_scope();
+
+    let mut io = IoContext {
+        reader: BufReader::new(io::empty()),
+        writer: BufWriter::new(&mut output),
+        args: Vec::new(),
+    };
+    let builtins = get_builtin_funcs();
+
+    // The test's body comes last, after whatever the program does before declaring it.
+    let (body, prelude) = stmts.split_last().unwrap();
+
+    let TypedExpr::Block(TypedBlock::Interpreted(body, _ty), _span) = &body.expr else {
+        unreachable!();
+    };
+
+    // Statements are run one at a time, both before the test and in its body, so one with a value
+    // doesn't end the program early, as it would a block.
+    let mut run_stmt = |exec: &mut ExecContext, stmt: &TypedStmt| {
+        let block = TypedBlock::Interpreted(Rc::new([stmt.clone()]), stmt.expr.ty());
+        interpret_in_scope(TypedExpr::Block(block, span), exec, &mut io, &builtins).map(|_| ())
+    };
+
+    let result = prelude
+        .iter()
+        .try_for_each(|stmt| run_stmt(&mut exec, stmt))
+        .and_then(|_| {
+            // Matches the scope the body was analyzed in, as it would be entered as a block.
+            exec.scope_stack.push_scope();
+            body.iter().try_for_each(|stmt| run_stmt(&mut exec, stmt))
+        });
+
+    drop(io);
+
+    let failure = result.err().map(|error| TestFailure {
+        error,
+        backtrace: exec.call_stack,
+    });
+
+    (failure, String::from_utf8_lossy(&output).into_owned())
+}
@@ -4,13 +4,22 @@ use crate::{
     analyzer,
     ast::{typed::typed_expr::TypedExpr, untyped::Expr},
     builtins::{get_builtin_func_bindings, get_builtin_funcs, get_builtin_type_bindings},
-    errors::{runtime_error::RuntimeError, type_error::TypeError},
+    compiler,
+    errors::{runtime_error::RuntimeError, type_error::TypeError, warning::Warning},
     interpreter::{self, resolved_value::ResolvedValue},
-    scopes::scope_stack::ScopeStack,
-    state::{exec_context::ExecContext, io_context::IoContext},
+    linter::{
+        self,
+        config::{LintConfig, LintLevel},
+    },
+    optimizer,
+    state::{
+        exec_context::{ArithmeticMode, ExecContext},
+        io_context::IoContext,
+    },
+    vm,
 };
 
-pub fn analyze_program(program: Expr) -> Result<TypedExpr, TypeError> {
+pub fn analyze_program(program: Expr) -> Result<TypedExpr, Vec<TypeError>> {
     analyzer::analyze_program(
         program,
         get_builtin_func_bindings(),
@@ -18,17 +27,53 @@ pub fn analyze_program(program: Expr) -> Result<TypedExpr, TypeError> {
     )
 }
 
+pub fn optimize_program(program: TypedExpr) -> Result<TypedExpr, Vec<TypeError>> {
+    optimizer::optimize_program(program, ArithmeticMode::Checked)
+}
+
+pub fn lint_program(program: &TypedExpr, source: &str) -> Vec<(Warning, LintLevel)> {
+    let mut config = LintConfig::new();
+    config.apply_directives(source);
+
+    linter::lint_program(program, &get_builtin_func_bindings(), &config)
+}
+
 pub fn exec_program(program: TypedExpr) -> Result<ResolvedValue, RuntimeError> {
+    exec_program_with_context(program, &mut ExecContext::new())
+}
+
+pub fn exec_program_with_context(
+    program: TypedExpr,
+    exec: &mut ExecContext,
+) -> Result<ResolvedValue, RuntimeError> {
     interpreter::interpret_program(
         program,
-        ExecContext {
-            control_stack: Vec::new(),
-            value_stack: Vec::new(),
-            scope_stack: ScopeStack::new(),
+        exec,
+        IoContext {
+            reader: BufReader::new(io::stdin().lock()),
+            writer: BufWriter::new(io::stdout().lock()),
+            args: Vec::new(),
         },
+        get_builtin_func_bindings(),
+        get_builtin_funcs(),
+    )
+}
+
+pub fn exec_program_vm(program: TypedExpr) -> Result<ResolvedValue, RuntimeError> {
+    exec_program_vm_with_context(program, &mut ExecContext::new())
+}
+
+pub fn exec_program_vm_with_context(
+    program: TypedExpr,
+    exec: &mut ExecContext,
+) -> Result<ResolvedValue, RuntimeError> {
+    vm::run_program(
+        &compiler::compile_program(program),
+        exec,
         IoContext {
             reader: BufReader::new(io::stdin().lock()),
             writer: BufWriter::new(io::stdout().lock()),
+            args: Vec::new(),
         },
         get_builtin_func_bindings(),
         get_builtin_funcs(),
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::ops::RangeInclusive;
+
+use crate::{
+    errors::{
+        location_frame::{get_column_number, get_line_number},
+        runtime_error::RuntimeError,
+    },
+    interpreter::Inspector,
+    state::{control_op::ControlOp, exec_context::ExecContext},
+};
+
+/// Which ops to trace. Every op is traced by default.
+#[derive(Clone, Debug, Default)]
+pub struct TraceFilter {
+    /// Only ops which enter or leave a function.
+    pub calls_only: bool,
+    /// Only ops which enter the named function, or run while it's the innermost one in progress.
+    pub function: Option<String>,
+    /// Only ops from source on these lines.
+    pub lines: Option<RangeInclusive<usize>>,
+}
+
+/// Logs each op the interpreter takes off its control stack, along with how deep the value stack
+/// and scopes are at the time. Entries are either written out as they're made, or kept in a buffer
+/// of the latest few to be dumped when something goes wrong.
+pub struct Tracer<T: Write> {
+    source: String,
+    filter: TraceFilter,
+    out: T,
+    // How many entries to keep, when they're buffered rather than written out.
+    capacity: Option<usize>,
+    buffer: VecDeque<String>,
+}
+
+impl<T: Write> Tracer<T> {
+    pub fn new(source: String, filter: TraceFilter, out: T, capacity: Option<usize>) -> Self {
+        Self {
+            source,
+            filter,
+            out,
+            capacity,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    /// Writes out the buffered entries, oldest first, and empties the buffer.
+    pub fn dump(&mut self) -> io::Result<()> {
+        for entry in self.buffer.drain(..) {
+            writeln!(self.out, "{entry}")?;
+        }
+
+        self.out.flush()
+    }
+
+    fn matches(&self, exec: &ExecContext, op: &ControlOp) -> bool {
+        if self.filter.calls_only && !op.is_call() {
+            return false;
+        }
+
+        if let Some(function) = &self.filter.function {
+            let is_entering = match op {
+                ControlOp::PushCallFrame(frame) | ControlOp::ReplaceFrame(_, frame) => {
+                    frame.name == *function
+                }
+                _ => false,
+            };
+
+            let is_innermost = exec
+                .call_stack
+                .last()
+                .is_some_and(|frame| frame.name == *function);
+
+            if !is_entering && !is_innermost {
+                return false;
+            }
+        }
+
+        if let Some(lines) = &self.filter.lines {
+            let line = op
+                .span()
+                .map(|span| get_line_number(&self.source, span.start));
+
+            if !line.is_some_and(|line| lines.contains(&line)) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn entry(&self, exec: &ExecContext, op: &ControlOp) -> String {
+        let location = match op.span() {
+            Some(span) => format!(
+                "{}:{}",
+                get_line_number(&self.source, span.start),
+                get_column_number(&self.source, span.start)
+            ),
+            None => String::from("-"),
+        };
+
+        format!(
+            "{:>6}  {location:>7}  values {:<3}  frames {:<3}  scopes {:<3}  {op}",
+            exec.steps,
+            exec.value_stack.len(),
+            exec.scope_stack.frame_depth(),
+            exec.scope_stack.scope_depth(),
+        )
+    }
+}
+
+impl<R: Read, W: Write, T: Write> Inspector<R, W> for Tracer<T> {
+    fn before_op(&mut self, exec: &ExecContext, op: &ControlOp) -> Result<(), RuntimeError> {
+        if !self.matches(exec, op) {
+            return Ok(());
+        }
+
+        let entry = self.entry(exec, op);
+
+        match self.capacity {
+            Some(capacity) => {
+                if self.buffer.len() == capacity {
+                    self.buffer.pop_front();
+                }
+
+                self.buffer.push_back(entry);
+                Ok(())
+            }
+            None => writeln!(self.out, "{entry}")
+                .map_err(|e| RuntimeError::Io(String::from("write trace"), e.to_string())),
+        }
+    }
+}
@@ -1,5 +1,9 @@
 use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Span;
+
 pub enum TypeBinding {
     Atomic(Type),
     Applied { arity: usize },
@@ -7,11 +11,19 @@ pub enum TypeBinding {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ProtoType {
-    Atomic(String),
-    Applied(String, Vec<ProtoType>),
+    Atomic(String, Span),
+    Applied(String, Vec<ProtoType>, Span),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+impl ProtoType {
+    pub fn span(&self) -> Span {
+        match self {
+            ProtoType::Atomic(_, span) | ProtoType::Applied(_, _, span) => *span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Type {
     Any, // TODO: Temporary for string coercion in print. Do not use, and remove when generics are implemented.
     Bool,
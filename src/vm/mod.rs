@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+use crate::ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr};
+use crate::builtins::{BuiltinFunc, BuiltinFuncId};
+use crate::compiler::chunk::{Instruction, Program};
+use crate::errors::runtime_error::RuntimeError;
+use crate::interpreter::evaluation::{
+    apply_add, apply_assignment, apply_declaration, apply_div, apply_eq, apply_gt, apply_gte,
+    apply_list, apply_lt, apply_lte, apply_modulo, apply_mult, apply_negate, apply_sub,
+};
+use crate::interpreter::resolved_value::ResolvedValue;
+use crate::state::{
+    exec_context::{CallFrame, ExecContext},
+    io_context::IoContext,
+};
+
+// A function being executed, and where in its chunk execution is up to.
+struct Frame {
+    chunk: usize,
+    ip: usize,
+}
+
+// Where a `break` resumes, and how deep each stack was when the loop was entered. Breaking can
+// leave any number of calls and scopes, all of which are unwound back to these depths.
+struct LoopHandler {
+    exit: usize,
+    frames: usize,
+    values: usize,
+    scope_frames: usize,
+    scopes: usize,
+    calls: usize,
+}
+
+/// Executes a compiled program. Shares `ExecContext` with the interpreter, so values, scopes, the
+/// arithmetic mode and the call stack behave the same under either backend.
+pub fn run_program<R: Read, W: Write>(
+    program: &Program,
+    exec: &mut ExecContext,
+    mut io: IoContext<R, W>,
+    builtin_bindings: Vec<(String, TypedExpr)>,
+    builtins: HashMap<BuiltinFuncId, BuiltinFunc<R, W>>,
+) -> Result<ResolvedValue, RuntimeError> {
+    for (ident, expr) in builtin_bindings {
+        if let TypedExpr::FuncDeclare(func, _, span) = expr {
+            let resolved = ResolvedValue::Func(Rc::new(func));
+            exec.scope_stack.insert(ident, false, resolved, span)?;
+        } else {
+            unreachable!();
+        }
+    }
+
+    let mut frames = vec![Frame { chunk: 0, ip: 0 }];
+
+    let mut loops: Vec<LoopHandler> = Vec::new();
+
+    loop {
+        let depth = frames.len();
+        let frame = frames.last_mut().unwrap();
+        let chunk = &program.chunks[frame.chunk];
+        let at = frame.ip;
+        let instruction = chunk.code[at];
+
+        frame.ip += 1;
+
+        exec.step()?;
+
+        match instruction {
+            // Values
+            Instruction::Constant(index) => exec.value_stack.push(chunk.constants[index].clone()),
+            Instruction::Void => exec.value_stack.push(ResolvedValue::Void),
+            Instruction::Pop => {
+                exec.value_stack.pop();
+            }
+            Instruction::List(size, span) => {
+                apply_list(exec, size, span)?;
+            }
+
+            // Binary operations
+            Instruction::Add(span) => {
+                apply_add(exec, span)?;
+            }
+            Instruction::Sub(span) => {
+                apply_sub(exec, span)?;
+            }
+            Instruction::Mult(span) => {
+                apply_mult(exec, span)?;
+            }
+            Instruction::Div(span) => {
+                apply_div(exec, span)?;
+            }
+            Instruction::Modulo(span) => {
+                apply_modulo(exec, span)?;
+            }
+            Instruction::Eq => {
+                apply_eq(exec);
+            }
+            Instruction::Gt => {
+                apply_gt(exec);
+            }
+            Instruction::Lt => {
+                apply_lt(exec);
+            }
+            Instruction::Gte => {
+                apply_gte(exec);
+            }
+            Instruction::Lte => {
+                apply_lte(exec);
+            }
+
+            // Unary operations
+            Instruction::Negate(span) => {
+                apply_negate(exec, span)?;
+            }
+
+            // Bindings
+            Instruction::Load(name, span) => {
+                let entry = exec.scope_stack.lookup(&chunk.names[name], span)?;
+                exec.value_stack.push(entry.value.clone());
+            }
+            Instruction::LoadSlot(slot) => {
+                let entry = exec.scope_stack.lookup_slot(slot);
+                exec.value_stack.push(entry.value.clone());
+            }
+            Instruction::Declare(name, is_mutable, span) => {
+                apply_declaration(exec, is_mutable, chunk.names[name].clone(), span)?;
+            }
+            Instruction::Assign(name, span) => {
+                apply_assignment(exec, chunk.names[name].clone(), None, span)?;
+            }
+            Instruction::AssignSlot(slot) => {
+                let value = exec.value_stack.pop().unwrap();
+                exec.scope_stack.mutate_slot(slot, value);
+                exec.value_stack.push(ResolvedValue::Void);
+            }
+
+            // Scope
+            Instruction::PushScope => exec.scope_stack.push_scope(),
+            Instruction::PopScope => exec.scope_stack.pop_scope(),
+
+            // Control flow
+            Instruction::Jump(offset) => frame.ip = jump_target(at, offset),
+            Instruction::JumpIfFalse(offset) => {
+                if !exec.value_stack.pop().unwrap().unwrap_bool() {
+                    frame.ip = jump_target(at, offset);
+                }
+            }
+            Instruction::JumpIfNotVoid(offset) => {
+                if let Some(ResolvedValue::Void) = exec.value_stack.last() {
+                    exec.value_stack.pop();
+                } else {
+                    frame.ip = jump_target(at, offset);
+                }
+            }
+            Instruction::EnterLoop(offset) => loops.push(LoopHandler {
+                exit: jump_target(at, offset),
+                frames: depth,
+                values: exec.value_stack.len(),
+                scope_frames: exec.scope_stack.frame_depth(),
+                scopes: exec.scope_stack.scope_depth(),
+                calls: exec.call_stack.len(),
+            }),
+            Instruction::Break => {
+                let handler = loops.pop().unwrap();
+
+                frames.truncate(handler.frames);
+                frames.last_mut().unwrap().ip = handler.exit;
+
+                while exec.scope_stack.frame_depth() > handler.scope_frames {
+                    exec.scope_stack.pop_frame();
+                }
+
+                while exec.scope_stack.scope_depth() > handler.scopes {
+                    exec.scope_stack.pop_scope();
+                }
+
+                exec.value_stack.truncate(handler.values);
+                exec.call_stack.truncate(handler.calls);
+            }
+
+            // Calls
+            Instruction::Call(arg_count, name, span)
+            | Instruction::TailCall(arg_count, name, span) => {
+                let args = exec
+                    .value_stack
+                    .split_off(exec.value_stack.len() - arg_count);
+
+                let func = match exec.value_stack.pop().unwrap() {
+                    ResolvedValue::Func(func) => func,
+                    _ => unreachable!(),
+                };
+
+                let call_frame = CallFrame {
+                    declared_name: func.name.clone(),
+                    declared_at: func.span,
+                    ..CallFrame::new(chunk.names[name].clone(), span)
+                };
+
+                let is_tail = matches!(instruction, Instruction::TailCall(..));
+
+                // Builtins don't need a frame, so calling one is the same in tail position or not.
+                if let (true, TypedExpr::Block(TypedBlock::Compiled(index, _), _)) =
+                    (is_tail, func.block.as_ref())
+                {
+                    exec.scope_stack.replace_frame(func.is_closure);
+
+                    for ((param, _ty), arg) in func.params.iter().zip(args) {
+                        exec.scope_stack.insert(param.clone(), false, arg, span)?;
+                    }
+
+                    exec.push_tail_call_frame(call_frame);
+
+                    frame.chunk = *index;
+                    frame.ip = 0;
+
+                    continue;
+                }
+
+                exec.push_call_frame(call_frame)?;
+
+                match func.block.as_ref() {
+                    TypedExpr::Block(TypedBlock::Builtin(_, builtin_id, _), _) => {
+                        let builtin = builtins.get(builtin_id).unwrap();
+                        let result = builtin(&mut io, args)?;
+                        exec.check_value_size(&result, span)?;
+
+                        exec.value_stack.push(result);
+                        exec.call_stack.pop();
+                    }
+                    TypedExpr::Block(TypedBlock::Compiled(index, _), _) => {
+                        exec.scope_stack.push_frame(func.is_closure);
+
+                        for ((param, _ty), arg) in func.params.iter().zip(args) {
+                            exec.scope_stack.insert(param.clone(), false, arg, span)?;
+                        }
+
+                        frames.push(Frame {
+                            chunk: *index,
+                            ip: 0,
+                        });
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Instruction::Return => {
+                frames.pop();
+
+                if frames.is_empty() {
+                    return Ok(exec.value_stack.pop().unwrap());
+                }
+
+                exec.scope_stack.pop_frame();
+
+                exec.pop_call_frame();
+            }
+        }
+    }
+}
+
+fn jump_target(at: usize, offset: isize) -> usize {
+    at.checked_add_signed(offset).unwrap()
+}
@@ -0,0 +1,64 @@
+use moss::{parser::parse_program, test_util};
+
+fn render_errors(code: &str) -> Vec<String> {
+    let parsed = parse_program(code).unwrap();
+    let errors = test_util::analyze_program(parsed).expect_err("program has type errors.");
+
+    errors
+        .into_iter()
+        .map(|error| {
+            error
+                .display("test.moss".to_string(), code.to_string())
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn frame_shows_line_and_column() {
+    let rendered = render_errors("let foo = 1;\nlet bar = foo + true;\n");
+
+    assert_eq!(rendered.len(), 1);
+    assert!(rendered[0].contains("test.moss:2:11"));
+    assert!(rendered[0].contains("2 | let bar = foo + true;"));
+    assert!(rendered[0].contains("  |           ^^^^^^^^^^\n"));
+}
+
+#[test]
+fn frame_at_start_of_line() {
+    let rendered = render_errors("let foo = 1;\nbar;\n");
+
+    assert_eq!(rendered.len(), 1);
+    assert!(rendered[0].contains("test.moss:2:1"));
+    assert!(rendered[0].contains("  | ^^^\n"));
+}
+
+#[test]
+fn every_error_has_a_location() {
+    let code = r#"let foo: Int = "hello";
+if 7 { 1; };
+let bar = -true;
+baz(1);
+"#;
+
+    let rendered = render_errors(code);
+
+    assert_eq!(rendered.len(), 4);
+    assert!(rendered[0].contains("test.moss:1:1"));
+    assert!(rendered[1].contains("test.moss:2:4"));
+    assert!(rendered[2].contains("test.moss:3:11"));
+    assert!(rendered[3].contains("test.moss:4:1"));
+}
+
+#[test]
+fn type_annotation_errors_point_at_the_annotation() {
+    let code = "let apply = |f: Func<Int>, x: Num|: List<Int, Int> => {\n    [f(x)];\n};\n";
+
+    let rendered = render_errors(code);
+
+    assert_eq!(rendered.len(), 3);
+    assert!(rendered[0].contains("test.moss:1:17"));
+    assert!(rendered[0].contains("  |                 ^^^^^^^^^\n"));
+    assert!(rendered[1].contains("test.moss:1:31"));
+    assert!(rendered[2].contains("test.moss:1:37"));
+}
@@ -1,3 +1,6 @@
 pub mod conditions;
+pub mod diagnostics;
 pub mod functions;
+pub mod recovery;
 pub mod scope;
+pub mod slots;
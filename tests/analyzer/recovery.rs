@@ -0,0 +1,46 @@
+use moss::{parser::parse_program, test_util};
+
+#[test]
+fn reports_every_error() {
+    let code = r#"
+        let foo: Int = true;
+        let bar = "hello" - 1;
+        baz;
+    "#;
+
+    let parsed = parse_program(code).unwrap();
+    let errors = test_util::analyze_program(parsed).expect_err("program has three type errors.");
+
+    assert_eq!(errors.len(), 3);
+}
+
+#[test]
+fn unknown_does_not_cascade() {
+    let code = r#"
+        let foo = bar;
+        let baz = foo + 1;
+        let qux = (x: Int): Int => { x; };
+        qux(baz);
+    "#;
+
+    let parsed = parse_program(code).unwrap();
+    let errors = test_util::analyze_program(parsed).expect_err("bar is not in scope.");
+
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn reports_errors_inside_function_bodies() {
+    let code = r#"
+        let foo = (x: Int): Int => {
+            let y: Str = x;
+            x + true;
+        };
+        foo(false);
+    "#;
+
+    let parsed = parse_program(code).unwrap();
+    let errors = test_util::analyze_program(parsed).expect_err("program has three type errors.");
+
+    assert_eq!(errors.len(), 3);
+}
@@ -1,4 +1,4 @@
-use moss::{grammar::ProgramParser, test_util};
+use moss::{parser::parse_program, test_util};
 
 #[test]
 fn search_parent_scope_non_closure_type_error() {
@@ -8,7 +8,7 @@ fn search_parent_scope_non_closure_type_error() {
         bar();
     "#;
 
-    let parsed = ProgramParser::new().parse(code).unwrap();
+    let parsed = parse_program(code).unwrap();
 
     test_util::analyze_program(parsed).expect_err("() => {} syntax should not create a closure.");
 }
@@ -21,7 +21,7 @@ fn search_child_scope_error_type_error() {
         bar;
     "#;
 
-    let parsed = ProgramParser::new().parse(code).unwrap();
+    let parsed = parse_program(code).unwrap();
 
     test_util::analyze_program(parsed).expect_err("bar is not in scope.");
 }
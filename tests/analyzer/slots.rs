@@ -0,0 +1,88 @@
+use moss::ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedStmt};
+use moss::scopes::scope_stack::Slot;
+use moss::{parser::parse_program, test_util};
+
+fn block_stmts(block: &TypedExpr) -> &[TypedStmt] {
+    match block {
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, _), _) => stmts,
+        _ => panic!("Expected a block, found {:?}", block),
+    }
+}
+
+#[test]
+fn declarations_get_slots_in_order() {
+    let code = r#"
+        mut a = 1;
+        mut b = 2;
+        b;
+    "#;
+
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let stmts = block_stmts(&analyzed);
+
+    // Scope 0 holds builtins, so the program's own bindings start at scope 1.
+    match &stmts[1].expr {
+        TypedExpr::Declaration { slot, .. } => assert_eq!(*slot, Slot { depth: 1, index: 1 }),
+        expr => panic!("Expected a declaration, found {:?}", expr),
+    }
+
+    match &stmts[2].expr {
+        TypedExpr::Identifier(_, slot, _, _) => {
+            assert_eq!(*slot, Some(Slot { depth: 1, index: 1 }))
+        }
+        expr => panic!("Expected an identifier, found {:?}", expr),
+    }
+}
+
+#[test]
+fn params_are_slotted_within_their_frame() {
+    let code = r#"
+        mut a = 1;
+        let add = (x: Int, y: Int): Int => { y; };
+    "#;
+
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let stmts = block_stmts(&analyzed);
+
+    let func = match &stmts[1].expr {
+        TypedExpr::Declaration { expr, .. } => match expr.as_ref() {
+            TypedExpr::FuncDeclare(func, _, _) => func,
+            expr => panic!("Expected a function, found {:?}", expr),
+        },
+        expr => panic!("Expected a declaration, found {:?}", expr),
+    };
+
+    match &block_stmts(&func.block)[0].expr {
+        TypedExpr::Identifier(_, slot, _, _) => {
+            assert_eq!(*slot, Some(Slot { depth: 0, index: 1 }))
+        }
+        expr => panic!("Expected an identifier, found {:?}", expr),
+    }
+}
+
+#[test]
+fn captured_bindings_are_looked_up_by_name() {
+    let code = r#"
+        mut a = 1;
+        let get = ||: Int => { a; };
+    "#;
+
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let stmts = block_stmts(&analyzed);
+
+    let func = match &stmts[1].expr {
+        TypedExpr::Declaration { expr, .. } => match expr.as_ref() {
+            TypedExpr::FuncDeclare(func, _, _) => func,
+            expr => panic!("Expected a function, found {:?}", expr),
+        },
+        expr => panic!("Expected a declaration, found {:?}", expr),
+    };
+
+    match &block_stmts(&func.block)[0].expr {
+        TypedExpr::Identifier(_, slot, _, _) => assert_eq!(*slot, None),
+        expr => panic!("Expected an identifier, found {:?}", expr),
+    }
+}
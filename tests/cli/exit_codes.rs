@@ -0,0 +1,285 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn moss(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_moss"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn inline_code() {
+    let output = moss(&["run", "-e", "print_line(\"hello\");"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "hello\n");
+}
+
+#[test]
+fn code_from_stdin() {
+    let output = moss(&["-"], "print_line(str(1 + 2));");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "3\n");
+}
+
+#[test]
+fn each_failure_has_its_own_code() {
+    let cases: &[(&[&str], i32, &str)] = &[
+        (&["run", "--bogus", "-e", "1;"], 2, "Unknown option"),
+        (&["run", "missing.moss"], 3, "Error loading file"),
+        (&["-e", "let x = ;"], 4, "Parse Error"),
+        (&["-e", "let x: Str = 1;"], 5, "Type Error"),
+        (&["-e", "mut zero = 0; 1 / zero;"], 6, "Runtime Error"),
+        (
+            &["lint", "--deny", "unused_binding", "-e", "let x = 1;"],
+            7,
+            "Lint Error",
+        ),
+    ];
+
+    for (args, code, message) in cases {
+        let output = moss(args, "");
+
+        assert_eq!(output.status.code(), Some(*code), "{args:?}");
+        assert!(stderr(&output).starts_with(message), "{args:?}");
+        assert_eq!(stdout(&output), "", "{args:?}");
+    }
+}
+
+#[test]
+fn check_does_not_run() {
+    let output = moss(&["check", "-e", "print_line(\"hello\");"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+
+    let output = moss(&["check", "-e", "let x: Str = 1;"], "");
+
+    assert_eq!(output.status.code(), Some(5));
+}
+
+#[test]
+fn tokens_and_ast() {
+    let output = moss(&["tokens", "-e", "let x = 1;"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("1:1     keyword     let\n"));
+
+    let output = moss(&["ast", "-e", "let x = 1;"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("Declaration {"));
+}
+
+#[test]
+fn fmt_check() {
+    let output = moss(&["fmt", "-e", "let  x=1;"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "let x = 1;\n");
+
+    let output = moss(&["fmt", "--check", "-"], "let x = 1;\n");
+
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = moss(&["fmt", "--check", "-"], "let  x=1;");
+
+    assert_eq!(output.status.code(), Some(8));
+    assert_eq!(stderr(&output), "<stdin> is not formatted.\n");
+}
+
+#[test]
+fn test_summary() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("moss_tests");
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+
+    std::fs::write(
+        dir.join("pass.moss"),
+        "test \"passes\" {\n    assert(true);\n};\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("nested/fail.moss"),
+        "test \"fails\" {\n    assert_eq(1, 2);\n};\n",
+    )
+    .unwrap();
+
+    let output = moss(&["test", dir.to_str().unwrap()], "");
+    let printed = stdout(&output);
+
+    assert_eq!(output.status.code(), Some(9));
+    assert!(printed.contains("PASS passes"));
+    assert!(printed.contains("FAIL fails"));
+    assert!(printed.contains("fail.moss:2:5"));
+    assert!(printed.ends_with("1 passed; 1 failed.\n"));
+}
+
+#[test]
+fn json_errors() {
+    let output = moss(
+        &["check", "--error-format=json", "-e", "let x = 1;\nx + y;"],
+        "",
+    );
+
+    assert_eq!(output.status.code(), Some(5));
+
+    let error: serde_json::Value = serde_json::from_str(&stderr(&output)).unwrap();
+
+    assert_eq!(error["severity"], "error");
+    assert_eq!(error["code"], "E0217");
+    assert_eq!(error["message"], "Binding \"y\" not found in scope.");
+    assert_eq!(error["file"], "<inline>");
+    assert_eq!(error["range"]["bytes"]["start"], 15);
+    assert_eq!(error["range"]["start"]["line"], 2);
+    assert_eq!(error["range"]["start"]["column"], 5);
+
+    let output = moss(&["--error-format", "json", "-"], "let x = 1");
+    let error: serde_json::Value = serde_json::from_str(&stderr(&output)).unwrap();
+
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(error["code"], "E0103");
+    assert_eq!(
+        error["notes"][1]["message"],
+        "Hint: Statements must end with a `;`."
+    );
+}
+
+#[test]
+fn json_test_failures() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("moss_json_tests");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    std::fs::write(
+        dir.join("fail.moss"),
+        "test \"fails\" {\n    assert_eq(1, 2);\n};\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("broken.moss"),
+        "test \"bad\" {\n    assert(1);\n};\n",
+    )
+    .unwrap();
+
+    let output = moss(&["test", "--error-format=json", dir.to_str().unwrap()], "");
+
+    assert_eq!(output.status.code(), Some(9));
+
+    let errors: Vec<serde_json::Value> = stdout(&output)
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    let codes: Vec<_> = errors.iter().map(|error| error["code"].clone()).collect();
+
+    // Files are tested in order, so the type error comes first.
+    assert_eq!(codes.len(), 2);
+    assert!(codes[0].as_str().unwrap().starts_with("E02"));
+    assert_eq!(codes[1], "E0313");
+    assert_eq!(errors[1]["notes"][2]["range"]["start"]["line"], 2);
+}
+
+#[test]
+fn script_args_and_exit() {
+    let code = "print_line(args());\nexit(3);";
+    let output = moss(&["run", "-e", code, "first", "--second"], "");
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "[first,--second]\n");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn exit_codes_out_of_range() {
+    for code in ["exit(300);", "exit(-1);"] {
+        let output = moss(&["run", "-e", code], "");
+
+        // A runtime error, rather than whatever the code would be truncated to.
+        assert_eq!(output.status.code(), Some(6));
+        assert!(stderr(&output).contains("is out of range, as codes run from 0 to 255."));
+    }
+}
+
+#[test]
+fn profile() {
+    let folded = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("profile.folded");
+    let code = "let double = |n: Int|: Int => { n * 2; };\nprint_line(double(2));";
+
+    let output = moss(
+        &[
+            "run",
+            "--profile-folded",
+            folded.to_str().unwrap(),
+            "-e",
+            code,
+        ],
+        "",
+    );
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "4\n");
+    assert!(stderr(&output).starts_with("Function "));
+    assert!(stderr(&output).contains("\ndouble "));
+    assert!(std::fs::read_to_string(folded)
+        .unwrap()
+        .contains("<main>;double "));
+
+    let output = moss(&["run", "--profile", "--backend", "vm", "-e", code], "");
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn trace() {
+    let code = "let double = |n: Int|: Int => { n * 2; };\nprint_line(double(2));";
+
+    let output = moss(&["run", "--trace-calls", "-e", code], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "4\n");
+    // Into and out of both `double` and `print_line`.
+    assert_eq!(stderr(&output).lines().count(), 4);
+    assert!(stderr(&output).contains("PushCallFrame double"));
+
+    // Buffered steps are only logged when the program fails.
+    let output = moss(&["run", "--trace-buffer", "3", "-e", code], "");
+
+    assert_eq!(stderr(&output), "");
+
+    let output = moss(&["run", "--trace-buffer", "3", "-e", "assert(false);"], "");
+
+    assert_eq!(output.status.code(), Some(6));
+    assert_eq!(
+        stderr(&output)
+            .lines()
+            .filter(|l| l.contains("values"))
+            .count(),
+        3
+    );
+
+    let output = moss(&["run", "--trace-lines", "5-2", "-e", code], "");
+
+    assert_eq!(output.status.code(), Some(2));
+}
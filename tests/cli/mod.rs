@@ -0,0 +1 @@
+pub mod exit_codes;
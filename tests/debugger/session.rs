@@ -0,0 +1,135 @@
+use std::io::{BufReader, BufWriter};
+
+use moss::builtins::{get_builtin_func_bindings, get_builtin_funcs};
+use moss::debugger::Debugger;
+use moss::errors::runtime_error::RuntimeError;
+use moss::interpreter::{inspect_program, resolved_value::ResolvedValue};
+use moss::parser::parse_program;
+use moss::state::{exec_context::ExecContext, io_context::IoContext};
+use moss::test_util;
+
+const PROGRAM: &str = "\
+let square = |n: Int|: Int => {
+    mut result = n * n;
+    result;
+};
+
+mut total = 0;
+mut i = 0;
+loop {
+    if i == 3 { break; };
+    mut sq = square(i);
+    total = total + sq;
+    i = i + 1;
+};
+total;
+";
+
+// Runs the program under the debugger with the given commands, returning its result and everything
+// written out, program output and debugger replies alike, and whether the debugger was quit.
+fn debug(commands: &str) -> (Result<ResolvedValue, RuntimeError>, String, bool) {
+    let parsed = parse_program(PROGRAM).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut debugger = Debugger::new(String::from("test.moss"), String::from(PROGRAM));
+    let mut writer = Vec::new();
+
+    let io = IoContext {
+        reader: BufReader::new(commands.as_bytes()),
+        writer: BufWriter::new(&mut writer),
+        args: Vec::new(),
+    };
+
+    let result = inspect_program(
+        analyzed,
+        &mut ExecContext::new(),
+        io,
+        get_builtin_func_bindings(),
+        get_builtin_funcs(),
+        &mut debugger,
+    );
+
+    (
+        result,
+        String::from_utf8(writer).unwrap(),
+        debugger.has_quit(),
+    )
+}
+
+// The line numbers stopped at, in order. Each stop follows the prompt for the previous command.
+fn stops(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .filter_map(|line| line.split("Stopped at test.moss:").nth(1))
+        .collect()
+}
+
+#[test]
+fn starts_stopped_at_first_statement() {
+    let (result, output, _) = debug("c\n");
+
+    assert_eq!(result.unwrap().unwrap_int(), 5);
+    assert_eq!(stops(&output), vec!["1"]);
+}
+
+#[test]
+fn step_over_skips_calls() {
+    let (_, output, _) = debug("b 10\nc\nn\nn\nc\nd 10\nc\n");
+
+    assert_eq!(stops(&output), vec!["1", "10", "11", "12", "10"]);
+}
+
+#[test]
+fn step_into_and_out_of_calls() {
+    let (_, output, _) = debug("b 10\nc\ns\ns\no\nd 10\nc\n");
+
+    assert_eq!(stops(&output), vec!["1", "10", "2", "3", "11"]);
+}
+
+#[test]
+fn breakpoints() {
+    let (_, output, _) = debug("b 2\nc\nc\nc\nc\n");
+
+    assert!(output.contains("Breakpoint set at test.moss:2."));
+    assert_eq!(stops(&output), vec!["1", "2", "2", "2"]);
+
+    let (_, output, _) = debug("b 40\nd 2\nc\n");
+
+    assert!(output.contains("No line 40 in test.moss."));
+    assert!(output.contains("No breakpoint at test.moss:2."));
+}
+
+#[test]
+fn print_bindings() {
+    let (_, output, _) = debug("b 3\nc\nc\np n\np result\np missing\nlocals\nc\n");
+
+    assert!(output.contains("n = 1\n"));
+    assert!(output.contains("result = 1\n"));
+    assert!(output.contains("No binding for \"missing\" in scope."));
+
+    // Innermost scope first, each in order of declaration. The closure sees its caller's bindings
+    // beneath its own.
+    assert!(output.contains("result = 1\nn = 1\nsquare = Func<Int, Int>\ntotal = 0\ni = 1\n"));
+}
+
+#[test]
+fn backtrace() {
+    let (_, output, _) = debug("b 2\nc\nbt\nd 2\nc\n");
+
+    assert!(output.contains("At test.moss:2\nIn square, called from test.moss:10\n"));
+}
+
+#[test]
+fn quit() {
+    let (result, output, has_quit) = debug("n\nq\n");
+
+    assert!(matches!(result, Err(RuntimeError::Interrupted)));
+    assert!(has_quit);
+    assert_eq!(stops(&output), vec!["1", "6"]);
+
+    // Running out of commands counts as quitting.
+    let (result, _, has_quit) = debug("");
+
+    assert!(matches!(result, Err(RuntimeError::Interrupted)));
+    assert!(has_quit);
+}
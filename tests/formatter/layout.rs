@@ -0,0 +1,203 @@
+use std::fs;
+
+use moss::formatter::format_program;
+use moss::parser::parse_program;
+use moss::test_util;
+
+fn format(code: &str) -> String {
+    format_program(code).unwrap()
+}
+
+#[test]
+fn examples_are_formatted() {
+    for entry in fs::read_dir("examples").unwrap() {
+        let path = entry.unwrap().path();
+        let code = fs::read_to_string(&path).unwrap();
+
+        assert_eq!(format(&code), code, "{path:?}");
+    }
+}
+
+#[test]
+fn formatting_is_stable() {
+    let code = "mut  x=1;loop{x=x+1;if x>3{break;};};\nlet f=(a:Int,b:List<Int>):Int=>{a;};";
+    let formatted = format(code);
+
+    assert_eq!(
+        formatted,
+        "\
+mut x = 1;
+loop {
+    x = x + 1;
+    if x > 3 {
+        break;
+    };
+};
+let f = (a: Int, b: List<Int>): Int => {
+    a;
+};
+"
+    );
+    assert_eq!(format(&formatted), formatted);
+}
+
+#[test]
+fn formatting_keeps_meaning() {
+    let code = "mut n = 0; let add = |a: Int|: Int => { a + 1 * 2 - n; }; \
+                if n == 1 { 10; } else { if n == 0 { add(-5); } else { 0; }; };";
+
+    let before = test_util::analyze_program(parse_program(code).unwrap()).unwrap();
+    let after = test_util::analyze_program(parse_program(&format(code)).unwrap()).unwrap();
+
+    assert_eq!(
+        test_util::exec_program(before).unwrap().unwrap_int(),
+        test_util::exec_program(after).unwrap().unwrap_int()
+    );
+}
+
+#[test]
+fn else_if_becomes_elif() {
+    let code = "if a { 1; }\nelif b { 2; }\nelse { if c { 3; } else { 4; }; };";
+
+    assert_eq!(
+        format(code),
+        "\
+if a {
+    1;
+} elif b {
+    2;
+} elif c {
+    3;
+} else {
+    4;
+};
+"
+    );
+}
+
+#[test]
+fn else_with_comments_is_kept() {
+    let code = "if a { 1; } else {\n// Why.\nif c { 3; };\n};";
+
+    assert_eq!(
+        format(code),
+        "\
+if a {
+    1;
+} else {
+    // Why.
+    if c {
+        3;
+    };
+};
+"
+    );
+}
+
+#[test]
+fn comments_are_kept() {
+    let code = "\
+// Leading.
+
+let x = 1;   // Trailing.
+loop {
+  // Inside.
+  break;
+  // Closing.
+};
+let s = \"// not a comment\";
+print_line(str( // Inside an expression.
+  x));
+// Last.
+";
+
+    assert_eq!(
+        format(code),
+        "\
+// Leading.
+
+let x = 1; // Trailing.
+loop {
+    // Inside.
+    break;
+    // Closing.
+};
+let s = \"// not a comment\";
+print_line(str( // Inside an expression.
+    x
+));
+// Last.
+"
+    );
+}
+
+#[test]
+fn comments_in_lists_stay_with_their_items() {
+    let code = "\
+call(1, // One.
+    // Before two.
+    [2, 3], 4);
+";
+
+    let formatted = format(code);
+
+    assert_eq!(
+        formatted,
+        "\
+call(
+    1, // One.
+    // Before two.
+    [2, 3],
+    4
+);
+"
+    );
+    assert_eq!(format(&formatted), formatted);
+}
+
+#[test]
+fn comments_after_a_block_stay_outside_it() {
+    let code = "let f = ||: Int => { 1; }; // After.\nloop { break; }; // Also after.\n";
+
+    let formatted = format(code);
+
+    assert_eq!(
+        formatted,
+        "\
+let f = ||: Int => {
+    1;
+}; // After.
+loop {
+    break;
+}; // Also after.
+"
+    );
+    assert_eq!(format(&formatted), formatted);
+}
+
+#[test]
+fn long_lists_are_wrapped() {
+    let params: Vec<_> = (1..=12).map(|i| format!("param_{i}: Int")).collect();
+    let args: Vec<_> = (1..=12).map(|i| format!("argument_{i}")).collect();
+
+    let code = format!(
+        "let f = |{}|: Int => {{ 1; }}; f({});",
+        params.join(", "),
+        args.join(", ")
+    );
+
+    let expected = format!(
+        "let f = |\n    {}\n|: Int => {{\n    1;\n}};\nf(\n    {}\n);\n",
+        params.join(",\n    "),
+        args.join(",\n    ")
+    );
+
+    assert_eq!(format(&code), expected);
+}
+
+#[test]
+fn trailing_function_argument_is_not_wrapped() {
+    let code = "call(1, ||: Int => { 2; });";
+
+    assert_eq!(format(code), "call(1, ||: Int => {\n    2;\n});\n");
+}
@@ -0,0 +1,86 @@
+use moss::errors::runtime_error::RuntimeError;
+use moss::parser::parse_program;
+use moss::state::exec_context::{ArithmeticMode, ExecContext};
+use moss::test_util;
+
+#[test]
+fn division_by_zero() {
+    let parsed = parse_program("let zero = 0; 7 / zero;").unwrap();
+
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let error = test_util::exec_program(analyzed).unwrap_err();
+
+    assert!(matches!(error, RuntimeError::DivisionZero(op, 7, _) if op == "/"));
+}
+
+#[test]
+fn modulo_by_zero() {
+    let parsed = parse_program("let zero = 0; 7 % zero;").unwrap();
+
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let error = test_util::exec_program(analyzed).unwrap_err();
+
+    assert!(matches!(error, RuntimeError::DivisionZero(op, 7, _) if op == "%"));
+}
+
+#[test]
+fn addition_overflow() {
+    let code = "let big = 2147483647;\nbig + 1;";
+    let parsed = parse_program(code).unwrap();
+
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let error = test_util::exec_program(analyzed).unwrap_err();
+
+    let span = error.span().unwrap();
+    assert_eq!(&code[span.start..span.end], "big + 1");
+    assert!(matches!(
+        error,
+        RuntimeError::BinaryOpOverflow(op, 2147483647, 1, _) if op == "+"
+    ));
+}
+
+#[test]
+fn multiplication_overflow() {
+    let parsed = parse_program("let big = 2147483647; big * 2;").unwrap();
+
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let error = test_util::exec_program(analyzed).unwrap_err();
+
+    assert!(matches!(error, RuntimeError::BinaryOpOverflow(op, _, 2, _) if op == "*"));
+}
+
+#[test]
+fn negation_overflow() {
+    let parsed = parse_program("let small = -2147483647 - 1; -small;").unwrap();
+
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let error = test_util::exec_program(analyzed).unwrap_err();
+
+    assert!(matches!(error, RuntimeError::UnaryOpOverflow(..)));
+}
+
+#[test]
+fn wrapping_mode() {
+    let parsed = parse_program("let big = 2147483647; big * 2;").unwrap();
+
+    let mut exec = ExecContext::new();
+    exec.arithmetic = ArithmeticMode::Wrapping;
+
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let result = test_util::exec_program_with_context(analyzed, &mut exec).unwrap();
+
+    assert_eq!(result.unwrap_int(), -2);
+}
+
+#[test]
+fn wrapping_mode_division_by_zero() {
+    let parsed = parse_program("let zero = 0; 7 / zero;").unwrap();
+
+    let mut exec = ExecContext::new();
+    exec.arithmetic = ArithmeticMode::Wrapping;
+
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let error = test_util::exec_program_with_context(analyzed, &mut exec).unwrap_err();
+
+    assert!(matches!(error, RuntimeError::DivisionZero(..)));
+}
@@ -1,9 +1,9 @@
-use moss::grammar::ProgramParser;
+use moss::parser::parse_program;
 use moss::test_util;
 
 #[test]
 fn declaration_basic() {
-    let parsed = ProgramParser::new().parse("let foo = 2 + 5; foo;").unwrap();
+    let parsed = parse_program("let foo = 2 + 5; foo;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -13,9 +13,7 @@ fn declaration_basic() {
 
 #[test]
 fn declaration_operated_on() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = 2 + 5; foo + 3;")
-        .unwrap();
+    let parsed = parse_program("let foo = 2 + 5; foo + 3;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -25,9 +23,7 @@ fn declaration_operated_on() {
 
 #[test]
 fn declaration_function() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = ||: Int => { 5; };")
-        .unwrap();
+    let parsed = parse_program("let foo = ||: Int => { 5; };").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -0,0 +1,136 @@
+use moss::parser::parse_program;
+use moss::state::exec_context::ExecContext;
+use moss::test_util;
+
+const NESTED_DIVISION: &str = r#"let divide = |a: Int, b: Int|: Int => {
+    a / b;
+};
+
+let halve_all = |x: Int|: Int => {
+    divide(x, x - x);
+};
+
+halve_all(4);
+"#;
+
+#[test]
+fn call_stack_at_error() {
+    let parsed = parse_program(NESTED_DIVISION).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    test_util::exec_program_with_context(analyzed, &mut exec).unwrap_err();
+
+    let names: Vec<_> = exec.call_stack.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["halve_all", "divide"]);
+}
+
+#[test]
+fn backtrace_shows_call_sites() {
+    let parsed = parse_program(NESTED_DIVISION).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let error = test_util::exec_program_with_context(analyzed, &mut exec).unwrap_err();
+
+    let rendered = error
+        .display(
+            "test.moss".to_string(),
+            NESTED_DIVISION.to_string(),
+            exec.call_stack,
+        )
+        .to_string();
+
+    let divide = rendered.find("In divide, called from:").unwrap();
+    let halve_all = rendered.find("In halve_all, called from:").unwrap();
+
+    assert!(rendered.contains("test.moss:2:5"));
+    assert!(divide < halve_all);
+    assert!(rendered[divide..].contains("test.moss:6:5"));
+    assert!(rendered[halve_all..].contains("test.moss:9:1"));
+}
+
+#[test]
+fn call_stack_is_empty_after_returning() {
+    let code = r#"
+        let double = |x: Int|: Int => { x * 2; };
+        double(double(2));
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let result = test_util::exec_program_with_context(analyzed, &mut exec).unwrap();
+
+    assert_eq!(result.unwrap_int(), 8);
+    assert!(exec.call_stack.is_empty());
+}
+
+#[test]
+fn tail_calls_are_collapsed() {
+    let code = r#"
+        let countdown = |n: Int|: Int => {
+            if n == 0 {
+                1 / n;
+            } else {
+                countdown(n - 1);
+            };
+        };
+        let start = |n: Int|: Int => { countdown(n) + 1; };
+        start(5);
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    test_util::exec_program_with_context(analyzed, &mut exec).unwrap_err();
+
+    let frames: Vec<_> = exec
+        .call_stack
+        .iter()
+        .map(|f| (f.name.as_str(), f.is_tail, f.elided))
+        .collect();
+
+    assert_eq!(
+        frames,
+        vec![
+            ("start", false, 0),
+            ("countdown", false, 0),
+            ("countdown", true, 4)
+        ]
+    );
+}
+
+#[test]
+fn backtrace_as_json_notes() {
+    let parsed = parse_program(NESTED_DIVISION).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let error = test_util::exec_program_with_context(analyzed, &mut exec).unwrap_err();
+    let json = error.to_json("test.moss", NESTED_DIVISION, &exec.call_stack);
+
+    assert_eq!(json["code"], "E0303");
+    assert_eq!(json["range"]["start"]["line"], 2);
+
+    let notes: Vec<_> = json["notes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|note| {
+            (
+                note["message"].as_str().unwrap(),
+                &note["range"]["start"]["line"],
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        notes,
+        vec![
+            ("Operation: 4 / 0", &serde_json::Value::Null),
+            ("In divide, called from here.", &serde_json::json!(6)),
+            ("In halve_all, called from here.", &serde_json::json!(9)),
+        ]
+    );
+}
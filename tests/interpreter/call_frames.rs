@@ -0,0 +1,95 @@
+use moss::parser::parse_program;
+use moss::state::exec_context::ExecContext;
+use moss::test_util;
+
+#[test]
+fn nested_calls_restore_caller_bindings() {
+    let code = r#"
+        let add_one = (x: Int): Int => { x + 1; };
+        let twice = (f: Func<Int, Int>, x: Int): Int => {
+            let y = f(x);
+            f(y);
+        };
+        mut base = 10;
+        twice(add_one, base) + base;
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let result = test_util::exec_program(analyzed).unwrap();
+
+    assert_eq!(result.unwrap_int(), 22);
+}
+
+#[test]
+fn deep_call_chain() {
+    let code = r#"
+        let add_one = (x: Int): Int => { x + 1; };
+        let twice = (f: Func<Int, Int>, x: Int): Int => {
+            let y = f(x);
+            f(y);
+        };
+        let descend = |n: Int|: Int => {
+            if n == 0 {
+                0;
+            } else {
+                let below = descend(n - 1);
+                twice(add_one, below) - n;
+            };
+        };
+        descend(200) + 1;
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let result = test_util::exec_program_with_context(analyzed, &mut exec).unwrap();
+
+    // Each level adds 2 and subtracts its own depth.
+    assert_eq!(result.unwrap_int(), 400 - 200 * 201 / 2 + 1);
+    assert_eq!(exec.scope_stack.frame_depth(), 1);
+}
+
+#[test]
+fn break_restores_loop_scope() {
+    let code = r#"
+        loop {
+            let x = 1;
+            break;
+        };
+        let x = 2;
+        x;
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let result = test_util::exec_program(analyzed).unwrap();
+
+    assert_eq!(result.unwrap_int(), 2);
+}
+
+#[test]
+fn break_through_calls_restores_frames() {
+    let code = r#"
+        let identity = (x: Int): Int => { x; };
+        mut i = 0;
+        loop {
+            let stop = |n: Int|: Int => {
+                if n > 2 {
+                    break;
+                };
+                n;
+            };
+            i = stop(identity(i)) + 1;
+        };
+        let stop = i;
+        stop;
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let result = test_util::exec_program_with_context(analyzed, &mut exec).unwrap();
+
+    assert_eq!(result.unwrap_int(), 3);
+    assert_eq!(exec.scope_stack.frame_depth(), 1);
+    assert!(exec.call_stack.is_empty());
+}
@@ -1,11 +1,9 @@
-use moss::grammar::ProgramParser;
+use moss::parser::parse_program;
 use moss::test_util;
 
 #[test]
 fn if_else_basic_true() {
-    let parsed = ProgramParser::new()
-        .parse("if true { 7; } else { 8; };")
-        .unwrap();
+    let parsed = parse_program("if true { 7; } else { 8; };").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -15,9 +13,7 @@ fn if_else_basic_true() {
 
 #[test]
 fn if_else_basic_false() {
-    let parsed = ProgramParser::new()
-        .parse("if false { 7; } else { 8; };")
-        .unwrap();
+    let parsed = parse_program("if false { 7; } else { 8; };").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -27,9 +23,7 @@ fn if_else_basic_false() {
 
 #[test]
 fn if_else_declare() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = if true { 7; } else { 8; }; foo;")
-        .unwrap();
+    let parsed = parse_program("let foo = if true { 7; } else { 8; }; foo;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
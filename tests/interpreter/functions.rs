@@ -1,11 +1,9 @@
-use moss::grammar::ProgramParser;
+use moss::parser::parse_program;
 use moss::test_util;
 
 #[test]
 fn non_closure_no_params() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = (): Int => { 7; }; foo();")
-        .unwrap();
+    let parsed = parse_program("let foo = (): Int => { 7; }; foo();").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -15,9 +13,7 @@ fn non_closure_no_params() {
 
 #[test]
 fn closure_no_params() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = ||: Int => { 7; }; foo();")
-        .unwrap();
+    let parsed = parse_program("let foo = ||: Int => { 7; }; foo();").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -27,9 +23,7 @@ fn closure_no_params() {
 
 #[test]
 fn non_closure_one_param() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = (x: Int): Int => { 7; }; foo(0);")
-        .unwrap();
+    let parsed = parse_program("let foo = (x: Int): Int => { 7; }; foo(0);").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -39,9 +33,7 @@ fn non_closure_one_param() {
 
 #[test]
 fn closure_one_param() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = |x: Int|: Int => { 7; }; foo(0);")
-        .unwrap();
+    let parsed = parse_program("let foo = |x: Int|: Int => { 7; }; foo(0);").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -51,9 +43,7 @@ fn closure_one_param() {
 
 #[test]
 fn non_closure_two_params() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = (x: Int, y: Int): Int => { 7; }; foo(0, 0);")
-        .unwrap();
+    let parsed = parse_program("let foo = (x: Int, y: Int): Int => { 7; }; foo(0, 0);").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -63,9 +53,7 @@ fn non_closure_two_params() {
 
 #[test]
 fn closure_two_params() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = |x: Int, y: Int|: Int => { 7; }; foo(0, 0);")
-        .unwrap();
+    let parsed = parse_program("let foo = |x: Int, y: Int|: Int => { 7; }; foo(0, 0);").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -75,9 +63,7 @@ fn closure_two_params() {
 
 #[test]
 fn call_one_arg() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = (x: Int): Int => { x; }; foo(7);")
-        .unwrap();
+    let parsed = parse_program("let foo = (x: Int): Int => { x; }; foo(7);").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -87,9 +73,8 @@ fn call_one_arg() {
 
 #[test]
 fn call_two_args() {
-    let parsed = ProgramParser::new()
-        .parse("let add = (x: Int, y: Int): Int => { x + y; }; add(7, 8);")
-        .unwrap();
+    let parsed =
+        parse_program("let add = (x: Int, y: Int): Int => { x + y; }; add(7, 8);").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -111,7 +96,7 @@ fn call_with_composition() {
     sub(add(3, 2), 1);
     ";
 
-    let parsed = ProgramParser::new().parse(code).unwrap();
+    let parsed = parse_program(code).unwrap();
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
 
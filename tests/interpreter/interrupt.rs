@@ -0,0 +1,79 @@
+use std::thread;
+use std::time::Duration;
+
+use moss::errors::runtime_error::RuntimeError;
+use moss::parser::parse_program;
+use moss::state::exec_context::ExecContext;
+use moss::test_util;
+
+const SPIN_FOREVER: &str = r#"
+    let spin = ||: Void => {
+        mut i = 0;
+        loop {
+            i = i + 1;
+        };
+    };
+    spin();
+"#;
+
+#[test]
+fn interrupt_from_another_thread() {
+    let parsed = parse_program(SPIN_FOREVER).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let interrupt = exec.interrupt.clone();
+
+    let trigger = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        interrupt.interrupt();
+    });
+
+    let error = test_util::exec_program_with_context(analyzed, &mut exec).unwrap_err();
+    trigger.join().unwrap();
+
+    assert!(matches!(error, RuntimeError::Interrupted));
+
+    // The call stack is left as it was, to render a backtrace from.
+    let names: Vec<_> = exec.call_stack.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["spin"]);
+}
+
+#[test]
+fn interrupt_vm() {
+    let parsed = parse_program(SPIN_FOREVER).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let interrupt = exec.interrupt.clone();
+
+    let trigger = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        interrupt.interrupt();
+    });
+
+    let error = test_util::exec_program_vm_with_context(analyzed, &mut exec).unwrap_err();
+    trigger.join().unwrap();
+
+    assert!(matches!(error, RuntimeError::Interrupted));
+}
+
+#[test]
+fn interrupt_is_cleared_once_acted_on() {
+    let code = r#"
+        mut x = 1;
+        x + 1;
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    exec.interrupt.interrupt();
+
+    let error = test_util::exec_program_with_context(analyzed.clone(), &mut exec).unwrap_err();
+    assert!(matches!(error, RuntimeError::Interrupted));
+    assert!(!exec.interrupt.is_interrupted());
+
+    let result = test_util::exec_program(analyzed).unwrap();
+    assert_eq!(result.unwrap_int(), 2);
+}
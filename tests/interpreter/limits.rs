@@ -0,0 +1,145 @@
+use moss::errors::runtime_error::RuntimeError;
+use moss::interpreter::resolved_value::ResolvedValue;
+use moss::parser::parse_program;
+use moss::state::exec_context::{ExecContext, Limits};
+use moss::test_util;
+
+// Runs the program under both backends with the same limits.
+fn run_both(code: &str, limits: Limits) -> [Result<ResolvedValue, RuntimeError>; 2] {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    exec.limits = limits;
+    let interpreted = test_util::exec_program_with_context(analyzed.clone(), &mut exec);
+
+    let mut exec = ExecContext::new();
+    exec.limits = limits;
+    let compiled = test_util::exec_program_vm_with_context(analyzed, &mut exec);
+
+    [interpreted, compiled]
+}
+
+const RECURSE_FOREVER: &str = r#"
+    let deeper = |n: Int|: Int => { 1 + deeper(n + 1); };
+    deeper(0);
+"#;
+
+#[test]
+fn step_limit() {
+    let code = r#"
+        mut i = 0;
+        loop {
+            i = i + 1;
+        };
+    "#;
+    let limits = Limits {
+        max_steps: Some(10_000),
+        ..Limits::default()
+    };
+
+    for result in run_both(code, limits) {
+        assert!(matches!(
+            result,
+            Err(RuntimeError::StepLimitExceeded(10_000))
+        ));
+    }
+}
+
+#[test]
+fn stack_limit() {
+    let limits = Limits {
+        max_stack_depth: Some(1000),
+        ..Limits::default()
+    };
+
+    for result in run_both(RECURSE_FOREVER, limits) {
+        assert!(matches!(
+            result,
+            Err(RuntimeError::StackLimitExceeded(1000))
+        ));
+    }
+}
+
+#[test]
+fn call_depth_limit() {
+    let limits = Limits {
+        max_call_depth: Some(50),
+        ..Limits::default()
+    };
+
+    for result in run_both(RECURSE_FOREVER, limits) {
+        assert!(matches!(
+            result,
+            Err(RuntimeError::CallDepthExceeded(50, _))
+        ));
+    }
+}
+
+#[test]
+fn value_size_limit() {
+    let code = r#"
+        mut text = "ab";
+        loop {
+            text = text + text;
+        };
+    "#;
+    let limits = Limits {
+        max_value_size: Some(4096),
+        ..Limits::default()
+    };
+
+    for result in run_both(code, limits) {
+        assert!(matches!(
+            result,
+            Err(RuntimeError::ValueSizeExceeded(4096, _))
+        ));
+    }
+}
+
+#[test]
+fn programs_within_limits_finish() {
+    let code = r#"
+        let count = |n: Int, total: Int|: Int => {
+            if n == 0 {
+                total;
+            } else {
+                count(n - 1, total + 1);
+            };
+        };
+        count(1000, 0);
+    "#;
+    let limits = Limits {
+        max_steps: Some(100_000),
+        max_stack_depth: Some(100),
+        max_call_depth: Some(10),
+        max_value_size: Some(1024),
+    };
+
+    for result in run_both(code, limits) {
+        assert_eq!(result.unwrap().unwrap_int(), 1000);
+    }
+}
+
+#[test]
+fn long_loops_run_within_a_small_stack() {
+    let code = r#"
+        mut i = 0;
+        loop {
+            i = i + 1;
+            if i == 1000 {
+                break;
+            };
+        };
+        i;
+    "#;
+    let limits = Limits {
+        max_stack_depth: Some(100),
+        ..Limits::default()
+    };
+
+    // Statements which evaluate to `Void` leave nothing behind to count against the limit.
+    for result in run_both(code, limits) {
+        assert_eq!(result.unwrap().unwrap_int(), 1000);
+    }
+}
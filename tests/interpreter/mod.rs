@@ -1,4 +1,13 @@
+pub mod arithmetic;
 pub mod assignment;
+pub mod backtrace;
+pub mod call_frames;
 pub mod conditions;
 pub mod functions;
+pub mod interrupt;
+pub mod limits;
+pub mod process;
 pub mod scope;
+pub mod slots;
+pub mod snapshots;
+pub mod tail_calls;
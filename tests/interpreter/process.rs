@@ -0,0 +1,40 @@
+use moss::errors::runtime_error::RuntimeError;
+use moss::parser::parse_program;
+use moss::test_util;
+
+#[test]
+fn env_is_empty_when_unset() {
+    let parsed = parse_program(r#"env("MOSS_SURELY_UNSET_VARIABLE");"#).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let result = test_util::exec_program(analyzed).unwrap();
+
+    assert!(result.unwrap_list().is_empty());
+}
+
+#[test]
+fn env_holds_value_when_set() {
+    std::env::set_var("MOSS_TEST_ENV", "moss");
+
+    let parsed = parse_program(r#"env("MOSS_TEST_ENV");"#).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let result = test_util::exec_program(analyzed).unwrap();
+
+    assert_eq!(result.unwrap_list()[0].unwrap_string(), "moss");
+}
+
+#[test]
+fn exit_unwinds_through_calls() {
+    let code = r#"
+        let stop = |code: Int|: Void => { exit(code); };
+        stop(3);
+        assert(false);
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let error = test_util::exec_program(analyzed.clone()).unwrap_err();
+    assert!(matches!(error, RuntimeError::Exit(3)));
+
+    let error = test_util::exec_program_vm(analyzed).unwrap_err();
+    assert!(matches!(error, RuntimeError::Exit(3)));
+}
@@ -1,4 +1,4 @@
-use moss::{grammar::ProgramParser, test_util};
+use moss::{parser::parse_program, test_util};
 
 #[test]
 fn search_parent_scope() {
@@ -8,7 +8,7 @@ fn search_parent_scope() {
         bar();
     "#;
 
-    let parsed = ProgramParser::new().parse(code).unwrap();
+    let parsed = parse_program(code).unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -0,0 +1,61 @@
+use moss::parser::parse_program;
+use moss::test_util;
+
+fn run_both(code: &str) -> (i32, i32) {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let interpreted = test_util::exec_program(analyzed.clone()).unwrap();
+    let compiled = test_util::exec_program_vm(analyzed).unwrap();
+
+    (interpreted.unwrap_int(), compiled.unwrap_int())
+}
+
+#[test]
+fn shadowing_in_branches() {
+    let code = r#"
+        mut x = 1;
+        mut y = 0;
+        if x == 1 {
+            let x = 10;
+            y = x;
+        };
+        x + y;
+    "#;
+
+    assert_eq!(run_both(code), (11, 11));
+}
+
+#[test]
+fn assignment_in_loop_body() {
+    let code = r#"
+        mut total = 0;
+        mut i = 0;
+        loop {
+            let step = 2;
+            total = total + step;
+            i = i + 1;
+            if i == 5 {
+                break;
+            };
+        };
+        total;
+    "#;
+
+    assert_eq!(run_both(code), (10, 10));
+}
+
+#[test]
+fn closures_see_caller_bindings() {
+    let code = r#"
+        mut a = 1;
+        let get = ||: Int => { a; };
+        let call = |n: Int|: Int => {
+            let a = n * 2;
+            get();
+        };
+        call(4) + a;
+    "#;
+
+    assert_eq!(run_both(code), (9, 9));
+}
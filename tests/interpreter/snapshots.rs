@@ -0,0 +1,108 @@
+use std::io::{BufReader, BufWriter};
+
+use moss::builtins::{get_builtin_func_bindings, get_builtin_funcs};
+use moss::errors::snapshot_error::SnapshotError;
+use moss::interpreter::{load_program, resolved_value::ResolvedValue, run_for, RunState};
+use moss::parser::parse_program;
+use moss::state::{exec_context::ExecContext, io_context::IoContext};
+use moss::test_util;
+
+const COUNT_UP: &str = r#"
+    let square = |n: Int|: Int => { n * n; };
+
+    mut total = 0;
+    mut i = 0;
+    loop {
+        if i == 10 { break; };
+        print_line(str(i));
+        total = total + square(i);
+        i = i + 1;
+    };
+    total;
+"#;
+
+fn load(code: &str) -> ExecContext {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    load_program(analyzed, &mut exec, get_builtin_func_bindings()).unwrap();
+
+    exec
+}
+
+fn output() -> IoContext<&'static [u8], Vec<u8>> {
+    IoContext {
+        reader: BufReader::new(&[]),
+        writer: BufWriter::new(Vec::new()),
+        args: Vec::new(),
+    }
+}
+
+fn expected_output() -> String {
+    (0..10).map(|i| format!("{i}\n")).collect()
+}
+
+#[test]
+fn run_in_slices() {
+    let mut exec = load(COUNT_UP);
+    let mut io = output();
+    let builtins = get_builtin_funcs();
+
+    let mut slices = 0;
+    let value = loop {
+        match run_for(&mut exec, &mut io, &builtins, 25).unwrap() {
+            RunState::Finished(value) => break value,
+            RunState::Suspended => slices += 1,
+        }
+    };
+
+    assert!(slices > 10);
+    assert!(matches!(value, ResolvedValue::Int(285)));
+    assert_eq!(
+        String::from_utf8(io.writer.into_inner().unwrap()).unwrap(),
+        expected_output()
+    );
+}
+
+#[test]
+fn resume_from_snapshot() {
+    let mut exec = load(COUNT_UP);
+    let mut io = output();
+    let builtins = get_builtin_funcs();
+
+    let state = run_for(&mut exec, &mut io, &builtins, 200).unwrap();
+    assert!(matches!(state, RunState::Suspended));
+
+    let printed_before = String::from_utf8(io.writer.into_inner().unwrap()).unwrap();
+    assert!(!printed_before.is_empty());
+
+    let bytes = exec.save().unwrap();
+    drop(exec);
+
+    let mut exec = ExecContext::restore(&bytes).unwrap();
+    let mut io = output();
+
+    let value = match run_for(&mut exec, &mut io, &builtins, u64::MAX).unwrap() {
+        RunState::Finished(value) => value,
+        RunState::Suspended => panic!("expected the program to finish"),
+    };
+
+    let printed_after = String::from_utf8(io.writer.into_inner().unwrap()).unwrap();
+
+    assert!(matches!(value, ResolvedValue::Int(285)));
+    assert_eq!(printed_before + &printed_after, expected_output());
+}
+
+#[test]
+fn restore_rejects_foreign_bytes() {
+    let error = ExecContext::restore(b"not a snapshot").err().unwrap();
+    assert!(matches!(error, SnapshotError::Incompatible));
+
+    let mut bytes = load(COUNT_UP).save().unwrap();
+    bytes.truncate(bytes.len() / 2);
+
+    let error = ExecContext::restore(&bytes).err().unwrap();
+    assert!(matches!(error, SnapshotError::Decoding(_)));
+    assert!(error.to_string().starts_with("Failed to decode snapshot: "));
+}
@@ -0,0 +1,169 @@
+use moss::ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr};
+use moss::parser::parse_program;
+use moss::state::exec_context::{ExecContext, Limits};
+use moss::test_util;
+
+fn run_both(code: &str) -> (i32, i32) {
+    run_both_with_limits(code, Limits::default())
+}
+
+fn run_both_with_limits(code: &str, limits: Limits) -> (i32, i32) {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    exec.limits = limits;
+    let interpreted = test_util::exec_program_with_context(analyzed.clone(), &mut exec).unwrap();
+
+    let mut exec = ExecContext::new();
+    exec.limits = limits;
+    let compiled = test_util::exec_program_vm_with_context(analyzed, &mut exec).unwrap();
+
+    (interpreted.unwrap_int(), compiled.unwrap_int())
+}
+
+// Collects whether each call in the expression, outside of any nested function, is a tail call.
+fn tail_flags(expr: &TypedExpr, flags: &mut Vec<bool>) {
+    match expr {
+        TypedExpr::FuncCall(call, _, _) => {
+            for arg in call.args.iter() {
+                tail_flags(arg, flags);
+            }
+            flags.push(call.is_tail);
+        }
+        TypedExpr::Sub(l, r, _, _) | TypedExpr::Add(l, r, _, _) => {
+            tail_flags(l, flags);
+            tail_flags(r, flags);
+        }
+        TypedExpr::If(_, then, _, _) => tail_flags(then, flags),
+        TypedExpr::IfElse(_, then, els, _, _) => {
+            tail_flags(then, flags);
+            tail_flags(els, flags);
+        }
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, _), _) => {
+            for stmt in stmts.iter() {
+                tail_flags(&stmt.expr, flags);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn func_body(program: &TypedExpr, index: usize) -> TypedExpr {
+    let stmts = match program {
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, _), _) => stmts,
+        _ => unreachable!(),
+    };
+
+    match &stmts[index].expr {
+        TypedExpr::Declaration { expr, .. } => match expr.as_ref() {
+            TypedExpr::FuncDeclare(func, _, _) => func.block.as_ref().clone(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn marks_calls_in_tail_position() {
+    let code = r#"
+        let id = (n: Int): Int => { n; };
+        let f = |n: Int|: Int => {
+            id(n);
+            if n == 0 {
+                id(1) - id(2);
+            } else {
+                f(n - 1);
+            };
+        };
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut flags = Vec::new();
+    tail_flags(&func_body(&analyzed, 1), &mut flags);
+
+    // An `Int` call ends the block even when it isn't the last statement. Operands never do.
+    assert_eq!(flags, vec![true, false, false, true]);
+}
+
+#[test]
+fn void_calls_before_the_end_are_not_tail_calls() {
+    let code = r#"
+        let noop = (): Void => { let x = 1; };
+        let f = ||: Void => {
+            noop();
+            noop();
+        };
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut flags = Vec::new();
+    tail_flags(&func_body(&analyzed, 1), &mut flags);
+
+    assert_eq!(flags, vec![false, true]);
+}
+
+#[test]
+fn self_recursion_a_million_deep() {
+    let code = r#"
+        let count = |n: Int, total: Int|: Int => {
+            mut next = total;
+            next = next + 1;
+            if n == 0 {
+                total;
+            } else {
+                count(n - 1, next);
+            };
+        };
+        count(1000000, 0);
+    "#;
+
+    // Tail calls run in constant space, whatever their callers left on the stacks.
+    let limits = Limits {
+        max_stack_depth: Some(100),
+        ..Limits::default()
+    };
+
+    assert_eq!(run_both_with_limits(code, limits), (1000000, 1000000));
+}
+
+#[test]
+fn mutual_recursion() {
+    let code = r#"
+        mut is_odd = |n: Int|: Int => { 0; };
+        let is_even = |n: Int|: Int => {
+            if n == 0 {
+                1;
+            } else {
+                is_odd(n - 1);
+            };
+        };
+        is_odd = |n: Int|: Int => {
+            if n == 0 {
+                0;
+            } else {
+                is_even(n - 1);
+            };
+        };
+        is_even(100001);
+    "#;
+
+    assert_eq!(run_both(code), (0, 0));
+}
+
+#[test]
+fn tail_calls_keep_caller_bindings_visible_to_closures() {
+    let code = r#"
+        mut a = 1;
+        let get = ||: Int => { a; };
+        let call = |n: Int|: Int => {
+            let a = n * 2;
+            get();
+        };
+        call(4) + a;
+    "#;
+
+    assert_eq!(run_both(code), (9, 9));
+}
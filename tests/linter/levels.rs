@@ -0,0 +1,52 @@
+use moss::{errors::warning::Lint, linter::config::LintLevel, parser::parse_program, test_util};
+
+fn lint(code: &str) -> Vec<(Lint, LintLevel)> {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    test_util::lint_program(&analyzed, code)
+        .into_iter()
+        .map(|(warning, level)| (warning.lint(), level))
+        .collect()
+}
+
+#[test]
+fn lints_warn_by_default() {
+    let warnings = lint("let foo = 1;");
+
+    assert_eq!(warnings, vec![(Lint::UnusedBinding, LintLevel::Warn)]);
+}
+
+#[test]
+fn allow_per_file() {
+    let code = "//! allow(unused_binding)\nlet foo = 1;\nlet bar = 2;\n";
+
+    assert!(lint(code).is_empty());
+}
+
+#[test]
+fn allow_per_binding() {
+    let code = "// allow(unused_binding)\nlet foo = 1;\nlet bar = 2;\n";
+
+    assert_eq!(lint(code), vec![(Lint::UnusedBinding, LintLevel::Warn)]);
+}
+
+#[test]
+fn deny_warnings_group() {
+    let code = "//! deny(warnings)\nlet foo = 1;\nmut bar = 2;\nbar;\n";
+
+    assert_eq!(
+        lint(code),
+        vec![
+            (Lint::UnusedBinding, LintLevel::Deny),
+            (Lint::UnusedMut, LintLevel::Deny),
+        ]
+    );
+}
+
+#[test]
+fn binding_directive_overrides_file_directive() {
+    let code = "//! deny(warnings)\n// allow(unused_binding)\nlet foo = 1;\nlet bar = 2;\n";
+
+    assert_eq!(lint(code), vec![(Lint::UnusedBinding, LintLevel::Deny)]);
+}
@@ -0,0 +1,86 @@
+use moss::{errors::warning::Lint, parser::parse_program, test_util};
+
+fn lint(code: &str) -> Vec<Lint> {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    test_util::lint_program(&analyzed, code)
+        .into_iter()
+        .map(|(warning, _)| warning.lint())
+        .collect()
+}
+
+#[test]
+fn unused_binding() {
+    let warnings = lint("let foo = 1; let bar = 2; bar;");
+
+    assert_eq!(warnings, vec![Lint::UnusedBinding]);
+}
+
+#[test]
+fn underscore_binding_is_not_unused() {
+    let warnings = lint("let _foo = 1;");
+
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn unused_param() {
+    let warnings = lint("let foo = (a: Int, b: Int): Int => { a; }; foo(1, 2);");
+
+    assert_eq!(warnings, vec![Lint::UnusedParam]);
+}
+
+#[test]
+fn recursive_func_is_used() {
+    let code = r#"
+        let count_down = |n: Int|: Int => {
+            if n > 0 {
+                count_down(n - 1);
+            } else {
+                n;
+            };
+        };
+        count_down(3);
+    "#;
+
+    assert!(lint(code).is_empty());
+}
+
+#[test]
+fn mut_never_reassigned() {
+    let warnings = lint("mut foo = 1; mut bar = 2; bar = foo; bar;");
+
+    assert_eq!(warnings, vec![Lint::UnusedMut]);
+}
+
+#[test]
+fn shadowed_builtin() {
+    let warnings = lint("let str = 1; str;");
+
+    assert_eq!(warnings, vec![Lint::ShadowedBuiltin]);
+}
+
+#[test]
+fn constant_comparisons() {
+    let warnings = lint("let foo = 1; 1 < 2; foo == foo; foo > foo; foo < 2;");
+
+    assert_eq!(
+        warnings,
+        vec![
+            Lint::ConstantComparison,
+            Lint::ConstantComparison,
+            Lint::ConstantComparison,
+        ]
+    );
+}
+
+#[test]
+fn empty_branches() {
+    let code = r#"
+        let foo = 1;
+        if foo > 0 { } elif foo < 0 { print_line("negative"); } else { };
+    "#;
+
+    assert_eq!(lint(code), vec![Lint::EmptyBranch, Lint::EmptyBranch]);
+}
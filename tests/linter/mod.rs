@@ -0,0 +1,2 @@
+pub mod levels;
+pub mod lints;
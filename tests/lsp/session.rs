@@ -0,0 +1,220 @@
+use std::io::BufReader;
+
+use moss::lsp::{read_message, run, write_message};
+use serde_json::{json, Value};
+
+const URI: &str = "file:///test.moss";
+
+const CODE: &str = "\
+let double = |n: Int|: Int => {
+    n * 2;
+};
+
+let total = double(21);
+print_line(str(total));
+";
+
+// Runs a session with the given messages, returning whether it was shut down properly along with
+// every message sent back.
+fn session(messages: &[Value]) -> (bool, Vec<Value>) {
+    let mut input = Vec::new();
+
+    for message in messages {
+        write_message(&mut input, message).unwrap();
+    }
+
+    let mut output = Vec::new();
+    let shut_down = run(BufReader::new(input.as_slice()), &mut output).unwrap();
+
+    let mut reader = BufReader::new(output.as_slice());
+    let mut replies = Vec::new();
+
+    while let Some(body) = read_message(&mut reader).unwrap() {
+        replies.push(serde_json::from_slice(&body).unwrap());
+    }
+
+    (shut_down, replies)
+}
+
+fn open(text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": URI, "languageId": "moss", "version": 1, "text": text } },
+    })
+}
+
+fn request(id: u64, method: &str, line: u64, character: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": {
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+        },
+    })
+}
+
+// The result of the request with the given id.
+fn result(replies: &[Value], id: u64) -> &Value {
+    &replies.iter().find(|reply| reply["id"] == id).unwrap()["result"]
+}
+
+#[test]
+fn diagnostics_follow_changes() {
+    let change = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": "let x: Int = 1;" }],
+        },
+    });
+
+    let (_, replies) = session(&[open("let x: Int = \"one\";"), change]);
+
+    let diagnostics = &replies[0]["params"]["diagnostics"];
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(
+        diagnostics[0]["message"],
+        "Cannot assign a value of type String where type Int is expected."
+    );
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 0, "character": 0 })
+    );
+
+    assert_eq!(replies[1]["params"]["diagnostics"], json!([]));
+}
+
+#[test]
+fn parse_errors_are_diagnosed() {
+    let (_, replies) = session(&[open("let x = 1;\nlet y = ;")]);
+
+    let diagnostic = &replies[0]["params"]["diagnostics"][0];
+    assert!(diagnostic["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Unexpected token `;`.\nExpected: "));
+    assert_eq!(
+        diagnostic["range"]["start"],
+        json!({ "line": 1, "character": 8 })
+    );
+}
+
+#[test]
+fn hover_shows_types() {
+    let (_, replies) = session(&[
+        open(CODE),
+        request(1, "textDocument/hover", 5, 17),
+        request(2, "textDocument/hover", 0, 5),
+        request(3, "textDocument/hover", 1, 4),
+        request(4, "textDocument/hover", 2, 0),
+    ]);
+
+    assert_eq!(
+        result(&replies, 1)["contents"]["value"],
+        "```moss\ntotal: Int\n```"
+    );
+    assert_eq!(
+        result(&replies, 1)["range"],
+        json!({ "start": { "line": 5, "character": 15 }, "end": { "line": 5, "character": 20 } })
+    );
+    assert_eq!(
+        result(&replies, 2)["contents"]["value"],
+        "```moss\ndouble: Func<Int, Int>\n```"
+    );
+    assert_eq!(
+        result(&replies, 3)["contents"]["value"],
+        "```moss\nn: Int\n```"
+    );
+    assert_eq!(*result(&replies, 4), Value::Null);
+}
+
+#[test]
+fn definitions_of_bindings_and_params() {
+    let (_, replies) = session(&[
+        open(CODE),
+        request(1, "textDocument/definition", 4, 13),
+        request(2, "textDocument/definition", 1, 4),
+        request(3, "textDocument/definition", 5, 0),
+    ]);
+
+    assert_eq!(
+        result(&replies, 1),
+        &json!({
+            "uri": URI,
+            "range": { "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 10 } },
+        })
+    );
+    assert_eq!(
+        result(&replies, 2)["range"],
+        json!({ "start": { "line": 0, "character": 14 }, "end": { "line": 0, "character": 15 } })
+    );
+
+    // Builtins aren't declared anywhere.
+    assert_eq!(*result(&replies, 3), Value::Null);
+}
+
+#[test]
+fn completion_offers_names_in_scope() {
+    let code = "\
+let outer = 1;
+let f = (a: Int): Int => {
+    a;
+};
+let g = |b: Int|: Int => {
+    b;
+};
+";
+
+    let (_, replies) = session(&[
+        open(code),
+        request(1, "textDocument/completion", 2, 4),
+        request(2, "textDocument/completion", 5, 4),
+    ]);
+
+    let labels = |id| -> Vec<String> {
+        result(&replies, id)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // A function which isn't a closure can't see anything outside of it.
+    assert_eq!(labels(1), vec!["a"]);
+
+    let in_closure = labels(2);
+    assert_eq!(in_closure[..4], ["b", "g", "f", "outer"]);
+
+    for builtin in ["int", "print_line", "push", "read_line", "str"] {
+        assert!(in_closure.iter().any(|label| label == builtin), "{builtin}");
+    }
+
+    let item = &result(&replies, 2)[0];
+    assert_eq!(item["kind"], 6);
+    assert_eq!(item["detail"], "Int");
+}
+
+#[test]
+fn shutdown_and_exit() {
+    let shutdown = json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" });
+    let exit = json!({ "jsonrpc": "2.0", "method": "exit" });
+    let unknown = json!({ "jsonrpc": "2.0", "id": 2, "method": "workspace/symbol" });
+
+    let (shut_down, replies) = session(&[unknown, shutdown, exit.clone()]);
+
+    assert!(shut_down);
+    assert_eq!(replies[0]["error"]["code"], -32601);
+    assert_eq!(
+        replies[1],
+        json!({ "jsonrpc": "2.0", "id": 1, "result": null })
+    );
+
+    let (shut_down, _) = session(&[exit]);
+
+    assert!(!shut_down);
+}
@@ -1,12 +1,27 @@
-use moss::grammar::ProgramParser;
+// The operator tests compare booleans explicitly, to read the same as the others.
+#![allow(clippy::bool_assert_comparison)]
+
+use moss::parser::parse_program;
 use moss::test_util;
 
 mod analyzer;
+mod cli;
+mod debugger;
+mod formatter;
 mod interpreter;
+mod linter;
+mod lsp;
+mod optimizer;
+mod parser;
+mod profiler;
+mod repl;
+mod test_runner;
+mod tracer;
+mod vm;
 
 #[test]
 fn operation_precedence() {
-    let parsed = ProgramParser::new().parse("10 + 5 * 2 - 8 / 4;").unwrap();
+    let parsed = parse_program("10 + 5 * 2 - 8 / 4;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -16,9 +31,7 @@ fn operation_precedence() {
 
 #[test]
 fn operation_precedence_with_negatives() {
-    let parsed = ProgramParser::new()
-        .parse("-10 + -5 * 2 - -8 / 4;")
-        .unwrap();
+    let parsed = parse_program("-10 + -5 * 2 - -8 / 4;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -28,7 +41,7 @@ fn operation_precedence_with_negatives() {
 
 #[test]
 fn equality_comparison_true() {
-    let parsed = ProgramParser::new().parse("15 - 5 == 5 + 5;").unwrap();
+    let parsed = parse_program("15 - 5 == 5 + 5;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -38,7 +51,7 @@ fn equality_comparison_true() {
 
 #[test]
 fn equality_comparison_false() {
-    let parsed = ProgramParser::new().parse("15 + 5 == 5 + 5;").unwrap();
+    let parsed = parse_program("15 + 5 == 5 + 5;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -48,7 +61,7 @@ fn equality_comparison_false() {
 
 #[test]
 fn greater_than_comparison_true() {
-    let parsed = ProgramParser::new().parse("15 + 5 > 5 + 5;").unwrap();
+    let parsed = parse_program("15 + 5 > 5 + 5;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -58,7 +71,7 @@ fn greater_than_comparison_true() {
 
 #[test]
 fn greater_than_comparison_false() {
-    let parsed = ProgramParser::new().parse("15 - 5 > 5 + 5;").unwrap();
+    let parsed = parse_program("15 - 5 > 5 + 5;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -68,7 +81,7 @@ fn greater_than_comparison_false() {
 
 #[test]
 fn less_than_comparison_true() {
-    let parsed = ProgramParser::new().parse("10 - 5 < 5 + 5;").unwrap();
+    let parsed = parse_program("10 - 5 < 5 + 5;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -78,7 +91,7 @@ fn less_than_comparison_true() {
 
 #[test]
 fn less_than_comparison_false() {
-    let parsed = ProgramParser::new().parse("15 + 5 < 5 + 5;").unwrap();
+    let parsed = parse_program("15 + 5 < 5 + 5;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -88,7 +101,7 @@ fn less_than_comparison_false() {
 
 #[test]
 fn boolean_literal_true() {
-    let parsed = ProgramParser::new().parse("true == true;").unwrap();
+    let parsed = parse_program("true == true;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -98,7 +111,7 @@ fn boolean_literal_true() {
 
 #[test]
 fn boolean_literal_false() {
-    let parsed = ProgramParser::new().parse("true == false;").unwrap();
+    let parsed = parse_program("true == false;").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -108,9 +121,7 @@ fn boolean_literal_false() {
 
 #[test]
 fn string_concatenation() {
-    let parsed = ProgramParser::new()
-        .parse("\"hello\" + \" world\";")
-        .unwrap();
+    let parsed = parse_program("\"hello\" + \" world\";").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -120,9 +131,7 @@ fn string_concatenation() {
 
 #[test]
 fn string_concatenation_assigned() {
-    let parsed = ProgramParser::new()
-        .parse("let foo = \"hello\"; foo + \" world\";")
-        .unwrap();
+    let parsed = parse_program("let foo = \"hello\"; foo + \" world\";").unwrap();
 
     let analyzed = test_util::analyze_program(parsed).unwrap();
     let result = test_util::exec_program(analyzed).unwrap();
@@ -0,0 +1,116 @@
+use moss::{
+    ast::typed::{typed_block::TypedBlock, typed_expr::TypedExpr, TypedLiteral},
+    errors::type_error::TypeError,
+    parser::parse_program,
+    test_util,
+};
+
+fn optimize(code: &str) -> Result<Vec<TypedExpr>, Vec<TypeError>> {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    match test_util::optimize_program(analyzed)? {
+        TypedExpr::Block(TypedBlock::Interpreted(stmts, _), _) => {
+            Ok(stmts.iter().map(|stmt| stmt.expr.clone()).collect())
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn folds_literal_arithmetic() {
+    let stmts = optimize("10 + 5 * 2 - 8 / 4;").unwrap();
+
+    assert!(matches!(
+        stmts[0],
+        TypedExpr::Literal(TypedLiteral::Int(18), ..)
+    ));
+}
+
+#[test]
+fn folds_literal_comparisons() {
+    let stmts = optimize("15 - 5 == 5 + 5;").unwrap();
+
+    assert!(matches!(
+        stmts[0],
+        TypedExpr::Literal(TypedLiteral::Bool(true), ..)
+    ));
+}
+
+#[test]
+fn inlines_immutable_literal_bindings() {
+    let stmts = optimize("let foo = 2 * 3; mut bar = 1; foo + 1; bar + 1;").unwrap();
+
+    assert!(matches!(
+        stmts[2],
+        TypedExpr::Literal(TypedLiteral::Int(7), ..)
+    ));
+    assert!(matches!(stmts[3], TypedExpr::Add(..)));
+}
+
+#[test]
+fn does_not_inline_into_function_bodies() {
+    let stmts = optimize("let foo = 1; let bar = ||: Int => { foo; };").unwrap();
+
+    let TypedExpr::Declaration { expr, .. } = &stmts[1] else {
+        unreachable!();
+    };
+    let TypedExpr::FuncDeclare(func, ..) = expr.as_ref() else {
+        unreachable!();
+    };
+    let TypedExpr::Block(TypedBlock::Interpreted(body, _), _) = func.block.as_ref() else {
+        unreachable!();
+    };
+
+    assert!(matches!(body[0].expr, TypedExpr::Identifier(..)));
+}
+
+#[test]
+fn eliminates_dead_branches() {
+    let code = r#"
+        let debug = false;
+        if debug { print_line("debug"); } else { print_line("release"); };
+        if true { 2; };
+    "#;
+    let stmts = optimize(code).unwrap();
+
+    assert!(matches!(stmts[1], TypedExpr::Block(..)));
+    assert!(matches!(stmts[2], TypedExpr::Block(..)));
+}
+
+#[test]
+fn folded_program_runs_the_same() {
+    let code = r#"
+        let limit = 3 * 4;
+        mut count = 0;
+        loop {
+            if count >= limit { break; };
+            count = count + 1;
+        };
+        count;
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let optimized = test_util::optimize_program(analyzed).unwrap();
+    let result = test_util::exec_program(optimized).unwrap();
+
+    assert_eq!(result.unwrap_int(), 12);
+}
+
+#[test]
+fn division_by_zero_while_folding() {
+    let errors = optimize("let zero = 1 - 1; 7 / zero;").unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], TypeError::DivisionZero(_)));
+}
+
+#[test]
+fn overflow_while_folding() {
+    let errors =
+        optimize("let big = 2147483647; big + 1; let small = -big - 1; -small;").unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(&errors[0], TypeError::BinaryOpOverflow(op, _, 1, _) if op == "+"));
+    assert!(matches!(&errors[1], TypeError::UnaryOpOverflow(op, _, _) if op == "-"));
+}
@@ -0,0 +1,75 @@
+use moss::parser::parse_program;
+
+fn render_errors(code: &str) -> Vec<String> {
+    let errors = parse_program(code).expect_err("program has syntax errors.");
+
+    errors
+        .into_iter()
+        .map(|error| {
+            error
+                .display("test.moss".to_string(), code.to_string())
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn reports_every_syntax_error() {
+    let code = "let foo = 5\nlet bar = 3;\nlet = 4;\nlet baz = 7;\nlet = 5;\n";
+    let rendered = render_errors(code);
+
+    assert_eq!(rendered.len(), 3);
+    assert!(rendered[0].contains("test.moss:2:1"));
+    assert!(rendered[1].contains("test.moss:3:5"));
+    assert!(rendered[2].contains("test.moss:5:5"));
+}
+
+#[test]
+fn recovers_between_statements_in_blocks() {
+    let code = "\
+let f = ||: Int => {
+    let a = 1
+    if a > 0 {
+        a;
+    } else {
+        0;
+    };
+};
+let = 4;
+";
+    let rendered = render_errors(code);
+
+    assert_eq!(rendered.len(), 2);
+    assert!(rendered[0].contains("test.moss:3:5"));
+    assert!(rendered[1].contains("test.moss:9:5"));
+}
+
+#[test]
+fn expected_tokens_in_plain_words() {
+    let rendered = render_errors("let = 4;\n");
+
+    assert_eq!(rendered.len(), 1);
+    assert!(rendered[0].starts_with("Unexpected token `=`."));
+    assert!(rendered[0].contains("1 | let = 4;"));
+    assert!(rendered[0].contains("  |     ^\n"));
+    assert!(rendered[0].contains("Expected: an identifier\n"));
+}
+
+#[test]
+fn hint_for_missing_semicolon_after_block() {
+    let code = "if true {\n    1;\n}\nlet foo = 7;\n";
+    let rendered = render_errors(code);
+
+    assert_eq!(rendered.len(), 1);
+    assert!(rendered[0].contains("test.moss:4:1"));
+    assert!(rendered[0].contains("need a `;` after the closing `}`"));
+}
+
+#[test]
+fn unexpected_end_of_file() {
+    let rendered = render_errors("let foo = (): Int => {\n    1;\n");
+
+    assert_eq!(rendered.len(), 1);
+    assert!(rendered[0].starts_with("Unexpected end of file."));
+    assert!(rendered[0].contains("Expected: "));
+}
@@ -0,0 +1,63 @@
+use moss::parser::tokens::{tokenize, TokenKind};
+
+fn tokens(code: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(code)
+        .unwrap()
+        .into_iter()
+        .map(|(kind, span)| (kind, &code[span.start..span.end]))
+        .collect()
+}
+
+#[test]
+fn kinds() {
+    use TokenKind::*;
+
+    assert_eq!(
+        tokens("let x = [1, 2.5, \"a b\", true];"),
+        vec![
+            (Keyword, "let"),
+            (Identifier, "x"),
+            (Symbol, "="),
+            (Symbol, "["),
+            (Int, "1"),
+            (Symbol, ","),
+            (Float, "2.5"),
+            (Symbol, ","),
+            (Str, "\"a b\""),
+            (Symbol, ","),
+            (Bool, "true"),
+            (Symbol, "]"),
+            (Symbol, ";"),
+        ]
+    );
+}
+
+#[test]
+fn longest_match_wins() {
+    use TokenKind::*;
+
+    assert_eq!(
+        tokens("letter >= => iffy"),
+        vec![
+            (Identifier, "letter"),
+            (Symbol, ">="),
+            (Symbol, "=>"),
+            (Identifier, "iffy"),
+        ]
+    );
+}
+
+#[test]
+fn skips_whitespace_and_comments() {
+    let tokens = tokens("// leading\n  loop { break; }; // trailing");
+    let texts: Vec<_> = tokens.iter().map(|(_, text)| *text).collect();
+
+    assert_eq!(texts, vec!["loop", "{", "break", ";", "}", ";"]);
+}
+
+#[test]
+fn invalid_token() {
+    let error = tokenize("let x = #;").unwrap_err();
+
+    assert_eq!(error.span().start, 8);
+}
@@ -0,0 +1,151 @@
+use std::io::{self, BufReader, BufWriter};
+
+use moss::builtins::{get_builtin_func_bindings, get_builtin_funcs};
+use moss::interpreter::inspect_program;
+use moss::parser::parse_program;
+use moss::profiler::{FunctionProfile, Profiler};
+use moss::state::{exec_context::ExecContext, io_context::IoContext};
+use moss::test_util;
+
+const PROGRAM: &str = "\
+let fib = |n: Int|: Int => {
+    if n <= 1 {
+        n;
+    } else {
+        fib(n - 1) + fib(n - 2);
+    };
+};
+
+let countdown = |n: Int|: Int => {
+    if n == 0 {
+        0;
+    } else {
+        countdown(n - 1);
+    };
+};
+
+let a = fib(10);
+let b = countdown(5);
+let c = |x: Int|: Int => { x; }(1);
+";
+
+// Profiles the program to the end, returning the profiler along with how many steps were taken.
+fn profile() -> (Profiler, u64) {
+    profile_source(PROGRAM)
+}
+
+fn profile_source(source: &str) -> (Profiler, u64) {
+    let parsed = parse_program(source).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut profiler = Profiler::new(String::from("test.moss"), String::from(source));
+    let mut exec = ExecContext::new();
+
+    let io = IoContext {
+        reader: BufReader::new(io::empty()),
+        writer: BufWriter::new(io::sink()),
+        args: Vec::new(),
+    };
+
+    inspect_program(
+        analyzed,
+        &mut exec,
+        io,
+        get_builtin_func_bindings(),
+        get_builtin_funcs(),
+        &mut profiler,
+    )
+    .unwrap();
+
+    (profiler, exec.steps)
+}
+
+fn function(profiler: &Profiler, name: &str) -> FunctionProfile {
+    profiler
+        .functions()
+        .into_iter()
+        .find(|function| function.name == name)
+        .unwrap()
+}
+
+#[test]
+fn counts_calls() {
+    let (profiler, _) = profile();
+
+    assert_eq!(function(&profiler, "<main>").calls, 1);
+    assert_eq!(function(&profiler, "fib").calls, 177);
+    // Each tail call is a call of its own, even though it takes over its caller's frame.
+    assert_eq!(function(&profiler, "countdown").calls, 6);
+}
+
+#[test]
+fn every_step_is_counted_once() {
+    let (profiler, steps) = profile();
+
+    let counted: u64 = profiler.functions().iter().map(|f| f.steps).sum();
+
+    assert_eq!(counted, steps);
+}
+
+#[test]
+fn recursion_is_timed_once() {
+    let (profiler, _) = profile();
+
+    let main = function(&profiler, "<main>");
+    let fib = function(&profiler, "fib");
+
+    assert!(fib.self_time <= fib.total_time);
+    assert!(fib.total_time <= main.total_time);
+}
+
+#[test]
+fn anonymous_functions_are_named_by_where_theyre_declared() {
+    let (profiler, _) = profile();
+
+    assert_eq!(
+        function(&profiler, "<anonymous at test.moss:19:9>").calls,
+        1
+    );
+}
+
+#[test]
+fn functions_are_named_by_their_declaration_rather_than_how_theyre_called() {
+    let (profiler, _) = profile_source(
+        "\
+let one = ||: Int => { 1; };
+let alias = one;
+let a = one() + alias();
+",
+    );
+
+    assert_eq!(function(&profiler, "one").calls, 2);
+    assert!(profiler
+        .functions()
+        .iter()
+        .all(|function| function.name != "alias"));
+}
+
+#[test]
+fn folded_stacks() {
+    let (profiler, _) = profile();
+    let folded = profiler.folded_stacks();
+
+    let stacks: Vec<_> = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+
+    assert!(stacks.contains(&"<main>"));
+    assert!(stacks.contains(&"<main>;fib;fib;fib"));
+    assert!(stacks.contains(&"<main>;countdown"));
+    assert!(stacks.contains(&"<main>;<anonymous at test.moss:19:9>"));
+
+    let steps: u64 = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum();
+
+    let counted: u64 = profiler.functions().iter().map(|f| f.steps).sum();
+
+    assert_eq!(steps, counted);
+}
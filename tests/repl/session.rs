@@ -0,0 +1,112 @@
+use std::io::{BufReader, BufWriter};
+
+use moss::errors::runtime_error::RuntimeError;
+use moss::repl::{self, Repl};
+use moss::state::io_context::IoContext;
+
+// Feeds the inputs to a fresh REPL, returning everything it wrote out besides its prompts.
+fn session(inputs: &str) -> String {
+    let mut writer = Vec::new();
+
+    let io = IoContext {
+        reader: BufReader::new(inputs.as_bytes()),
+        writer: BufWriter::new(&mut writer),
+        args: Vec::new(),
+    };
+
+    repl::run(&mut Repl::new(), io).unwrap();
+
+    String::from_utf8(writer)
+        .unwrap()
+        .replace("... ", "")
+        .replace("> ", "")
+}
+
+#[test]
+fn echoes_value_and_type() {
+    let output = session("1 + 2\n\"moss\";\ntrue\n");
+
+    assert_eq!(output, "3: Int\n\"moss\": String\ntrue: Bool\n\n");
+}
+
+#[test]
+fn every_statement_in_an_input_runs() {
+    let output = session("1; let x = 2;\nx\n");
+
+    assert_eq!(output, "1: Int\n2: Int\n\n");
+
+    let output = session("1; 2\n");
+
+    assert_eq!(output, "1: Int\n2: Int\n\n");
+}
+
+#[test]
+fn bindings_persist_between_inputs() {
+    let output =
+        session("mut x = 2;\nlet double = |n: Int|: Int => { n * 2; };\nx = double(x)\nx\n");
+
+    assert_eq!(output, "4: Int\n\n");
+}
+
+#[test]
+fn multi_line_input() {
+    let inputs = "\
+let pick = |flag: Bool|: Str => {
+    // Braces in comments } and strings \"}\" don't count.
+    if flag { \"yes\"; } else { \"no\"; };
+}
+pick(true)
+";
+    let output = session(inputs);
+
+    assert_eq!(output, "\"yes\": String\n\n");
+}
+
+#[test]
+fn type_command() {
+    let output = session("mut x = 2\n:type x + 1\n:type \"a\"\n:type let y = 1\ny\n");
+
+    assert!(output.starts_with("Int\nString\nVoid\n"));
+
+    // Nothing declared under `:type` is kept.
+    assert!(output.contains("Binding \"y\" not found in scope."));
+}
+
+#[test]
+fn reset_command() {
+    let output = session("mut x = 2\n:reset\nx\nmut x = 3\nx\n");
+
+    assert!(output.starts_with("Session reset.\nType Error: Binding \"x\" not found in scope."));
+    assert!(output.ends_with("3: Int\n\n"));
+}
+
+#[test]
+fn failed_inputs_keep_no_bindings() {
+    let output = session("mut a = 1\nmut b = 2; let c: Str = 3;\nb\nmut b = 4\nb\n");
+
+    assert!(output.starts_with("Type Error:"));
+    assert!(output.contains("Binding \"b\" not found in scope."));
+    assert!(output.ends_with("4: Int\n\n"));
+
+    // Assignments made before a runtime error are kept, but not declarations.
+    let output = session("mut a = 0\nmut b = 5; a = 7; mut d = a - 7; mut c = 10 / d;\na\nb\n");
+
+    assert!(output.starts_with("Runtime Error: Cannot divide by 0."));
+    assert!(output.contains("7: Int\nType Error: Binding \"b\" not found in scope."));
+}
+
+#[test]
+fn exit_ends_the_session() {
+    let mut writer = Vec::new();
+
+    let io = IoContext {
+        reader: BufReader::new("print_line(1);\nexit(4);\nprint_line(2);\n".as_bytes()),
+        writer: BufWriter::new(&mut writer),
+        args: Vec::new(),
+    };
+
+    let error = repl::run(&mut Repl::new(), io).unwrap_err();
+
+    assert!(matches!(error, RuntimeError::Exit(4)));
+    assert_eq!(String::from_utf8(writer).unwrap(), "> 1\n> ");
+}
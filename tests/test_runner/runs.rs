@@ -0,0 +1,138 @@
+use moss::errors::runtime_error::RuntimeError;
+use moss::test_runner::{run_tests, LoadFailure, TestOutcome};
+
+// The test's failure, rendered as it would be against its file.
+fn failure(outcome: TestOutcome, code: &str) -> String {
+    let failure = outcome.failure.unwrap();
+
+    failure
+        .error
+        .display("test.moss".into(), code.into(), failure.backtrace)
+        .to_string()
+}
+
+#[test]
+fn passing_and_failing_tests() {
+    let code = r#"let double = |x: Int|: Int => { x * 2; };
+
+test "doubles" {
+    assert_eq(double(2), 4);
+};
+
+test "doubles wrongly" {
+    assert_eq(double(2), 5);
+};
+"#;
+
+    let mut outcomes = run_tests(code).unwrap();
+    let names: Vec<_> = outcomes.iter().map(|o| o.name.as_str()).collect();
+
+    assert_eq!(names, vec!["doubles", "doubles wrongly"]);
+    assert!(outcomes[0].failure.is_none());
+
+    let failure = failure(outcomes.remove(1), code);
+
+    assert!(failure.contains("Assertion failed: values are not equal."));
+    assert!(failure.contains("Left: 4\nRight: 5"));
+    assert!(failure.contains("test.moss:8:5"));
+}
+
+#[test]
+fn tests_run_in_isolation() {
+    let code = r#"mut count = 0;
+
+test "first" {
+    count = count + 1;
+    assert(count == 1);
+};
+
+test "second" {
+    count = count + 1;
+    assert(count == 1);
+};
+"#;
+
+    let outcomes = run_tests(code).unwrap();
+
+    assert!(outcomes.iter().all(|o| o.failure.is_none()));
+}
+
+#[test]
+fn tests_only_see_what_comes_before_them() {
+    let code = r#"test "early" {
+    print_line("in test");
+};
+
+print_line("after");
+"#;
+
+    let outcomes = run_tests(code).unwrap();
+
+    assert_eq!(outcomes[0].output, "in test\n");
+}
+
+#[test]
+fn failed_assertion() {
+    let code = r#"test "false" {
+    assert(1 > 2);
+};
+"#;
+
+    let mut outcomes = run_tests(code).unwrap();
+
+    assert!(matches!(
+        outcomes[0].failure.as_ref().unwrap().error,
+        RuntimeError::AssertionFailed
+    ));
+
+    let failure = failure(outcomes.remove(0), code);
+
+    assert!(failure.starts_with("Assertion failed."));
+    assert!(failure.contains("In assert, called from:"));
+}
+
+#[test]
+fn type_errors_fail_the_file() {
+    let code = r#"test "bad" {
+    assert(1);
+};
+"#;
+
+    let failure = run_tests(code).unwrap_err();
+
+    assert!(matches!(failure, LoadFailure::Type(errors) if errors.len() == 1));
+}
+
+#[test]
+fn statements_with_values_before_a_test_dont_end_it() {
+    let code = r#"let x = 1;
+x;
+
+test "runs" {
+    assert(x == 2);
+};
+"#;
+
+    let outcomes = run_tests(code).unwrap();
+
+    assert!(outcomes[0].failure.is_some());
+}
+
+#[test]
+fn statements_with_values_in_a_test_dont_end_it() {
+    let code = r#"let add = |a: Int, b: Int|: Int => { a + b; };
+
+test "t" {
+    add(1, 2);
+    let x = 3;
+    assert_eq(add(1, 2), x + 1);
+};
+"#;
+
+    let outcomes = run_tests(code).unwrap();
+
+    assert!(matches!(
+        outcomes[0].failure.as_ref().map(|failure| &failure.error),
+        Some(RuntimeError::AssertionNotEqual(..))
+    ));
+}
@@ -0,0 +1,145 @@
+use std::io::{self, BufReader, BufWriter};
+
+use moss::builtins::{get_builtin_func_bindings, get_builtin_funcs};
+use moss::errors::runtime_error::RuntimeError;
+use moss::interpreter::inspect_program;
+use moss::parser::parse_program;
+use moss::state::{exec_context::ExecContext, io_context::IoContext};
+use moss::test_util;
+use moss::tracer::{TraceFilter, Tracer};
+
+const PROGRAM: &str = "\
+let double = |n: Int|: Int => {
+    n * 2;
+};
+
+let half = |n: Int|: Int => {
+    n / 2;
+};
+
+let a = double(3);
+let b = half(a);
+";
+
+// Traces the program to the end, returning the lines logged.
+fn trace(program: &str, filter: TraceFilter) -> Vec<String> {
+    let (result, lines) = trace_buffered(program, filter, None);
+    result.unwrap();
+    lines
+}
+
+fn trace_buffered(
+    program: &str,
+    filter: TraceFilter,
+    capacity: Option<usize>,
+) -> (Result<(), RuntimeError>, Vec<String>) {
+    let parsed = parse_program(program).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut out = Vec::new();
+    let mut tracer = Tracer::new(String::from(program), filter, &mut out, capacity);
+    let mut exec = ExecContext::new();
+
+    let io = IoContext {
+        reader: BufReader::new(io::empty()),
+        writer: BufWriter::new(io::sink()),
+        args: Vec::new(),
+    };
+
+    let result = inspect_program(
+        analyzed,
+        &mut exec,
+        io,
+        get_builtin_func_bindings(),
+        get_builtin_funcs(),
+        &mut tracer,
+    );
+
+    if result.is_err() {
+        tracer.dump().unwrap();
+    }
+
+    let lines = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+
+    (result.map(|_| ()), lines)
+}
+
+#[test]
+fn every_op_is_traced() {
+    let parsed = parse_program(PROGRAM).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+    let mut exec = ExecContext::new();
+    test_util::exec_program_with_context(analyzed, &mut exec).unwrap();
+
+    let lines = trace(PROGRAM, TraceFilter::default());
+
+    assert_eq!(lines.len() as u64, exec.steps);
+    assert!(lines[0].trim_start().starts_with("1 "));
+}
+
+#[test]
+fn calls_only() {
+    let filter = TraceFilter {
+        calls_only: true,
+        ..TraceFilter::default()
+    };
+
+    let lines = trace(PROGRAM, filter);
+
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].ends_with("PushCallFrame double"));
+    assert!(lines[1].ends_with("PopCallFrame"));
+    assert!(lines[2].ends_with("PushCallFrame half"));
+}
+
+#[test]
+fn one_function() {
+    let filter = TraceFilter {
+        function: Some(String::from("half")),
+        ..TraceFilter::default()
+    };
+
+    let lines = trace(PROGRAM, filter);
+
+    assert!(lines[0].ends_with("PushCallFrame half"));
+    assert!(lines.iter().any(|line| line.ends_with("ApplyDiv")));
+    assert!(!lines.iter().any(|line| line.ends_with("ApplyMult")));
+    // Leaving the function is the last op taken inside it.
+    assert!(lines.last().unwrap().ends_with("PopCallFrame"));
+}
+
+#[test]
+fn line_range() {
+    let filter = TraceFilter {
+        lines: Some(2..=2),
+        ..TraceFilter::default()
+    };
+
+    let lines = trace(PROGRAM, filter);
+
+    assert!(!lines.is_empty());
+    assert!(lines.iter().all(|line| line.contains(" 2:")));
+}
+
+#[test]
+fn buffer_keeps_the_latest_ops() {
+    let (result, lines) = trace_buffered(PROGRAM, TraceFilter::default(), Some(5));
+
+    // Nothing is written unless the buffer is dumped.
+    assert!(result.is_ok());
+    assert!(lines.is_empty());
+
+    let program = "let a = 1;\nlet b = 2;\nassert(false);\n";
+    let (result, lines) = trace_buffered(program, TraceFilter::default(), Some(5));
+
+    assert!(matches!(result, Err(RuntimeError::AssertionFailed)));
+    assert_eq!(lines.len(), 5);
+    // The failing call is among the latest.
+    assert!(lines
+        .iter()
+        .any(|line| line.ends_with("PushCallFrame assert")));
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::io::BufReader;
+use std::io::BufWriter;
+
+use moss::builtins::{get_builtin_func_bindings, get_builtin_funcs};
+use moss::compiler::compile_program;
+use moss::interpreter::interpret_program;
+use moss::parser::parse_program;
+use moss::state::{exec_context::ExecContext, io_context::IoContext};
+use moss::{test_util, vm};
+
+// Runs an example under both backends with the given input, returning each one's output.
+fn run_example(name: &str, input: &str) -> (String, String) {
+    let code = fs::read_to_string(format!("examples/{name}.moss")).unwrap();
+    let parsed = parse_program(&code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut interpreted = Vec::new();
+    let mut compiled = Vec::new();
+
+    interpret_program(
+        analyzed.clone(),
+        &mut ExecContext::new(),
+        IoContext {
+            reader: BufReader::new(input.as_bytes()),
+            writer: BufWriter::new(&mut interpreted),
+            args: Vec::new(),
+        },
+        get_builtin_func_bindings(),
+        get_builtin_funcs(),
+    )
+    .unwrap();
+
+    vm::run_program(
+        &compile_program(analyzed),
+        &mut ExecContext::new(),
+        IoContext {
+            reader: BufReader::new(input.as_bytes()),
+            writer: BufWriter::new(&mut compiled),
+            args: Vec::new(),
+        },
+        get_builtin_func_bindings(),
+        get_builtin_funcs(),
+    )
+    .unwrap();
+
+    (
+        String::from_utf8(interpreted).unwrap(),
+        String::from_utf8(compiled).unwrap(),
+    )
+}
+
+#[test]
+fn fibonacci() {
+    let (interpreted, compiled) = run_example("fibonacci", "");
+
+    assert_eq!(compiled, "55\n");
+    assert_eq!(interpreted, compiled);
+}
+
+#[test]
+fn fizz_buzz() {
+    let (interpreted, compiled) = run_example("fizz_buzz_pp", "");
+
+    assert_eq!(compiled, "[Fizz,1,2,Fizz,4,Buzz,Fizz,7,8,Fizz,Buzz]\n");
+    assert_eq!(interpreted, compiled);
+}
+
+#[test]
+fn high_low() {
+    let (interpreted, compiled) = run_example("high_low", "100\n50\n68\n");
+
+    assert!(compiled.ends_with("You got it!\n"));
+    assert_eq!(interpreted, compiled);
+}
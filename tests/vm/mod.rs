@@ -0,0 +1,2 @@
+pub mod examples;
+pub mod parity;
@@ -0,0 +1,123 @@
+use moss::parser::parse_program;
+use moss::state::exec_context::ExecContext;
+use moss::test_util;
+
+// Runs the program under both backends, expecting the same result.
+fn assert_same_result(code: &str) -> String {
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let interpreted = test_util::exec_program(analyzed.clone()).unwrap();
+    let compiled = test_util::exec_program_vm(analyzed).unwrap();
+
+    assert_eq!(interpreted.to_string(), compiled.to_string());
+    compiled.to_string()
+}
+
+#[test]
+fn arithmetic() {
+    let result = assert_same_result("-10 + -5 * 2 - -8 / 4 % 3;");
+    assert_eq!(result, "-18");
+}
+
+#[test]
+fn first_non_void_statement_is_block_value() {
+    let code = r#"
+        let pick = |n: Int|: Str => {
+            if n > 1 {
+                "many";
+            } elif n == 1 {
+                "one";
+            } else {
+                "none";
+            };
+        };
+        pick(0) + pick(1) + pick(2);
+    "#;
+
+    assert_eq!(assert_same_result(code), "noneonemany");
+}
+
+#[test]
+fn recursion() {
+    let code = r#"
+        let fib = |n: Int|: Int => {
+            if n <= 1 {
+                n;
+            } else {
+                fib(n - 1) + fib(n - 2);
+            };
+        };
+        fib(15);
+    "#;
+
+    assert_eq!(assert_same_result(code), "610");
+}
+
+#[test]
+fn nested_loops_with_break() {
+    let code = r#"
+        mut total = 0;
+        mut i = 0;
+        loop {
+            if i == 5 {
+                break;
+            };
+            mut j = 0;
+            loop {
+                if j == i {
+                    break;
+                };
+                total = total + j;
+                j = j + 1;
+            };
+            i = i + 1;
+        };
+        total;
+    "#;
+
+    assert_eq!(assert_same_result(code), "10");
+}
+
+#[test]
+fn break_through_calls() {
+    let code = r#"
+        let identity = (x: Int): Int => { x; };
+        mut i = 0;
+        loop {
+            let stop = |n: Int|: Int => {
+                if n > 2 {
+                    break;
+                };
+                n;
+            };
+            i = stop(identity(i)) + 1;
+        };
+        let stop = i;
+        stop;
+    "#;
+
+    assert_eq!(assert_same_result(code), "3");
+}
+
+#[test]
+fn runtime_error_backtrace() {
+    let code = r#"
+        let divide = |a: Int, b: Int|: Int => { a / b; };
+        let halve_all = |x: Int|: Int => { divide(x, x - x); };
+        halve_all(4);
+    "#;
+    let parsed = parse_program(code).unwrap();
+    let analyzed = test_util::analyze_program(parsed).unwrap();
+
+    let mut exec = ExecContext::new();
+    let error = test_util::exec_program_vm_with_context(analyzed, &mut exec).unwrap_err();
+
+    let names: Vec<_> = exec.call_stack.iter().map(|f| f.name.as_str()).collect();
+
+    assert!(matches!(
+        error,
+        moss::errors::runtime_error::RuntimeError::DivisionZero(..)
+    ));
+    assert_eq!(names, vec!["halve_all", "divide"]);
+}